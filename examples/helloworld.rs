@@ -17,5 +17,6 @@ fn main() {
 
     // Create a server that listens at [::]:9999 with up to 2048 worker threads under load if necessary
     let server: Server<_> = Server::new(2048, connection_handler);
-    server.accept("[::]:9999").expect("server failed");
+    let Err(e) = server.accept("[::]:9999");
+    panic!("server failed: {e}");
 }
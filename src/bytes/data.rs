@@ -1,9 +1,9 @@
 //! An owned, type-abstract data type
 
+use crate::error::Error;
 use std::{
     fmt::{Debug, Display, Formatter, Write},
     ops::{Deref, Range},
-    panic::UnwindSafe,
     sync::Arc,
 };
 
@@ -14,11 +14,11 @@ pub trait AnyData {
     /// `self` as implementor of `Debug`
     fn as_debug(&self) -> &dyn Debug;
     /// Clones `self`
-    fn opaque_clone(&self) -> Box<dyn AnyData + Send + UnwindSafe>;
+    fn opaque_clone(&self) -> Box<dyn AnyData + Send>;
 }
 impl<T> AnyData for T
 where
-    T: AsRef<[u8]> + Debug + Clone + Send + UnwindSafe + 'static,
+    T: AsRef<[u8]> + Debug + Clone + Send + 'static,
 {
     fn as_bytes(&self) -> &[u8] {
         self.as_ref()
@@ -26,7 +26,7 @@ where
     fn as_debug(&self) -> &dyn Debug {
         self
     }
-    fn opaque_clone(&self) -> Box<dyn AnyData + Send + UnwindSafe> {
+    fn opaque_clone(&self) -> Box<dyn AnyData + Send> {
         let clone = self.clone();
         Box::new(clone)
     }
@@ -38,9 +38,21 @@ where
 /// The idea behind this type is to provide some dynamic polymorphism, but with some "fast-paths" for common types to
 /// avoid the overhead of boxing and vtable-lookup (while the latter is probable negligible, the former may be significant
 /// overhead if all you want is to reference some static memory).
+///
+/// # Note
+/// The enum is non-exhaustive, so that new fast paths (e.g. [`Data::ArcSlice`]) can be added without a breaking change,
+/// and some variants only exist if the respective crate feature is enabled (e.g. `Data::Bytes` with `bytes`); so matches
+/// must always have a wildcard arm, which also keeps the features additive.
+///
+/// # Unwind safety
+/// The opaque variant does not require its backing to be `UnwindSafe`, so `Data` is not `UnwindSafe` either; this is a
+/// breaking change compared to earlier versions. Wrap it into `std::panic::AssertUnwindSafe` if it must cross a
+/// `catch_unwind` boundary.
+#[derive(Default)]
 #[non_exhaustive]
 pub enum Data {
     /// Some empty data
+    #[default]
     Empty,
     /// Some `Vec<u8>`-backed data
     Vec(Vec<u8>),
@@ -60,10 +72,20 @@ pub enum Data {
         /// The referenced data within the backing
         range: Range<usize>,
     },
+    /// An `Arc`ed slice to share an already shared buffer (e.g. a file that is cached at startup) without copying
+    ArcSlice {
+        /// The data backing
+        backing: Arc<[u8]>,
+        /// The referenced data within the backing
+        range: Range<usize>,
+    },
+    /// A `bytes::Bytes` buffer to interoperate with applications that are built on the `bytes` crate
+    #[cfg(feature = "bytes")]
+    Bytes(::bytes::Bytes),
     /// A catch-all/opaque variant for all types that cannot be covered by the enum's specific variants
     Other {
         /// The underlying data backing
-        data: Box<dyn AnyData + Send + UnwindSafe>,
+        data: Box<dyn AnyData + Send>,
         /// The referenced data within the backing
         range: Range<usize>,
     },
@@ -92,13 +114,36 @@ impl Data {
     /// Creates a new catch-all/opaque variant from a typed object by moving it to the heap
     pub fn from_other<T>(typed: T) -> Self
     where
-        T: AnyData + Send + UnwindSafe + 'static,
+        T: AnyData + Send + 'static,
     {
         // Box the value and init self
         let range = 0..typed.as_bytes().len();
-        let untyped: Box<dyn AnyData + Send + UnwindSafe> = Box::new(typed);
+        let untyped: Box<dyn AnyData + Send> = Box::new(typed);
         Self::Other { data: untyped, range }
     }
+
+    /// Converts `self` into an owned vector
+    ///
+    /// # Note
+    /// This function avoids copying if `self` already owns a vector, or if the backing is a uniquely referenced
+    /// `Arc<Vec<u8>>` (e.g. a parsed header after all other subcopies have been dropped).
+    pub fn into_vec(self) -> Vec<u8> {
+        match self {
+            Self::Vec(vec) => vec,
+            Self::ArcVec { backing, range } => match Arc::try_unwrap(backing) {
+                Ok(mut vec) => {
+                    // Cut the referenced range out of the backing
+                    vec.truncate(range.end);
+                    vec.drain(..range.start);
+                    vec
+                }
+                Err(backing) => backing[range].to_vec(),
+            },
+            #[cfg(feature = "bytes")]
+            Self::Bytes(bytes) => Vec::from(bytes),
+            data => data.to_vec(),
+        }
+    }
 }
 impl Deref for Data {
     type Target = [u8];
@@ -115,6 +160,9 @@ impl AsRef<[u8]> for Data {
             Self::Static(static_) => static_,
             Self::Smolbuf { buf, range } => &buf[range.start..range.end],
             Self::ArcVec { backing, range } => &backing[range.start..range.end],
+            Self::ArcSlice { backing, range } => &backing[range.start..range.end],
+            #[cfg(feature = "bytes")]
+            Self::Bytes(bytes) => bytes,
             Self::Other { data, range } => {
                 let slice = data.as_bytes();
                 &slice[range.start..range.end]
@@ -134,6 +182,11 @@ impl Debug for Data {
             Self::ArcVec { backing, range } => {
                 f.debug_struct("RcVec").field("backing", &backing).field("range", &range).finish()
             }
+            Self::ArcSlice { backing, range } => {
+                f.debug_struct("ArcSlice").field("backing", &backing).field("range", &range).finish()
+            }
+            #[cfg(feature = "bytes")]
+            Self::Bytes(arg0) => f.debug_tuple("Bytes").field(arg0).finish(),
             Self::Other { data, range } => {
                 f.debug_struct("Other").field("data", data.as_debug()).field("range", &range).finish()
             }
@@ -148,6 +201,9 @@ impl Clone for Data {
             Self::Static(arg0) => Self::Static(arg0),
             Self::Smolbuf { buf, range } => Self::Smolbuf { buf: *buf, range: range.clone() },
             Self::ArcVec { backing, range } => Self::ArcVec { backing: backing.clone(), range: range.clone() },
+            Self::ArcSlice { backing, range } => Self::ArcSlice { backing: backing.clone(), range: range.clone() },
+            #[cfg(feature = "bytes")]
+            Self::Bytes(arg0) => Self::Bytes(arg0.clone()),
             Self::Other { data, range } => Self::Other { data: data.opaque_clone(), range: range.clone() },
         }
     }
@@ -195,11 +251,6 @@ impl PartialEq<&str> for Data {
         self.eq(*other)
     }
 }
-impl Default for Data {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
 impl From<Vec<u8>> for Data {
     fn from(value: Vec<u8>) -> Self {
         Self::Vec(value)
@@ -225,3 +276,122 @@ impl From<&'static str> for Data {
         Self::Static(value.as_bytes())
     }
 }
+impl From<Arc<Vec<u8>>> for Data {
+    fn from(value: Arc<Vec<u8>>) -> Self {
+        let range = 0..value.len();
+        Self::ArcVec { backing: value, range }
+    }
+}
+impl From<Arc<[u8]>> for Data {
+    fn from(value: Arc<[u8]>) -> Self {
+        let range = 0..value.len();
+        Self::ArcSlice { backing: value, range }
+    }
+}
+impl From<Arc<str>> for Data {
+    fn from(value: Arc<str>) -> Self {
+        let backing: Arc<[u8]> = Arc::from(value);
+        Self::from(backing)
+    }
+}
+impl From<Data> for Vec<u8> {
+    fn from(value: Data) -> Self {
+        value.into_vec()
+    }
+}
+impl TryFrom<Data> for String {
+    type Error = Error;
+
+    fn try_from(value: Data) -> Result<Self, Self::Error> {
+        let string = String::from_utf8(value.into_vec()).map_err(|e| e.utf8_error())?;
+        Ok(string)
+    }
+}
+#[cfg(feature = "bytes")]
+impl From<::bytes::Bytes> for Data {
+    fn from(value: ::bytes::Bytes) -> Self {
+        Self::Bytes(value)
+    }
+}
+#[cfg(feature = "bytes")]
+impl From<Data> for ::bytes::Bytes {
+    fn from(value: Data) -> Self {
+        // Note: Shared backings are converted without copying
+        match value {
+            Data::Empty => Self::new(),
+            Data::Vec(vec) => Self::from(vec),
+            Data::Static(static_) => Self::from_static(static_),
+            Data::ArcVec { backing, range } => Self::from_owner(ArcVecOwner(backing)).slice(range),
+            Data::ArcSlice { backing, range } => Self::from_owner(backing).slice(range),
+            Data::Bytes(bytes) => bytes,
+            data => Self::copy_from_slice(&data),
+        }
+    }
+}
+
+/// An owner adapter to share an `Arc<Vec<u8>>` with `bytes::Bytes`
+#[cfg(feature = "bytes")]
+struct ArcVecOwner(Arc<Vec<u8>>);
+#[cfg(feature = "bytes")]
+impl AsRef<[u8]> for ArcVecOwner {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for Data {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        // Prefer a string representation for human-readable formats if possible
+        match std::str::from_utf8(self) {
+            Ok(string) if serializer.is_human_readable() => serializer.serialize_str(string),
+            _ => serializer.serialize_bytes(self),
+        }
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for Data {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        /// A visitor that accepts strings, bytes and byte sequences
+        struct DataVisitor;
+        impl<'de> ::serde::de::Visitor<'de> for DataVisitor {
+            type Value = Data;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a string or a byte sequence")
+            }
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(Data::from(value.to_string()))
+            }
+            fn visit_string<E>(self, value: String) -> Result<Self::Value, E> {
+                Ok(Data::from(value))
+            }
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E> {
+                Ok(Data::from(value.to_vec()))
+            }
+            fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(Data::from(value))
+            }
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: ::serde::de::SeqAccess<'de>,
+            {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(Data::from(bytes))
+            }
+        }
+        // Note: Non-self-describing formats do not support `deserialize_any`, but always use the byte representation
+        match deserializer.is_human_readable() {
+            true => deserializer.deserialize_any(DataVisitor),
+            false => deserializer.deserialize_byte_buf(DataVisitor),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! A chain of data segments that can be read as one contiguous stream
+
+use crate::bytes::{data::Data, source::Source};
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+};
+
+/// A chain of data segments that can be read as one contiguous stream without flattening the segments into one buffer
+///
+/// # Example
+/// A response body that consists of a static prefix, a dynamic middle part and a static suffix can be built without
+/// copying the static parts for every request:
+/// ```
+/// # use ehttpd::bytes::DataChain;
+/// let name = String::from("Testolope");
+/// let chain = DataChain::new().with(b"<html><body>").with(name).with(b"</body></html>");
+/// assert_eq!(chain.len(), 35);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DataChain {
+    /// The remaining segments
+    segments: VecDeque<Data>,
+    /// The read offset within the first segment
+    offset: usize,
+}
+impl DataChain {
+    /// Creates a new empty chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a segment to the chain
+    pub fn push<T>(&mut self, segment: T)
+    where
+        T: Into<Data>,
+    {
+        self.segments.push_back(segment.into());
+    }
+    /// Appends a segment to the chain and returns `self`
+    pub fn with<T>(mut self, segment: T) -> Self
+    where
+        T: Into<Data>,
+    {
+        self.push(segment);
+        self
+    }
+
+    /// The total length of the remaining data
+    pub fn len(&self) -> usize {
+        let total: usize = self.segments.iter().map(|segment| segment.len()).sum();
+        total - self.offset
+    }
+    /// Whether the chain has no remaining data
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Flattens the remaining data into a single data object
+    ///
+    /// # Note
+    /// If there is only a single segment left, the segment is returned without copying.
+    pub fn into_data(mut self) -> Data {
+        // Take the fast path if possible
+        if self.segments.len() <= 1 && self.offset == 0 {
+            return self.segments.pop_front().unwrap_or_default();
+        }
+
+        // Copy the remaining data
+        let mut flattened = Vec::with_capacity(self.len());
+        let mut segments = self.segments.iter();
+        if let Some(first) = segments.next() {
+            flattened.extend_from_slice(&first[self.offset..]);
+        }
+        segments.for_each(|segment| flattened.extend_from_slice(segment));
+        Data::Vec(flattened)
+    }
+}
+impl Read for DataChain {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(segment) = self.segments.front() {
+            // Drop exhausted segments
+            let remaining = &segment[self.offset..];
+            if remaining.is_empty() {
+                self.segments.pop_front();
+                self.offset = 0;
+                continue;
+            }
+
+            // Copy the next bytes
+            let len = remaining.len().min(buf.len());
+            buf[..len].copy_from_slice(&remaining[..len]);
+            self.offset += len;
+            return Ok(len);
+        }
+        Ok(0)
+    }
+}
+impl<T> FromIterator<T> for DataChain
+where
+    T: Into<Data>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let segments = iter.into_iter().map(Into::into).collect();
+        Self { segments, offset: 0 }
+    }
+}
+impl From<DataChain> for Source {
+    fn from(value: DataChain) -> Self {
+        Self::from_other(value)
+    }
+}
@@ -28,6 +28,9 @@ impl DataSliceExt for Data {
             Data::Static(static_) => 0..static_.len(),
             Data::Smolbuf { range, .. } => range.start..range.end,
             Data::ArcVec { range, .. } => range.start..range.end,
+            Data::ArcSlice { range, .. } => range.start..range.end,
+            #[cfg(feature = "bytes")]
+            Data::Bytes(bytes) => 0..bytes.len(),
             Data::Other { range, .. } => range.start..range.end,
         };
 
@@ -56,6 +59,9 @@ impl DataSliceExt for Data {
             Data::Vec(vec) => Data::Vec(vec[start..end].to_vec()),
             Data::Static(static_) => Data::Static(&static_[start..end]),
             Data::ArcVec { backing, .. } => Data::ArcVec { backing: backing.clone(), range: start..end },
+            Data::ArcSlice { backing, .. } => Data::ArcSlice { backing: backing.clone(), range: start..end },
+            #[cfg(feature = "bytes")]
+            Data::Bytes(bytes) => Data::Bytes(bytes.slice(start..end)),
             Data::Other { data, .. } => Data::Other { data: data.opaque_clone(), range: start..end },
             Data::Smolbuf { buf, .. } => Data::Smolbuf { buf: *buf, range: start..end },
         };
@@ -64,6 +70,10 @@ impl DataSliceExt for Data {
 }
 
 /// Some parsing extensions for `Data`
+///
+/// # Note
+/// All methods except [`Self::split_off`] and [`Self::trimmed`] have default implementations for implementors that are
+/// also byte slices with subcopies, so that existing implementors of this trait are not broken by new methods.
 pub trait DataParseExt
 where
     Self: Sized,
@@ -74,20 +84,81 @@ where
     /// # Note
     /// This method uses the cheapest way to clone the data by e.g. performing an `Rc::clone` on `Self::RcVec`
     fn split_off(&mut self, pat: &[u8]) -> Option<Self>;
+    /// Like [`Self::split_off`], but performs an ASCII-case-insensitive search
+    fn split_off_ignore_ascii_case(&mut self, pat: &[u8]) -> Option<Self>
+    where
+        Self: AsRef<[u8]> + DataSliceExt,
+    {
+        let offset = self.find_ignore_ascii_case(pat)?;
+        Some(split_at(self, offset, pat.len()))
+    }
+    /// Splits `self` on the last occurrence of `pat` and returns the suffix after the delimiter; `self` is updated to hold
+    /// the remaining prefix before `pat`
+    fn rsplit_off(&mut self, pat: &[u8]) -> Option<Self>
+    where
+        Self: AsRef<[u8]> + DataSliceExt,
+    {
+        let offset = self.rfind(pat)?;
+        let suffix = self.subcopy(offset + pat.len()..).expect("invalid suffix offset");
+        *self = self.subcopy(..offset).expect("invalid prefix offset");
+        Some(suffix)
+    }
+    /// Splits off up to `n` prefixes that are delimited by `pat` (e.g. the first elements of a comma-separated list);
+    /// `self` is updated to hold the remaining suffix after the last split
+    fn splitn_off(&mut self, n: usize, pat: &[u8]) -> Vec<Self> {
+        (0..n).map_while(|_| self.split_off(pat)).collect()
+    }
+
+    /// The offset of the first occurrence of `pat` within `self` if any
+    fn find(&self, pat: &[u8]) -> Option<usize>
+    where
+        Self: AsRef<[u8]>,
+    {
+        find(self.as_ref(), pat)
+    }
+    /// The offset of the first ASCII-case-insensitive occurrence of `pat` within `self` if any
+    fn find_ignore_ascii_case(&self, pat: &[u8]) -> Option<usize>
+    where
+        Self: AsRef<[u8]>,
+    {
+        position(self.as_ref(), pat, <[u8]>::eq_ignore_ascii_case)
+    }
+    /// The offset of the last occurrence of `pat` within `self` if any
+    fn rfind(&self, pat: &[u8]) -> Option<usize>
+    where
+        Self: AsRef<[u8]>,
+    {
+        rfind(self.as_ref(), pat)
+    }
+
     /// Trims leading and trailing ASCII whitespaces
     fn trimmed(&self) -> Self;
+
+    /// Maps all ASCII letters to lowercase, independent of the locale
+    ///
+    /// # Note
+    /// If `self` contains no uppercase letters, this returns a cheap subcopy instead of allocating.
+    fn to_lowercase(&self) -> Self
+    where
+        Self: AsRef<[u8]> + DataSliceExt + From<Vec<u8>>,
+    {
+        match self.as_ref().iter().any(u8::is_ascii_uppercase) {
+            true => Self::from(self.as_ref().to_ascii_lowercase()),
+            false => self.subcopy(..).expect("invalid segment range"),
+        }
+    }
+    /// Whether `self` is a valid HTTP token (RFC 9110, section 5.6.2), e.g. a method or a field name
+    fn is_token(&self) -> bool
+    where
+        Self: AsRef<[u8]>,
+    {
+        is_token(self.as_ref())
+    }
 }
 impl DataParseExt for Data {
     fn split_off(&mut self, pat: &[u8]) -> Option<Self> {
-        for (offset, haystack) in self.windows(pat.len()).enumerate() {
-            // Check for match
-            if haystack == pat {
-                let split = self.subcopy(..offset).expect("invalid prefix offset");
-                *self = self.subcopy(offset + pat.len()..).expect("invalid suffix offset");
-                return Some(split);
-            }
-        }
-        None
+        let offset = self.find(pat)?;
+        Some(split_at(self, offset, pat.len()))
     }
     fn trimmed(&self) -> Self {
         // Trim the leading bytes
@@ -99,3 +170,76 @@ impl DataParseExt for Data {
         trimmed.subcopy(..trimmed.len() - trailing).expect("invalid segment range")
     }
 }
+
+/// Whether `data` is a valid HTTP token
+pub(crate) fn is_token(data: &[u8]) -> bool {
+    !data.is_empty() && data.iter().all(|byte| TOKEN_CHARS[*byte as usize])
+}
+
+/// The lookup table for HTTP token characters (`tchar` in RFC 9110)
+const TOKEN_CHARS: [bool; 256] = {
+    let (mut table, mut byte) = ([false; 256], 0);
+    while byte < 256 {
+        table[byte] = matches!(byte as u8, b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z')
+            || matches!(byte as u8, b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_')
+            || matches!(byte as u8, b'`' | b'|' | b'~');
+        byte += 1;
+    }
+    table
+};
+
+/// Finds the offset of the first occurrence of `pat` using a SIMD-accelerated two-way search
+#[cfg(feature = "memchr")]
+fn find(data: &[u8], pat: &[u8]) -> Option<usize> {
+    memchr::memmem::find(data, pat)
+}
+/// Finds the offset of the first occurrence of `pat`
+#[cfg(not(feature = "memchr"))]
+fn find(data: &[u8], pat: &[u8]) -> Option<usize> {
+    // Use the first byte as prefilter to avoid comparing every window
+    let Some((first, rest)) = pat.split_first() else {
+        return Some(0);
+    };
+    let candidates = data.len().checked_sub(rest.len())?;
+    let mut offset = 0;
+    while let Some(position) = data[offset..candidates].iter().position(|byte| byte == first) {
+        offset += position;
+        if data[offset + 1..].starts_with(rest) {
+            return Some(offset);
+        }
+        offset += 1;
+    }
+    None
+}
+/// Finds the offset of the last occurrence of `pat` using a SIMD-accelerated two-way search
+#[cfg(feature = "memchr")]
+fn rfind(data: &[u8], pat: &[u8]) -> Option<usize> {
+    memchr::memmem::rfind(data, pat)
+}
+/// Finds the offset of the last occurrence of `pat`
+#[cfg(not(feature = "memchr"))]
+fn rfind(data: &[u8], pat: &[u8]) -> Option<usize> {
+    match pat.is_empty() {
+        true => Some(data.len()),
+        false => data.windows(pat.len()).rposition(|haystack| haystack == pat),
+    }
+}
+/// Finds the offset of the first window that is equal to `pat` according to `eq`
+fn position<F>(data: &[u8], pat: &[u8], eq: F) -> Option<usize>
+where
+    F: Fn(&[u8], &[u8]) -> bool,
+{
+    match pat.is_empty() {
+        true => Some(0),
+        false => data.windows(pat.len()).position(|haystack| eq(haystack, pat)),
+    }
+}
+/// Splits `data` at `offset` and returns the prefix; `data` is updated to hold the suffix after the delimiter
+fn split_at<T>(data: &mut T, offset: usize, delimiter_len: usize) -> T
+where
+    T: DataSliceExt,
+{
+    let prefix = data.subcopy(..offset).expect("invalid prefix offset");
+    *data = data.subcopy(offset + delimiter_len..).expect("invalid suffix offset");
+    prefix
+}
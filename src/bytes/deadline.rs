@@ -0,0 +1,88 @@
+//! A deadline-enforcing wrapper around a data source
+
+use crate::bytes::source::Source;
+use std::{
+    borrow::BorrowMut,
+    io::{self, ErrorKind, Read},
+    time::{Duration, Instant},
+};
+
+/// A wrapper around a `Source` (or a mutable reference to a `Source`) that enforces a deadline on every read
+///
+/// # Note
+/// The deadline is checked before every read, so a read that blocks forever on the underlying source cannot be
+/// interrupted. The exception are TCP stream sources (including buffered TCP streams, see [`Source::buffered`]), where the
+/// socket read timeout is set to the remaining time before each read.
+#[derive(Debug)]
+pub struct Deadline<T>
+where
+    T: BorrowMut<Source>,
+{
+    /// The underlying source
+    inner: T,
+    /// The deadline
+    deadline: Instant,
+    /// The original socket read timeout to restore on drop if the underlying source is a TCP stream
+    original_timeout: Option<Option<Duration>>,
+}
+impl<T> Deadline<T>
+where
+    T: BorrowMut<Source>,
+{
+    /// Wraps the given source and enforces the given deadline
+    pub fn new(inner: T, deadline: Instant) -> Self {
+        Self { inner, deadline, original_timeout: None }
+    }
+    /// Wraps the given source and enforces a deadline that expires after the given timeout
+    pub fn with_timeout(inner: T, timeout: Duration) -> Self {
+        Self::new(inner, Instant::now() + timeout)
+    }
+
+    /// The deadline
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+    /// The remaining time until the deadline or `None` if the deadline has passed
+    pub fn remaining(&self) -> Option<Duration> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        (!remaining.is_zero()).then_some(remaining)
+    }
+}
+impl<T> Read for Deadline<T>
+where
+    T: BorrowMut<Source>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Check the deadline
+        let Some(remaining) = self.remaining() else {
+            return Err(io::Error::new(ErrorKind::TimedOut, "request deadline exceeded"));
+        };
+
+        // Limit blocking reads to the remaining time if possible
+        let source = self.inner.borrow_mut();
+        if let Some(tcp_stream) = source.tcp_stream() {
+            // Backup the original timeout once
+            if self.original_timeout.is_none() {
+                self.original_timeout = Some(tcp_stream.read_timeout()?);
+            }
+            tcp_stream.set_read_timeout(Some(remaining))?;
+        }
+
+        // Read and map socket timeouts to `TimedOut`
+        match source.read(buf) {
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Err(io::Error::new(ErrorKind::TimedOut, e)),
+            result => result,
+        }
+    }
+}
+impl<T> Drop for Deadline<T>
+where
+    T: BorrowMut<Source>,
+{
+    fn drop(&mut self) {
+        // Restore the original socket read timeout
+        if let (Some(original_timeout), Some(tcp_stream)) = (self.original_timeout, self.inner.borrow().tcp_stream()) {
+            let _ = tcp_stream.set_read_timeout(original_timeout);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Decompressing adapters for sources that are stored compressed at rest
+//!
+//! # Note
+//! The decoders work on the fly with a fixed-size internal state, so the memory usage is bounded regardless of the
+//! (de)compressed size.
+
+use crate::bytes::source::Source;
+#[cfg(feature = "zstd")]
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Read};
+
+/// A source that decompresses a gzip-compressed source on the fly
+#[cfg(feature = "flate2")]
+#[derive(Debug)]
+pub struct GzipDecoder {
+    /// The underlying decoder
+    decoder: flate2::read::MultiGzDecoder<Source>,
+}
+#[cfg(feature = "flate2")]
+impl GzipDecoder {
+    /// Creates a new gzip decoder for the given compressed source
+    ///
+    /// # Note
+    /// Concatenated gzip members are decoded one after another, like `gzip -d` does.
+    pub fn new<T>(source: T) -> Self
+    where
+        T: Into<Source>,
+    {
+        let decoder = flate2::read::MultiGzDecoder::new(source.into());
+        Self { decoder }
+    }
+}
+#[cfg(feature = "flate2")]
+impl Read for GzipDecoder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.decoder.read(buf)
+    }
+}
+#[cfg(feature = "flate2")]
+impl From<GzipDecoder> for Source {
+    fn from(value: GzipDecoder) -> Self {
+        Source::from_other(value)
+    }
+}
+
+/// A source that decompresses a zstd-compressed source on the fly
+#[cfg(feature = "zstd")]
+pub struct ZstdDecoder {
+    /// The underlying decoder
+    decoder: zstd::stream::read::Decoder<'static, io::BufReader<Source>>,
+}
+#[cfg(feature = "zstd")]
+impl ZstdDecoder {
+    /// The maximum window size as power of two (8 MiB, which is the limit recommended by RFC 8878, section 3.1.1.1.2)
+    const WINDOW_LOG_MAX: u32 = 23;
+
+    /// Creates a new zstd decoder for the given compressed source
+    ///
+    /// # Note
+    /// Frames that require a window larger than 8 MiB are rejected, so that a malicious source cannot force a large
+    /// allocation.
+    pub fn new<T>(source: T) -> io::Result<Self>
+    where
+        T: Into<Source>,
+    {
+        let mut decoder = zstd::stream::read::Decoder::new(source.into())?;
+        decoder.window_log_max(Self::WINDOW_LOG_MAX)?;
+        Ok(Self { decoder })
+    }
+}
+#[cfg(feature = "zstd")]
+impl Read for ZstdDecoder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.decoder.read(buf)
+    }
+}
+#[cfg(feature = "zstd")]
+impl Debug for ZstdDecoder {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("ZstdDecoder").field("source", self.decoder.get_ref().get_ref()).finish()
+    }
+}
+#[cfg(feature = "zstd")]
+impl From<ZstdDecoder> for Source {
+    fn from(value: ZstdDecoder) -> Self {
+        Source::from_other(value)
+    }
+}
@@ -1,13 +1,29 @@
 //! Provides (mostly) stack-allocating trait implementors over different underlying sources
 
 mod data;
+mod datachain;
 mod dataext;
+mod deadline;
+#[cfg(any(feature = "flate2", feature = "zstd"))]
+mod decoder;
 mod sink;
 mod source;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod splice;
 
+pub(crate) use crate::bytes::dataext::is_token;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) use crate::bytes::splice::splice;
 pub use crate::bytes::{
     data::Data,
+    datachain::DataChain,
     dataext::{DataParseExt, DataSliceExt},
+    deadline::Deadline,
     sink::{AnySink, Sink},
-    source::{AnySource, Source},
+    source::{AnySource, ChannelSender, Source},
 };
+
+#[cfg(feature = "flate2")]
+pub use crate::bytes::decoder::GzipDecoder;
+#[cfg(feature = "zstd")]
+pub use crate::bytes::decoder::ZstdDecoder;
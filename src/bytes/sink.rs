@@ -1,11 +1,12 @@
 //! An owned, type-abstract writeable data sink
 
+#[cfg(target_family = "unix")]
+use socket2::SockRef;
 use std::{
     fmt::{Debug, Formatter},
     fs::File,
-    io::{self, Write},
-    net::TcpStream,
-    panic::UnwindSafe,
+    io::{self, ErrorKind, Write},
+    net::{SocketAddr, TcpStream},
 };
 
 /// An umbrella trait to combine `Write`, `Debug` and `Send` which are required for `Sink`
@@ -33,9 +34,14 @@ where
 /// The idea behind this type is to provide some dynamic polymorphism, but with some "fast-paths" for common types to
 /// avoid the overhead of boxing and vtable-lookup (while the latter is probable negligible, the former may be significant
 /// overhead if all you want is to write to some preallocated memory).
+///
+/// # Unwind safety
+/// `Sink::Other` does not require `UnwindSafe`, so `Sink` is not `UnwindSafe` anymore (see [`crate::bytes::Data`]).
+#[derive(Default)]
 #[non_exhaustive]
 pub enum Sink {
     /// A writer which will move data into the void
+    #[default]
     Null,
     /// A vector sink
     Vector(Vec<u8>),
@@ -44,30 +50,85 @@ pub enum Sink {
     /// A TCP stream
     TcpStream(TcpStream),
     /// A catch-all/opaque variant for all types that cannot be covered by the enum's specific variants
-    Other(Box<dyn AnySink + Send + UnwindSafe>),
+    Other(Box<dyn AnySink + Send>),
 }
 impl Sink {
     /// Creates a new catch-all/opaque variant from a typed object by moving it to the heap
     pub fn from_other<T>(typed: T) -> Self
     where
-        T: AnySink + Send + UnwindSafe + 'static,
+        T: AnySink + Send + 'static,
     {
         let boxed = Box::new(typed);
         Self::Other(boxed)
     }
-}
-impl Write for Sink {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+
+    /// The peer address if the sink is a TCP stream
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Sink::TcpStream(tcp_stream) => tcp_stream.peer_addr().ok(),
+            _ => None,
+        }
+    }
+
+    /// Probes whether the peer is still connected, so that long-running handlers can abandon their work if the client
+    /// has gone away
+    ///
+    /// # Note
+    /// The probe is a non-blocking `peek` on the underlying socket and does not consume any data. Since only TCP streams
+    /// can be probed, all other sinks are always considered alive.
+    pub fn connection_alive(&self) -> bool {
+        match self {
+            Sink::TcpStream(tcp_stream) => Self::tcp_stream_alive(tcp_stream),
+            _ => true,
+        }
+    }
+    /// Probes whether the peer of a TCP stream is still connected
+    ///
+    /// # Note
+    /// The original mode of the stream cannot be queried on Windows; it is assumed to be blocking there.
+    fn tcp_stream_alive(tcp_stream: &TcpStream) -> bool {
+        // Switch the stream into non-blocking mode for the probe
+        #[cfg(target_family = "unix")]
+        let Ok(nonblocking) = SockRef::from(tcp_stream).nonblocking() else {
+            return true;
+        };
+        #[cfg(not(target_family = "unix"))]
+        let nonblocking = false;
+        if !nonblocking && tcp_stream.set_nonblocking(true).is_err() {
+            return true;
+        }
+
+        // Peek and restore the original mode
+        let result = tcp_stream.peek(&mut [0]);
+        if !nonblocking {
+            let _ = tcp_stream.set_nonblocking(false);
+        }
+        match result {
+            Ok(0) => false,
+            Ok(_) => true,
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::Interrupted => true,
+            Err(_) => false,
+        }
+    }
+
+    /// Writes to the underlying sink without retrying
+    fn write_once(&mut self, buf: &[u8]) -> io::Result<usize> {
         match self {
             Sink::Null => io::sink().write(buf),
             Sink::Vector(vector) => vector.write(buf),
             Sink::File(file) => file.write(buf),
-            Sink::TcpStream(tcp_stream) => tcp_stream.write(buf),
+            Sink::TcpStream(tcp_stream) => match tcp_stream.write(buf) {
+                // Map an expired socket write timeout to `TimedOut` so that it is not confused with a non-blocking socket
+                Err(e) if e.kind() == ErrorKind::WouldBlock && matches!(tcp_stream.write_timeout(), Ok(Some(_))) => {
+                    Err(io::Error::new(ErrorKind::TimedOut, e))
+                }
+                result => result,
+            },
             Sink::Other(other) => other.as_write_mut().write(buf),
         }
     }
-
-    fn flush(&mut self) -> std::io::Result<()> {
+    /// Flushes the underlying sink without retrying
+    fn flush_once(&mut self) -> io::Result<()> {
         match self {
             Sink::Null => Ok(()),
             Sink::Vector(vector) => vector.flush(),
@@ -77,6 +138,27 @@ impl Write for Sink {
         }
     }
 }
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Retry interrupted writes
+        loop {
+            match self.write_once(buf) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                result => return result,
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Retry interrupted flushes
+        loop {
+            match self.flush_once() {
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                result => return result,
+            }
+        }
+    }
+}
 impl Debug for Sink {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
@@ -88,11 +170,6 @@ impl Debug for Sink {
         }
     }
 }
-impl Default for Sink {
-    fn default() -> Self {
-        Self::Null
-    }
-}
 impl From<Vec<u8>> for Sink {
     fn from(value: Vec<u8>) -> Self {
         Self::Vector(value)
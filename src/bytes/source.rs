@@ -1,12 +1,14 @@
 //! An owned, type-abstract readable data source
 
 use crate::bytes::data::Data;
+use flume::{Receiver, RecvTimeoutError, Sender};
 use std::{
+    collections::VecDeque,
     fmt::{Debug, Formatter},
     fs::File,
-    io::{Cursor, Read},
+    io::{self, BufRead, BufReader, Cursor, ErrorKind, Read},
     net::TcpStream,
-    panic::UnwindSafe,
+    time::Duration,
 };
 
 /// An umbrella trait to combine `Read`, `Debug` and `Send` which are required for `Source`
@@ -28,15 +30,123 @@ where
     }
 }
 
+/// A source that generates its data via a closure
+struct FnSource<F>(F);
+impl<F> Read for FnSource<F>
+where
+    F: FnMut(&mut [u8]) -> io::Result<usize>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (self.0)(buf)
+    }
+}
+impl<F> Debug for FnSource<F> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_tuple("FnSource").finish_non_exhaustive()
+    }
+}
+
+/// A source that reads the chunks of an iterator one after another
+struct ChunkSource<I> {
+    /// The chunk that is currently read
+    current: Cursor<Data>,
+    /// The remaining chunks
+    chunks: I,
+}
+impl<I> Read for ChunkSource<I>
+where
+    I: Iterator<Item = Data>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            // Read from the current chunk or advance to the next one
+            let read = self.current.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            let Some(chunk) = self.chunks.next() else {
+                return Ok(0);
+            };
+            self.current = Cursor::new(chunk);
+        }
+    }
+}
+impl<I> Debug for ChunkSource<I> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_struct("ChunkSource").field("current", &self.current).finish_non_exhaustive()
+    }
+}
+
+/// The sending half of a channel-backed source (see [`Source::from_channel`])
+#[derive(Debug, Clone)]
+pub struct ChannelSender(Sender<io::Result<Data>>);
+impl ChannelSender {
+    /// Sends the next chunk, blocking while the channel is full
+    ///
+    /// # Note
+    /// This function fails with `BrokenPipe` if the source has been dropped (e.g. because the client went away), so the
+    /// producer can stop early.
+    pub fn send<T>(&self, chunk: T) -> io::Result<()>
+    where
+        T: Into<Data>,
+    {
+        self.0.send(Ok(chunk.into())).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+    /// Aborts the source with the given error, so that the body is not terminated as if it was complete
+    pub fn abort(self, error: io::Error) {
+        let _ = self.0.send(Err(error));
+    }
+}
+
+/// A source that reads the chunks of a channel one after another
+#[derive(Debug)]
+struct ChannelSource {
+    /// The chunk that is currently read
+    current: Cursor<Data>,
+    /// The receiving half of the channel
+    receiver: Receiver<io::Result<Data>>,
+    /// The maximum time to wait for the next chunk if any
+    timeout: Option<Duration>,
+}
+impl Read for ChannelSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            // Read from the current chunk or wait for the next one
+            let read = self.current.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            let chunk = match self.timeout {
+                Some(timeout) => self.receiver.recv_timeout(timeout),
+                None => self.receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match chunk {
+                Ok(chunk) => self.current = Cursor::new(chunk?),
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+                Err(RecvTimeoutError::Timeout) => return Err(io::Error::new(ErrorKind::TimedOut, "No chunk received")),
+            }
+        }
+    }
+}
+
 /// An owned, type-abstract data source
 ///
 /// # Rationale
 /// The idea behind this type is to provide some dynamic polymorphism, but with some "fast-paths" for common types to
 /// avoid the overhead of boxing and vtable-lookup (while the latter is probable negligible, the former may be significant
 /// overhead if all you want is to read from some static memory).
+///
+/// # Note
+/// The enum is non-exhaustive, so that new adapters (e.g. [`Source::Limited`] or [`Source::Chain`]) can be added without
+/// a breaking change; matches must always have a wildcard arm.
+///
+/// # Unwind safety
+/// `Source::Other` does not require `UnwindSafe`, so `Source` is not `UnwindSafe` anymore (see [`crate::bytes::Data`]).
+#[derive(Default)]
 #[non_exhaustive]
 pub enum Source {
     /// An empty source
+    #[default]
     Empty,
     /// A linear data backed source
     Data(Cursor<Data>),
@@ -44,18 +154,116 @@ pub enum Source {
     File(File),
     /// A TCP stream
     TcpStream(TcpStream),
+    /// A source that reads at most a limited amount of bytes from the underlying source
+    Limited {
+        /// The underlying source
+        source: Box<Source>,
+        /// The remaining amount of bytes that may be read
+        remaining: u64,
+    },
+    /// A buffered source, which allows to peek into the stream without consuming it (e.g. for protocol sniffing)
+    Buffered(Box<BufReader<Source>>),
+    /// A chain of sources that are read one after another (e.g. a static header, a file and a static footer)
+    Chain(VecDeque<Source>),
     /// A catch-all/opaque variant for all types that cannot be covered by the enum's specific variants
-    Other(Box<dyn AnySource + Send + UnwindSafe>),
+    Other(Box<dyn AnySource + Send>),
 }
 impl Source {
     /// Creates a new catch-all/opaque variant from a typed object by moving it to the heap
     pub fn from_other<T>(typed: T) -> Self
     where
-        T: AnySource + Send + UnwindSafe + 'static,
+        T: AnySource + Send + 'static,
     {
         let boxed = Box::new(typed);
         Self::Other(boxed)
     }
+    /// Creates a new source that generates its data lazily via the given closure, which fills the buffer like
+    /// `Read::read` and returns `0` at the end of the data (e.g. for CSV exports or log tails)
+    pub fn from_fn<F>(read: F) -> Self
+    where
+        F: FnMut(&mut [u8]) -> io::Result<usize> + Send + 'static,
+    {
+        Self::from_other(FnSource(read))
+    }
+    /// Creates a new source that lazily reads the chunks yielded by the given iterator one after another
+    pub fn from_chunks<I>(chunks: I) -> Self
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        I::Item: Into<Data> + 'static,
+    {
+        let chunks = chunks.into_iter().map(Into::into);
+        Self::from_other(ChunkSource { current: Cursor::new(Data::Empty), chunks })
+    }
+    /// Creates a new source that is fed with chunks by another thread via the returned sender, and ends once all senders
+    /// have been dropped
+    ///
+    /// # Note
+    /// The channel holds at most `capacity` chunks, so a fast producer is blocked until the worker has streamed the
+    /// pending chunks to the client. The source waits indefinitely for the next chunk, so a stalled producer blocks the
+    /// worker; use [`Source::from_channel_timeout`] to bound the wait.
+    pub fn from_channel(capacity: usize) -> (ChannelSender, Self) {
+        let (sender, receiver) = flume::bounded(capacity);
+        let source = ChannelSource { current: Cursor::new(Data::Empty), receiver, timeout: None };
+        (ChannelSender(sender), Self::from_other(source))
+    }
+    /// Creates a new channel-backed source like [`Source::from_channel`], which fails with `TimedOut` if no chunk has
+    /// been received within `timeout`
+    pub fn from_channel_timeout(capacity: usize, timeout: Duration) -> (ChannelSender, Self) {
+        let (sender, receiver) = flume::bounded(capacity);
+        let source = ChannelSource { current: Cursor::new(Data::Empty), receiver, timeout: Some(timeout) };
+        (ChannelSender(sender), Self::from_other(source))
+    }
+    /// Creates a new source that reads at most `limit` bytes from the given source (e.g. to consume a body with a given
+    /// `Content-Length` or to serve a byte range of a file)
+    ///
+    /// # Note
+    /// Unlike `Read::take`, the result is a `Source` again, and the underlying source keeps its fast path. The limit is
+    /// an upper bound; if the underlying source ends early, the limited source ends early too.
+    pub fn limited<T>(source: T, limit: u64) -> Self
+    where
+        T: Into<Source>,
+    {
+        Self::Limited { source: Box::new(source.into()), remaining: limit }
+    }
+    /// Creates a new buffered source from the given source
+    ///
+    /// # Note
+    /// Sources that are already buffered (i.e. data-backed, empty or buffered sources) are returned as-is.
+    pub fn buffered<T>(source: T) -> Self
+    where
+        T: Into<Source>,
+    {
+        match source.into() {
+            source @ (Self::Empty | Self::Data(_) | Self::Buffered(_)) => source,
+            source => Self::Buffered(Box::new(BufReader::new(source))),
+        }
+    }
+    /// Creates a new source that reads the given sources one after another without intermediate buffering
+    pub fn chain<I, T>(sources: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Source>,
+    {
+        let sources = sources.into_iter().map(Into::into).collect();
+        Self::Chain(sources)
+    }
+
+    /// Whether `self` is a buffered source with buffered bytes that have not been consumed yet
+    pub(crate) fn has_buffered(&self) -> bool {
+        match self {
+            Self::Buffered(buffered) => !buffered.buffer().is_empty(),
+            _ => false,
+        }
+    }
+    /// The underlying TCP stream if `self` is a plain or buffered TCP stream
+    pub(crate) fn tcp_stream(&self) -> Option<&TcpStream> {
+        match self {
+            Self::TcpStream(tcp_stream) => Some(tcp_stream),
+            Self::Buffered(buffered) => buffered.get_ref().tcp_stream(),
+            _ => None,
+        }
+    }
 }
 impl Read for Source {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
@@ -64,10 +272,51 @@ impl Read for Source {
             Source::Data(data) => data.read(buf),
             Source::File(file) => file.read(buf),
             Source::TcpStream(tcp_stream) => tcp_stream.read(buf),
+            Source::Buffered(buffered) => buffered.read(buf),
+            Source::Limited { source, remaining } => {
+                // Limit the buffer to the remaining bytes
+                let len = usize::try_from(*remaining).unwrap_or(usize::MAX).min(buf.len());
+                let read = source.read(&mut buf[..len])?;
+                *remaining -= read as u64;
+                Ok(read)
+            }
+            Source::Chain(sources) => {
+                // Read from the first source that is not exhausted
+                while let Some(source) = sources.front_mut() {
+                    let read = source.read(buf)?;
+                    if read > 0 || buf.is_empty() {
+                        return Ok(read);
+                    }
+                    sources.pop_front();
+                }
+                Ok(0)
+            }
             Source::Other(other) => other.as_read_mut().read(buf),
         }
     }
 }
+impl BufRead for Source {
+    /// Returns the contents of the internal buffer, filling it with more data from the underlying source if it is empty
+    ///
+    /// # Note
+    /// Only empty, data-backed and buffered sources support buffered reads; other sources fail with
+    /// `ErrorKind::Unsupported` and must be wrapped via [`Source::buffered`] first.
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Self::Empty => Ok(&[]),
+            Self::Data(data) => data.fill_buf(),
+            Self::Buffered(buffered) => buffered.fill_buf(),
+            _ => Err(io::Error::new(ErrorKind::Unsupported, "source is not buffered")),
+        }
+    }
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Data(data) => data.consume(amt),
+            Self::Buffered(buffered) => buffered.consume(amt),
+            _ => (),
+        }
+    }
+}
 impl Debug for Source {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
@@ -75,15 +324,15 @@ impl Debug for Source {
             Self::Data(arg0) => f.debug_tuple("Data").field(arg0).finish(),
             Self::File(arg0) => f.debug_tuple("File").field(arg0).finish(),
             Self::TcpStream(arg0) => f.debug_tuple("TcpStream").field(arg0).finish(),
+            Self::Limited { source, remaining } => {
+                f.debug_struct("Limited").field("source", source).field("remaining", remaining).finish()
+            }
+            Self::Buffered(arg0) => f.debug_tuple("Buffered").field(arg0).finish(),
+            Self::Chain(arg0) => f.debug_tuple("Chain").field(arg0).finish(),
             Self::Other(other) => f.debug_tuple("Other").field(other.as_debug()).finish(),
         }
     }
 }
-impl Default for Source {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
 impl From<Data> for Source {
     fn from(value: Data) -> Self {
         Self::Data(Cursor::new(value))
@@ -0,0 +1,66 @@
+//! Zero-copy transfers between TCP streams via `splice(2)`
+
+use std::{
+    io::{self, ErrorKind},
+    net::TcpStream,
+    os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    ptr,
+};
+
+/// The maximum amount of bytes to move through the pipe at once
+const PIPE_CHUNK_MAX: usize = 64 * 1024;
+
+/// Moves up to `limit` bytes (or everything until EOF if `limit` is `None`) from `from` to `to` without copying them
+/// through userspace, adds the amount of bytes that have been written to `sent`, and returns the amount of bytes moved
+///
+/// # Note
+/// The bytes are moved through an intermediate pipe; the socket timeouts of both streams apply.
+pub fn splice(from: &TcpStream, to: &TcpStream, limit: Option<u64>, sent: &mut u64) -> io::Result<u64> {
+    // Create the intermediate pipe
+    let mut fds: [RawFd; 2] = [-1; 2];
+    // Safety: The pointer refers to a valid array of two file descriptors
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Safety: The file descriptors have just been created and are owned by nobody else
+    let (pipe_rx, pipe_tx) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+    // Move the data in pipe-sized chunks
+    let (mut remaining, mut moved) = (limit.unwrap_or(u64::MAX), 0);
+    while remaining > 0 {
+        // Fill the pipe from the source
+        let chunk = usize::try_from(remaining).unwrap_or(usize::MAX).min(PIPE_CHUNK_MAX);
+        let filled = match splice_once(from.as_raw_fd(), pipe_tx.as_raw_fd(), chunk)? {
+            0 => break,
+            filled => filled,
+        };
+
+        // Drain the pipe into the destination
+        let mut pending = filled;
+        while pending > 0 {
+            let drained = splice_once(pipe_rx.as_raw_fd(), to.as_raw_fd(), pending)?;
+            if drained == 0 {
+                return Err(io::Error::new(ErrorKind::WriteZero, "failed to splice into the destination"));
+            }
+            (pending, *sent) = (pending - drained, *sent + drained as u64);
+        }
+        (remaining, moved) = (remaining - filled as u64, moved + filled as u64);
+    }
+    Ok(moved)
+}
+
+/// Performs a single `splice` call and retries if it has been interrupted
+fn splice_once(from: RawFd, to: RawFd, len: usize) -> io::Result<usize> {
+    loop {
+        // Safety: Both file descriptors are valid for the duration of the call, and null offsets are allowed for pipes
+        // and sockets
+        let result = unsafe {
+            libc::splice(from, ptr::null_mut(), to, ptr::null_mut(), len, libc::SPLICE_F_MOVE | libc::SPLICE_F_MORE)
+        };
+        match usize::try_from(result) {
+            Ok(len) => return Ok(len),
+            Err(_) if io::Error::last_os_error().kind() == ErrorKind::Interrupted => continue,
+            Err(_) => return Err(io::Error::last_os_error()),
+        }
+    }
+}
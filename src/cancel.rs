@@ -0,0 +1,60 @@
+//! Cooperative cancellation tokens to abort long-running handlers
+
+use crate::{error, error::Error};
+use std::sync::{
+    atomic::{AtomicBool, Ordering::SeqCst},
+    Arc,
+};
+
+/// The shared token state
+#[derive(Debug, Default)]
+struct Inner {
+    /// Whether the token has been cancelled
+    cancelled: AtomicBool,
+    /// The parent token whose cancellation is inherited
+    parent: Option<CancellationToken>,
+}
+
+/// A cheaply cloneable token to signal that an operation should be aborted (e.g. during shutdown or if the client has
+/// disconnected)
+///
+/// # Note
+/// Cancellation is cooperative: Long-running handlers must poll the token (e.g. between two chunks) and abort on their
+/// own; a handler that does not poll the token runs to completion.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    /// The shared state
+    inner: Arc<Inner>,
+}
+impl CancellationToken {
+    /// Creates a new token
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates a child token that is cancelled if either `self` or the child itself is cancelled
+    pub fn child(&self) -> Self {
+        let inner = Inner { cancelled: AtomicBool::default(), parent: Some(self.clone()) };
+        Self { inner: Arc::new(inner) }
+    }
+
+    /// Cancels the token and all of its children
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, SeqCst);
+    }
+    /// Whether the token or one of its parents has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        // Check the token itself and the parent
+        if self.inner.cancelled.load(SeqCst) {
+            return true;
+        }
+        let parent = self.inner.parent.as_ref();
+        parent.is_some_and(Self::is_cancelled)
+    }
+    /// Returns an error if the token has been cancelled, so that handlers can abort via `?`
+    pub fn check(&self) -> Result<(), Error> {
+        match self.is_cancelled() {
+            true => Err(error!("Operation has been cancelled")),
+            false => Ok(()),
+        }
+    }
+}
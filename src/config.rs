@@ -0,0 +1,108 @@
+//! Parsers for human-friendly configuration values
+
+use crate::{error, error::Error};
+use std::{env, time::Duration};
+
+/// Parses a boolean value (`true`/`false`, `yes`/`no`, `on`/`off` or `1`/`0`)
+pub fn parse_bool(value: &str) -> Result<bool, Error> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(error!("Invalid boolean: {value}")),
+    }
+}
+
+/// Parses a byte size with an optional unit, e.g. `512`, `64KiB` or `1.5 MB`
+///
+/// # Units
+/// The units are case-insensitive: `B` (or no unit), the decimal units `KB`, `MB`, `GB`, `TB` and the binary units `KiB`,
+/// `MiB`, `GiB`, `TiB`. Fractional values are rounded down to whole bytes.
+pub fn parse_size(value: &str) -> Result<u64, Error> {
+    // Split the number and the unit
+    let value = value.trim();
+    let split = value.find(|char: char| !char.is_ascii_digit() && char != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    // Get the unit multiplier
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000 * 1000,
+        "gb" => 1000 * 1000 * 1000,
+        "tb" => 1000 * 1000 * 1000 * 1000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => return Err(error!("Invalid size unit: {value}")),
+    };
+
+    // Parse the number
+    let size = match number.contains('.') {
+        false => number.parse::<u64>().ok().and_then(|number| number.checked_mul(multiplier)),
+        true => (number.parse::<f64>().ok())
+            .map(|number| number * multiplier as f64)
+            .filter(|size| *size < u64::MAX as f64)
+            .map(|size| size as u64),
+    };
+    size.ok_or_else(|| error!("Invalid size: {value}"))
+}
+
+/// Parses a duration as sequence of integer values with units, e.g. `250ms`, `2m30s` or `1h`
+///
+/// # Units
+/// The units are `ms`, `s`, `m`, `h` and `d`; a single number without unit is interpreted as seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, Error> {
+    // Accept plain seconds
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(error!("Empty duration"));
+    }
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+
+    // Parse the components
+    let (mut rest, mut duration) = (value, Duration::ZERO);
+    while !rest.is_empty() {
+        // Split the number and the unit
+        let split = rest.find(|char: char| !char.is_ascii_digit()).unwrap_or(rest.len());
+        let (number, remainder) = rest.split_at(split);
+        let split = remainder.find(|char: char| char.is_ascii_digit()).unwrap_or(remainder.len());
+        let (unit, remainder) = remainder.split_at(split);
+        rest = remainder;
+
+        // Parse the component
+        let number: u64 = number.parse().map_err(|_| error!("Invalid duration: {value}"))?;
+        let component = match unit {
+            "ms" => Some(Duration::from_millis(number)),
+            "s" => Some(Duration::from_secs(number)),
+            "m" => number.checked_mul(60).map(Duration::from_secs),
+            "h" => number.checked_mul(60 * 60).map(Duration::from_secs),
+            "d" => number.checked_mul(24 * 60 * 60).map(Duration::from_secs),
+            _ => return Err(error!("Invalid duration unit: {value}")),
+        };
+        duration = component
+            .and_then(|component| duration.checked_add(component))
+            .ok_or_else(|| error!("Duration is too large: {value}"))?;
+    }
+    Ok(duration)
+}
+
+/// Reads the environment variable with the given name and parses it if it is set
+pub fn env<T, F>(name: &str, parse: F) -> Result<Option<T>, Error>
+where
+    F: FnOnce(&str) -> Result<T, Error>,
+{
+    // Read the variable
+    let Some(value) = env::var_os(name) else {
+        return Ok(None);
+    };
+    let Some(value) = value.to_str() else {
+        return Err(error!("Invalid environment variable {name}: value is not valid UTF-8"));
+    };
+
+    // Parse the value
+    let parsed = parse(value).map_err(|e| error!("Invalid environment variable {name}: {}", e.error))?;
+    Ok(Some(parsed))
+}
@@ -0,0 +1,165 @@
+//! A local control endpoint to manage a running server
+
+use crate::{
+    cancel::CancellationToken,
+    drain::Connections,
+    log::{self, Level},
+    threadpool::ThreadpoolStats,
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::{
+        atomic::{AtomicBool, Ordering::SeqCst},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// A callback to get the current threadpool statistics
+type StatsCallback = Arc<dyn Fn() -> ThreadpoolStats + Send + Sync + 'static>;
+
+/// A handle to manage a running server at runtime (e.g. via [`Control::serve`])
+///
+/// # Commands
+/// The control protocol is line-based; every command is answered with a single line that starts with either `ok` or
+/// `error`:
+/// - `stats`: Dumps the threadpool statistics as `key=value` pairs
+/// - `level <off|error|warn|info|debug>`: Sets the log level
+/// - `maintenance <on|off>`: Toggles the maintenance mode, where new connections are answered with
+///   `503 Service Unavailable`
+/// - `connections`: Dumps the amount of active connections, the amount of in-flight requests and the age of the oldest
+///   connection in seconds
+/// - `drain`: Cancels the server's cancellation token, so that polling handlers abort and keep-alive connections are
+///   closed after the current request
+/// - `abort`: Aborts all active connections (e.g. after the drain deadline has passed)
+#[derive(Clone)]
+pub struct Control {
+    /// The threadpool statistics
+    stats: StatsCallback,
+    /// The maintenance mode flag
+    maintenance: Arc<AtomicBool>,
+    /// The server-wide cancellation token
+    cancellation: CancellationToken,
+    /// The active connections
+    connections: Connections,
+}
+impl Control {
+    /// The timeout after which idle or slow clients are disconnected
+    pub const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+    /// The maximum size of a single command line in bytes, including the trailing newline
+    pub const COMMAND_SIZE_MAX: usize = 256;
+
+    /// Creates a new control handle
+    pub(crate) fn new(
+        stats: StatsCallback,
+        maintenance: Arc<AtomicBool>,
+        cancellation: CancellationToken,
+        connections: Connections,
+    ) -> Self {
+        Self { stats, maintenance, cancellation, connections }
+    }
+
+    /// Executes a single command and returns the response line
+    pub fn execute(&self, command: &str) -> String {
+        let mut args = command.split_ascii_whitespace();
+        match (args.next(), args.next(), args.next()) {
+            (Some("stats"), None, _) => {
+                let stats = (self.stats)();
+                format!(
+                    "ok workers={} idle={} queued={} capacity={} executed={} panicked={}",
+                    stats.workers, stats.idle, stats.queued, stats.capacity, stats.executed, stats.panicked
+                )
+            }
+            (Some("level"), Some(level), None) => {
+                let Ok(level) = level.parse::<Level>() else {
+                    return format!("error invalid level: {level}");
+                };
+                log::set_level(level);
+                "ok".to_string()
+            }
+            (Some("maintenance"), Some(mode @ ("on" | "off")), None) => {
+                self.maintenance.store(mode == "on", SeqCst);
+                "ok".to_string()
+            }
+            (Some("connections"), None, _) => {
+                let snapshot = self.connections.snapshot();
+                let in_flight = snapshot.iter().filter(|connection| connection.request.is_some()).count();
+                let oldest = snapshot.iter().map(|connection| connection.age).max().unwrap_or_default();
+                format!("ok active={} in_flight={in_flight} oldest={:.3}", snapshot.len(), oldest.as_secs_f64())
+            }
+            (Some("drain"), None, _) => {
+                self.cancellation.cancel();
+                "ok".to_string()
+            }
+            (Some("abort"), None, _) => format!("ok aborted={}", self.connections.abort_all()),
+            _ => format!("error invalid command: {}", command.trim()),
+        }
+    }
+
+    /// Listens on the Unix socket at the given path and executes all commands forever
+    ///
+    /// # Important
+    /// Everyone who can connect to the socket can manage the server; so the socket is restricted to the owner (mode
+    /// `0600`), and should additionally be placed in a directory that is only accessible to the operators (the socket
+    /// is created with the process umask before it is restricted). Clients are served one at a time; idle clients are
+    /// disconnected after [`Self::CLIENT_TIMEOUT`], and commands longer than [`Self::COMMAND_SIZE_MAX`] are rejected.
+    #[cfg(target_family = "unix")]
+    pub fn serve<P>(self, path: P) -> Result<std::convert::Infallible, crate::error::Error>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        use std::{
+            fs::{self, Permissions},
+            io::{BufRead, BufReader, Read, Write},
+            os::unix::{fs::PermissionsExt, net::UnixListener},
+        };
+
+        // Bind the socket and restrict it to the owner
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, Permissions::from_mode(0o600))?;
+        loop {
+            // Accept the next client
+            let (stream, _) = listener.accept()?;
+            let _ = stream.set_read_timeout(Some(Self::CLIENT_TIMEOUT));
+            let _ = stream.set_write_timeout(Some(Self::CLIENT_TIMEOUT));
+            let Ok(mut tx) = stream.try_clone() else {
+                continue;
+            };
+
+            // Execute all commands
+            let mut rx = BufReader::new(stream);
+            let mut command = Vec::new();
+            loop {
+                // Read the next command
+                // Note: The line is limited to one byte above the maximum command size to detect overlong commands
+                command.clear();
+                let limit = Self::COMMAND_SIZE_MAX as u64 + 1;
+                let Ok(1..) = rx.by_ref().take(limit).read_until(b'\n', &mut command) else {
+                    break;
+                };
+                if command.len() > Self::COMMAND_SIZE_MAX {
+                    let _ = writeln!(tx, "error command is too long");
+                    break;
+                }
+
+                // Execute the command
+                let response = match std::str::from_utf8(&command) {
+                    Ok(command) => self.execute(command),
+                    Err(_) => "error command is not valid UTF-8".to_string(),
+                };
+                if writeln!(tx, "{response}").is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+impl Debug for Control {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Control")
+            .field("maintenance", &self.maintenance)
+            .field("cancellation", &self.cancellation)
+            .field("connections", &self.connections)
+            .finish_non_exhaustive()
+    }
+}
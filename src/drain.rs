@@ -0,0 +1,241 @@
+//! Tracks the active connections of a server so that it can be drained gracefully during shutdown
+
+use crate::cancel::CancellationToken;
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    net::{Shutdown, SocketAddr, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering::Relaxed},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A snapshot of an active connection
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ActiveConnection {
+    /// The unique connection ID
+    pub id: u64,
+    /// The peer address if known
+    pub peer: Option<SocketAddr>,
+    /// The tag that has been assigned to the connection if any
+    pub tag: Option<Arc<str>>,
+    /// The time since the connection has been accepted
+    pub age: Duration,
+    /// The request that is currently being handled (e.g. `GET /index.html`), or `None` if the connection is idle
+    ///
+    /// # Note
+    /// Requests are only recorded once a handle to the active connections has been created (see
+    /// [`crate::Server::connections`] and [`crate::Server::control`]).
+    pub request: Option<Arc<str>>,
+}
+
+/// The outcome of draining a server (see [`Connections::drain`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DrainReport {
+    /// The amount of connections that have been closed cleanly while draining
+    pub drained: u64,
+    /// The amount of stragglers that have been aborted after the deadline
+    pub aborted: usize,
+    /// The amount of requests that have been finished while draining
+    pub completed: u64,
+    /// The time it took to drain the server
+    pub elapsed: Duration,
+}
+impl DrainReport {
+    /// Whether all connections have been drained cleanly without aborting a straggler
+    pub fn is_clean(&self) -> bool {
+        self.aborted == 0
+    }
+}
+
+/// An entry in the connection registry
+#[derive(Debug)]
+struct Entry {
+    /// The peer address if known
+    peer: Option<SocketAddr>,
+    /// The tag that has been assigned to the connection if any
+    tag: Option<Arc<str>>,
+    /// The time when the connection has been accepted
+    accepted: Instant,
+    /// The request that is currently being handled if any
+    request: Option<Arc<str>>,
+    /// A clone of the underlying TCP stream to abort the connection if possible
+    stream: Option<TcpStream>,
+}
+
+/// The connection registry
+#[derive(Debug, Default)]
+struct Registry {
+    /// The next connection ID
+    next_id: AtomicU64,
+    /// Whether the current requests are recorded (i.e. if there is a consumer for the snapshots)
+    tracking: AtomicBool,
+    /// The active connections by ID
+    entries: Mutex<HashMap<u64, Entry>>,
+    /// The total amount of closed connections
+    closed: AtomicU64,
+    /// The total amount of finished requests (only counted while the current requests are recorded)
+    completed: AtomicU64,
+}
+impl Registry {
+    /// The active connections
+    fn entries(&self) -> MutexGuard<'_, HashMap<u64, Entry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A handle to the active connections of a server (see [`crate::Server::connections`])
+///
+/// # Note
+/// A connection is active from the time it is accepted until it is closed, including the time it spends idle between
+/// keep-alive requests.
+#[derive(Clone)]
+pub struct Connections {
+    /// The connection registry
+    registry: Arc<Registry>,
+    /// The server-wide cancellation token
+    cancellation: CancellationToken,
+}
+impl Connections {
+    /// The interval to poll the registry while draining
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    /// Creates a new empty connection registry
+    pub(crate) fn new(cancellation: CancellationToken) -> Self {
+        Self { registry: Arc::default(), cancellation }
+    }
+
+    /// Enables the recording of the current requests, which is disabled by default to save a registry update per request
+    pub(crate) fn track_requests(&self) {
+        self.registry.tracking.store(true, Relaxed);
+    }
+
+    /// Registers a new active connection until the returned guard is dropped
+    ///
+    /// # Note
+    /// The registry holds a clone of the stream, so the connection can be aborted even if the handler has replaced its
+    /// sink; the clone is closed once the guard is dropped.
+    pub(crate) fn register(
+        &self,
+        peer: Option<SocketAddr>,
+        tag: Option<Arc<str>>,
+        stream: Option<&TcpStream>,
+    ) -> ActiveGuard {
+        let id = self.registry.next_id.fetch_add(1, Relaxed);
+        let stream = stream.and_then(|stream| stream.try_clone().ok());
+        let entry = Entry { peer, tag, accepted: Instant::now(), request: None, stream };
+        self.registry.entries().insert(id, entry);
+        ActiveGuard { handle: ActiveHandle { registry: self.registry.clone(), id } }
+    }
+
+    /// The amount of active connections
+    pub fn len(&self) -> usize {
+        self.registry.entries().len()
+    }
+    /// Whether there are no active connections
+    pub fn is_empty(&self) -> bool {
+        self.registry.entries().is_empty()
+    }
+    /// A snapshot of all active connections, ordered from the oldest to the newest connection
+    pub fn snapshot(&self) -> Vec<ActiveConnection> {
+        let entries = self.registry.entries();
+        let mut snapshot: Vec<_> = (entries.iter())
+            .map(|(id, entry)| ActiveConnection {
+                id: *id,
+                peer: entry.peer,
+                tag: entry.tag.clone(),
+                age: entry.accepted.elapsed(),
+                request: entry.request.clone(),
+            })
+            .collect();
+        snapshot.sort_by_key(|connection| connection.id);
+        snapshot
+    }
+
+    /// Aborts all active connections by shutting down the underlying TCP streams, and returns the amount of aborted
+    /// connections
+    ///
+    /// # Note
+    /// Connections that have been dispatched manually with a non-TCP sink cannot be aborted.
+    pub fn abort_all(&self) -> usize {
+        let entries = self.registry.entries();
+        let streams = entries.values().filter_map(|entry| entry.stream.as_ref());
+        streams.filter(|stream| stream.shutdown(Shutdown::Both).is_ok()).count()
+    }
+    /// Drains the server: Cancels the server-wide cancellation token so that no keep-alive connection is rescheduled, waits
+    /// until all connections are closed or the deadline has passed, and aborts the remaining stragglers
+    ///
+    /// # Note
+    /// Returns a report so that deployment tooling can verify a clean rollout. New connections are still accepted and
+    /// should be stopped beforehand (e.g. via the maintenance mode or by removing the instance from the load balancer);
+    /// connections that are accepted and closed while draining are counted as drained.
+    pub fn drain(&self, deadline: Duration) -> DrainReport {
+        // Signal the shutdown and wait for the connections to close
+        let (closed, completed) = (self.registry.closed.load(Relaxed), self.registry.completed.load(Relaxed));
+        self.cancellation.cancel();
+        let start = Instant::now();
+        while !self.is_empty() && start.elapsed() < deadline {
+            thread::sleep(Self::POLL_INTERVAL);
+        }
+
+        // Abort the stragglers
+        // Note: The counters are read before the abort, so that aborted connections are not counted as drained
+        let drained = self.registry.closed.load(Relaxed) - closed;
+        let completed = self.registry.completed.load(Relaxed) - completed;
+        let aborted = self.abort_all();
+        DrainReport { drained, aborted, completed, elapsed: start.elapsed() }
+    }
+}
+impl Debug for Connections {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Connections").field("active", &self.len()).finish()
+    }
+}
+
+/// A handle to update the registry entry of an active connection
+#[derive(Debug, Clone)]
+pub(crate) struct ActiveHandle {
+    /// The connection registry
+    registry: Arc<Registry>,
+    /// The connection ID
+    id: u64,
+}
+impl ActiveHandle {
+    /// Whether the current requests are recorded
+    pub fn is_tracking(&self) -> bool {
+        self.registry.tracking.load(Relaxed)
+    }
+    /// Sets the request that is currently being handled
+    pub fn set_request(&self, request: Option<Arc<str>>) {
+        if let Some(entry) = self.registry.entries().get_mut(&self.id) {
+            if entry.request.is_some() && request.is_none() {
+                self.registry.completed.fetch_add(1, Relaxed);
+            }
+            entry.request = request;
+        }
+    }
+}
+
+/// A registered active connection which is unregistered if the guard is dropped
+#[derive(Debug)]
+pub(crate) struct ActiveGuard {
+    /// The handle to the registry entry
+    handle: ActiveHandle,
+}
+impl ActiveGuard {
+    /// A handle to update the registry entry
+    pub fn handle(&self) -> ActiveHandle {
+        self.handle.clone()
+    }
+}
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.handle.registry.entries().remove(&self.handle.id);
+        self.handle.registry.closed.fetch_add(1, Relaxed);
+    }
+}
@@ -5,7 +5,8 @@ use std::{
     convert::Infallible,
     error,
     fmt::{self, Display, Formatter},
-    num::ParseIntError,
+    io::{self, ErrorKind},
+    num::{ParseIntError, TryFromIntError},
     ops::Deref,
     str::Utf8Error,
 };
@@ -13,6 +14,11 @@ use std::{
 /// Creates a new error
 #[macro_export]
 macro_rules! error {
+    (all: $errors:expr, $($arg:tt)*) => {{
+        let error = format!($($arg)*);
+        let errors = $errors.into_iter().map(|error| -> Box<dyn std::error::Error + Send> { Box::new(error) });
+        $crate::error::Error::aggregate(error, errors.collect())
+    }};
     (with: $error:expr, $($arg:tt)*) => {{
         let error = format!($($arg)*);
         let source = Box::new($error);
@@ -25,12 +31,21 @@ macro_rules! error {
 }
 
 /// The crates error type
+///
+/// # Note
+/// Since the aggregated errors are private, the error cannot be created via a struct literal outside of this crate
+/// anymore; this is a breaking change compared to earlier versions. Use the [`error!`](crate::error!) macro instead.
 #[derive(Debug)]
 pub struct Error {
     /// The error description
     pub error: String,
     /// The underlying error
     pub source: Option<Box<dyn error::Error + Send>>,
+    /// The aggregated underlying errors if multiple attempts have failed (e.g. all upstreams during a failover)
+    ///
+    /// # Note
+    /// Use [`Self::sources`] to access the aggregated errors.
+    aggregated: Vec<Box<dyn error::Error + Send>>,
     /// The backtrace
     pub backtrace: Backtrace,
 }
@@ -39,13 +54,44 @@ impl Error {
     #[doc(hidden)]
     pub fn new(error: String, source: Option<Box<dyn error::Error + Send>>) -> Self {
         let backtrace = Backtrace::capture();
-        Self { error, source, backtrace }
+        Self { error, source, aggregated: Vec::new(), backtrace }
+    }
+    /// Creates a new error that aggregates multiple underlying errors
+    #[doc(hidden)]
+    pub fn aggregate(error: String, aggregated: Vec<Box<dyn error::Error + Send>>) -> Self {
+        let backtrace = Backtrace::capture();
+        Self { error, source: None, aggregated, backtrace }
+    }
+
+    /// All underlying errors, i.e. the direct source if any followed by the aggregated errors
+    pub fn sources(&self) -> impl Iterator<Item = &(dyn error::Error + Send + 'static)> {
+        self.source.iter().chain(&self.aggregated).map(|source| source.deref())
     }
 
     /// Whether the error has captured a backtrace or not
     pub fn has_backtrace(&self) -> bool {
         self.backtrace.status() == BacktraceStatus::Captured
     }
+
+    /// The underlying I/O error kind if the error was caused by an I/O error
+    pub fn io_kind(&self) -> Option<ErrorKind> {
+        let source = self.source.as_ref()?;
+        let io_error = source.downcast_ref::<io::Error>()?;
+        Some(io_error.kind())
+    }
+    /// Whether the error was caused by the client aborting the connection (e.g. a reset or closed connection), as opposed
+    /// to a server fault
+    pub fn is_client_abort(&self) -> bool {
+        matches!(
+            self.io_kind(),
+            Some(
+                ErrorKind::BrokenPipe
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::UnexpectedEof
+            )
+        )
+    }
 }
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
@@ -56,17 +102,23 @@ impl Display for Error {
         if let Some(source) = &self.source {
             writeln!(f, " caused by: {source}")?;
         }
+
+        // Print the aggregated errors
+        for (index, error) in self.aggregated.iter().enumerate() {
+            writeln!(f, " caused by [{index}]: {error}")?;
+        }
         Ok(())
     }
 }
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        let boxed = self.source.as_ref()?;
+        // Fall back to the first aggregated error so that aggregated errors remain visible via the standard chain
+        let boxed = self.source.as_ref().or(self.aggregated.first())?;
         Some(boxed.deref())
     }
 }
-impl From<std::io::Error> for Error {
-    fn from(value: std::io::Error) -> Self {
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
         error!(with: value, "An I/O error occurred")
     }
 }
@@ -80,6 +132,11 @@ impl From<ParseIntError> for Error {
         error!(with: value, "Value is not a valid integer")
     }
 }
+impl From<TryFromIntError> for Error {
+    fn from(value: TryFromIntError) -> Self {
+        error!(with: value, "Value is out of range")
+    }
+}
 impl From<Infallible> for Error {
     fn from(_: Infallible) -> Self {
         unreachable!("infallible variant can never be constructed")
@@ -0,0 +1,196 @@
+//! An access logging middleware
+
+use crate::{
+    bytes::Data,
+    http::{Request, Response},
+    log_info,
+    timing::PhaseTimings,
+};
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// The access log format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessLogFormat {
+    /// The Common Log Format, extended by the handler duration in microseconds
+    Common,
+    /// One JSON object per request
+    Json,
+}
+
+/// A single access log record
+#[derive(Debug, Clone)]
+pub struct AccessLogRecord {
+    /// The peer address if known
+    pub peer: Option<SocketAddr>,
+    /// The request method
+    pub method: Data,
+    /// The request target
+    pub target: Data,
+    /// The request version
+    pub version: Data,
+    /// The response status
+    pub status: Data,
+    /// The response body size if known
+    pub size: Option<u64>,
+    /// The time when the request was received
+    pub time: SystemTime,
+    /// The handler duration
+    pub duration: Duration,
+    /// The phase timings up to and including the request parsing
+    pub timings: PhaseTimings,
+}
+impl AccessLogRecord {
+    /// Formats the record in the given format
+    pub fn format(&self, format: AccessLogFormat) -> String {
+        match format {
+            AccessLogFormat::Common => self.format_common(),
+            AccessLogFormat::Json => self.format_json(),
+        }
+    }
+
+    /// Formats the record in the Common Log Format
+    fn format_common(&self) -> String {
+        // Format the optional fields
+        let peer = match self.peer {
+            Some(peer) => peer.ip().to_string(),
+            None => "-".to_string(),
+        };
+        let size = match self.size {
+            Some(size) => size.to_string(),
+            None => "-".to_string(),
+        };
+
+        // Format the record
+        let (method, target, version) =
+            (common_string(&self.method), common_string(&self.target), common_string(&self.version));
+        let (status, time, duration) = (common_string(&self.status), common_time(self.time), self.duration.as_micros());
+        format!(r#"{peer} - - [{time}] "{method} {target} {version}" {status} {size} {duration}"#)
+    }
+    /// Formats the record as JSON object
+    fn format_json(&self) -> String {
+        // Format the optional fields
+        let peer = match self.peer {
+            Some(peer) => json_string(&peer.to_string()),
+            None => "null".to_string(),
+        };
+        let size = match self.size {
+            Some(size) => size.to_string(),
+            None => "null".to_string(),
+        };
+
+        // Format the record
+        let time = self.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let (method, target, version) =
+            (json_string(&lossy(&self.method)), json_string(&lossy(&self.target)), json_string(&lossy(&self.version)));
+        let (status, duration) = (json_string(&lossy(&self.status)), self.duration.as_micros());
+        let (queue, first_byte, parse) =
+            (json_micros(self.timings.queue), json_micros(self.timings.first_byte), json_micros(self.timings.parse));
+        format!(
+            r#"{{"time":{time},"peer":{peer},"method":{method},"target":{target},"version":{version},"status":{status},"size":{size},"duration_us":{duration},"queue_us":{queue},"first_byte_us":{first_byte},"parse_us":{parse}}}"#
+        )
+    }
+}
+
+/// Wraps a `request->response`-handler and logs every request via the crate's log facility at info level
+///
+/// # Note
+/// Since access log records are logged at `log::Level::Info`, the log level must be set accordingly.
+pub fn access_log<F>(format: AccessLogFormat, handler: F) -> impl Fn(Request) -> Response + Send + Sync + 'static
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    move |request: Request| {
+        // Capture the request information
+        let (peer, timings, time, start) = (request.peer, request.timings, SystemTime::now(), Instant::now());
+        let (method, target, version) = (request.method.clone(), request.target.clone(), request.version.clone());
+
+        // Handle the request and log the record
+        let mut response = handler(request);
+        response.resolve();
+        let (status, size, duration) = (response.status.clone(), response.body.len, start.elapsed());
+        let record = AccessLogRecord { peer, method, target, version, status, size, time, duration, timings };
+        log_info!("{}", record.format(format));
+        response
+    }
+}
+
+/// Converts the data into a string, replacing invalid UTF-8 sequences
+fn lossy(data: &Data) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
+
+/// Escapes the data for a quoted Common Log Format field
+///
+/// # Note
+/// Quotes, backslashes and control characters are escaped like Apache does (`\"`, `\\` and `\xNN`), so that a crafted
+/// request target cannot terminate the quoted request line or inject additional log lines.
+fn common_string(data: &Data) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    for char in lossy(data).chars() {
+        match char {
+            '"' => escaped.push_str(r#"\""#),
+            '\\' => escaped.push_str(r"\\"),
+            char if char.is_control() => {
+                let _ = write!(escaped, r"\x{:02x}", char as u32);
+            }
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+/// Formats a string as JSON string literal
+fn json_string(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len() + 2);
+    escaped.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => escaped.push_str(r#"\""#),
+            '\\' => escaped.push_str(r"\\"),
+            char if char.is_control() => {
+                let _ = write!(escaped, r"\u{:04x}", char as u32);
+            }
+            char => escaped.push(char),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Formats an optional duration as JSON number of microseconds
+fn json_micros(duration: Option<Duration>) -> String {
+    match duration {
+        Some(duration) => duration.as_micros().to_string(),
+        None => "null".to_string(),
+    }
+}
+
+/// Formats a timestamp in the Common Log Format time format (e.g. `10/Oct/2000:13:55:36 +0000`)
+fn common_time(time: SystemTime) -> String {
+    /// The month names
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+    // Split the timestamp into days and seconds
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+    let (hour, minute, second) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+
+    // Convert the days to a civil date (see http://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    // Format the date
+    let month = MONTHS[(month - 1) as usize];
+    format!("{day:02}/{month}/{year}:{hour:02}:{minute:02}:{second:02} +0000")
+}
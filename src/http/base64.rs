@@ -0,0 +1,53 @@
+//! A minimal base64 codec for header field values
+
+/// Decodes standard base64 (RFC 4648, section 4) with optional padding, or returns `None` if the input is invalid
+///
+/// # Note
+/// The decoding is strict: If padding is present, it must complete the last group, and the unused bits of the last group
+/// must be zero (RFC 4648, section 3.5), so that every value has exactly one valid encoding.
+pub(crate) fn decode(encoded: &[u8]) -> Option<Vec<u8>> {
+    // Strip and validate the padding
+    let (unpadded, padding) = match encoded {
+        [rest @ .., b'=', b'='] => (rest, 2),
+        [rest @ .., b'='] => (rest, 1),
+        rest => (rest, 0),
+    };
+    match (unpadded.len() % 4, padding) {
+        (1, _) => return None,
+        (_, 0) | (2, 2) | (3, 1) => (),
+        _ => return None,
+    }
+
+    // Decode the groups
+    let mut decoded = Vec::with_capacity(unpadded.len() / 4 * 3 + 2);
+    for group in unpadded.chunks(4) {
+        // Accumulate the sextets
+        let mut accumulator = 0u32;
+        for byte in group {
+            accumulator = (accumulator << 6) | u32::from(sextet(*byte)?);
+        }
+
+        // Reject non-zero unused bits of a partial group
+        let unused = (group.len() * 6) % 8;
+        if accumulator & ((1 << unused) - 1) != 0 {
+            return None;
+        }
+
+        // Emit the bytes
+        let bytes = (accumulator << (6 * (4 - group.len()))).to_be_bytes();
+        decoded.extend_from_slice(&bytes[1..group.len()]);
+    }
+    Some(decoded)
+}
+
+/// Decodes a single base64 character
+fn sextet(byte: u8) -> Option<u8> {
+    match byte {
+        b'A'..=b'Z' => Some(byte - b'A'),
+        b'a'..=b'z' => Some(byte - b'a' + 26),
+        b'0'..=b'9' => Some(byte - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
@@ -0,0 +1,34 @@
+//! A benchmark handler with a canned response
+
+use crate::{
+    bytes::Data,
+    http::{Request, Response, ResponseExt},
+};
+use std::{str, sync::Arc};
+
+/// Creates a `request->response`-handler that answers every request with a canned `200 OK` response with a body of
+/// `size_max` bytes, so that the server itself can be measured without any application overhead
+///
+/// # Note
+/// The body size can be reduced per request via a `size` query parameter (e.g. `/?size=1024`); larger sizes are capped to
+/// `size_max`. The body is allocated once and shared between all responses.
+pub fn benchmark(size_max: usize) -> impl Fn(Request) -> Response + Send + Sync + 'static {
+    let body = Arc::new(vec![b'x'; size_max]);
+    move |request: Request| {
+        // Get the requested size
+        let size = query_size(&request.target).unwrap_or(size_max).min(size_max);
+
+        // Create the response
+        let mut response = Response::new_200_ok();
+        response.set_content_type("application/octet-stream");
+        response.set_body_data(Data::ArcVec { backing: body.clone(), range: 0..size });
+        response
+    }
+}
+
+/// Gets the `size` query parameter from the target if any
+fn query_size(target: &[u8]) -> Option<usize> {
+    let (_, query) = str::from_utf8(target).ok()?.split_once('?')?;
+    let size = query.split('&').find_map(|param| param.strip_prefix("size="))?;
+    size.parse().ok()
+}
@@ -0,0 +1,251 @@
+//! A HTTP body
+
+use crate::{
+    bytes::{Data, DataChain, Source},
+    http::Deferred,
+};
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+};
+
+/// The framing of a HTTP body on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// The body has a fixed length which is announced via `Content-Length`
+    Fixed,
+    /// The body is sent as a sequence of chunks via `Transfer-Encoding: chunked`
+    Chunked,
+    /// The body is delimited by closing the connection
+    Close,
+}
+
+/// A HTTP body, consisting of a data source, the body length if known and the framing
+#[derive(Debug)]
+pub struct Body {
+    /// The body source
+    pub source: Source,
+    /// The body length if known
+    pub len: Option<u64>,
+    /// The framing to use when writing the body
+    pub framing: Framing,
+    /// The deferred response that replaces the response of this body once it is completed if any
+    pub(crate) deferred: Option<Deferred>,
+}
+impl Body {
+    /// The buffer size for chunked writes
+    const CHUNK_SIZE_MAX: usize = 4096;
+
+    /// Creates a new body with the given source and length
+    ///
+    /// # Note
+    /// The framing is `Framing::Fixed` if the length is known, and `Framing::Chunked` otherwise; [`crate::reqresp`]
+    /// delimits chunked bodies by closing the connection instead if the client does not support HTTP/1.1.
+    pub fn new(source: Source, len: Option<u64>) -> Self {
+        let framing = match len {
+            Some(_) => Framing::Fixed,
+            None => Framing::Chunked,
+        };
+        Self { source, len, framing, deferred: None }
+    }
+    /// Creates a new body with the given source, length and framing
+    pub fn with_framing(source: Source, len: Option<u64>, framing: Framing) -> Self {
+        Self { source, len, framing, deferred: None }
+    }
+    /// Creates a new empty body
+    pub fn empty() -> Self {
+        Self::new(Source::Empty, Some(0))
+    }
+
+    /// Whether the body is known to be empty or not
+    pub fn is_empty(&self) -> bool {
+        self.len == Some(0) || matches!(self.source, Source::Empty)
+    }
+
+    /// Appends the body to the given buffer if it is an in-memory body with a fixed length of at most `max` bytes, and
+    /// returns whether the body has been appended
+    pub(crate) fn append_small(&mut self, buf: &mut Vec<u8>, max: usize) -> bool {
+        // Only fixed-length bodies within the limit can be inlined
+        let (Framing::Fixed, Some(len)) = (self.framing, self.len) else {
+            return false;
+        };
+        let Some(len) = usize::try_from(len).ok().filter(|len| *len <= max) else {
+            return false;
+        };
+
+        // Copy the data if it is available in memory
+        match &mut self.source {
+            _ if len == 0 => true,
+            Source::Data(cursor) => {
+                let position = usize::try_from(cursor.position()).unwrap_or(usize::MAX);
+                let Some(data) = cursor.get_ref().get(position..position.saturating_add(len)) else {
+                    return false;
+                };
+                buf.extend_from_slice(data);
+                cursor.set_position((position + len) as u64);
+                true
+            }
+            _ => false,
+        }
+    }
+    /// Writes the body to the given stream using the body framing
+    ///
+    /// # Note
+    /// Fixed-length bodies fail with `UnexpectedEof` if the source ends before the announced length.
+    pub fn to_stream<T>(&mut self, stream: &mut T) -> io::Result<()>
+    where
+        T: Write,
+    {
+        self.write_counted(stream, &mut 0)
+    }
+    /// Writes the body to the given stream using the body framing and adds the amount of payload bytes that have been
+    /// written to `sent`
+    ///
+    /// # Note
+    /// For chunked bodies, only completely written chunks are counted.
+    pub(crate) fn write_counted<T>(&mut self, stream: &mut T, sent: &mut u64) -> io::Result<()>
+    where
+        T: Write,
+    {
+        match (self.framing, self.len) {
+            (Framing::Fixed, Some(len)) => {
+                // Never write more than the announced length to keep the framing intact
+                let mut stream = CountingWriter { inner: stream, written: sent };
+                let copied = io::copy(&mut (&mut self.source).take(len), &mut stream)?;
+
+                // Fail if the source is shorter than the announced length, since the framing is broken anyway
+                if copied < len {
+                    let message = format!("body is shorter than the announced length ({copied} < {len})");
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, message));
+                }
+            }
+            (Framing::Chunked, _) => self.write_chunked(stream, sent)?,
+            (Framing::Fixed | Framing::Close, _) => {
+                let mut stream = CountingWriter { inner: stream, written: sent };
+                io::copy(&mut self.source, &mut stream)?;
+            }
+        }
+        Ok(())
+    }
+    /// Moves a fixed-length or close-delimited body from an underlying TCP stream to the given stream via `splice`, adds
+    /// the amount of payload bytes that have been written to `sent`, and returns whether the body has been written
+    ///
+    /// # Note
+    /// Only plain and buffered TCP stream sources are spliced (e.g. relayed upstream bodies); bytes that have already been
+    /// buffered are written normally first. For all other bodies, nothing is written and `false` is returned.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) fn splice_counted(&mut self, mut stream: &std::net::TcpStream, sent: &mut u64) -> io::Result<bool> {
+        use crate::bytes::splice;
+        use std::io::BufRead;
+
+        // Get the body limit
+        let limit = match (self.framing, self.len) {
+            (Framing::Fixed, Some(len)) => Some(len),
+            (Framing::Close, _) => None,
+            _ => return Ok(false),
+        };
+
+        // Write the bytes that have already been buffered and get the TCP stream
+        let mut copied = 0;
+        let source = match &mut self.source {
+            Source::TcpStream(source) => source,
+            Source::Buffered(buffered) if matches!(buffered.get_ref(), Source::TcpStream(_)) => {
+                let buffer = buffered.buffer();
+                let len =
+                    limit.map_or(buffer.len(), |limit| buffer.len().min(usize::try_from(limit).unwrap_or(usize::MAX)));
+                stream.write_all(&buffer[..len])?;
+                buffered.consume(len);
+                (copied, *sent) = (len as u64, *sent + len as u64);
+
+                // Get the underlying stream
+                let Source::TcpStream(source) = buffered.get_ref() else {
+                    unreachable!("the buffered source is not a TCP stream");
+                };
+                source
+            }
+            _ => return Ok(false),
+        };
+
+        // Splice the remaining bytes
+        copied += splice(source, stream, limit.map(|limit| limit - copied), sent)?;
+
+        // Fail if the source is shorter than the announced length, since the framing is broken anyway
+        if let Some(len) = self.len.filter(|len| self.framing == Framing::Fixed && copied < *len) {
+            let message = format!("body is shorter than the announced length ({copied} < {len})");
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, message));
+        }
+        Ok(true)
+    }
+    /// Writes the body to the given stream using chunked transfer encoding
+    fn write_chunked<T>(&mut self, stream: &mut T, sent: &mut u64) -> io::Result<()>
+    where
+        T: Write,
+    {
+        let mut buf = [0; Self::CHUNK_SIZE_MAX];
+        loop {
+            // Read the next chunk
+            let len = match self.source.read(&mut buf) {
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            // Write the chunk or the terminating zero-length chunk
+            write!(stream, "{len:x}\r\n")?;
+            stream.write_all(&buf[..len])?;
+            stream.write_all(b"\r\n")?;
+            *sent += len as u64;
+            if len == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+impl Default for Body {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+impl From<Data> for Body {
+    fn from(value: Data) -> Self {
+        let len = value.len() as u64;
+        Self::new(Source::from(value), Some(len))
+    }
+}
+impl From<DataChain> for Body {
+    fn from(value: DataChain) -> Self {
+        let len = value.len() as u64;
+        Self::new(Source::from(value), Some(len))
+    }
+}
+impl From<Source> for Body {
+    fn from(value: Source) -> Self {
+        Self::new(value, None)
+    }
+}
+impl From<File> for Body {
+    fn from(value: File) -> Self {
+        Self::new(Source::from(value), None)
+    }
+}
+
+/// A writer that counts the bytes that have been accepted by the underlying writer
+struct CountingWriter<'a, T> {
+    /// The underlying writer
+    inner: &'a mut T,
+    /// The amount of bytes written
+    written: &'a mut u64,
+}
+impl<T> Write for CountingWriter<'_, T>
+where
+    T: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        *self.written += written as u64;
+        Ok(written)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,192 @@
+//! A request body reader with support for chunked transfer encoding and trailers
+
+use crate::{
+    bytes::{Data, Source},
+    http::{parse_header_params, Request},
+};
+use std::io::{self, BufRead, ErrorKind, Read};
+
+/// The state of a body reader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// A fixed-length body with the given amount of remaining bytes
+    Fixed(u64),
+    /// A chunked body that expects the next chunk size line
+    ChunkStart,
+    /// A chunked body within a chunk with the given amount of remaining bytes
+    ChunkData(u64),
+    /// The body has been read completely
+    Done,
+}
+
+/// A reader for a request body that decodes `Transfer-Encoding: chunked` and limits `Content-Length`-framed bodies (see
+/// [`Request::body`])
+///
+/// # Note
+/// For chunked bodies, the trailer fields are parsed after the terminating zero-length chunk and are available via
+/// [`Request::trailers`] once the reader has returned EOF.
+#[derive(Debug)]
+pub struct BodyReader<'a> {
+    /// The connection stream
+    stream: &'a mut Source,
+    /// The reader state
+    state: State,
+    /// The parsed trailer fields of the request
+    trailers: &'a mut Option<Vec<(Data, Data)>>,
+}
+impl<'a> BodyReader<'a> {
+    /// The maximum size of a chunk size line (including chunk extensions)
+    const CHUNK_LINE_SIZE_MAX: u64 = 1024;
+    /// The maximum size of the trailer section
+    const TRAILER_SIZE_MAX: u64 = 4096;
+
+    /// Creates a new body reader for a chunked or fixed-length body
+    pub(crate) fn new(
+        stream: &'a mut Source,
+        chunked: bool,
+        len: u64,
+        trailers: &'a mut Option<Vec<(Data, Data)>>,
+    ) -> Self {
+        let state = match chunked {
+            true => State::ChunkStart,
+            false => State::Fixed(len),
+        };
+        Self { stream, state, trailers }
+    }
+
+    /// Reads a CRLF-terminated line up to the given size limit, or fails if the line is truncated or too long
+    fn read_line(&mut self, limit: u64) -> io::Result<Vec<u8>> {
+        let mut line = Vec::new();
+        (&mut *self.stream).take(limit).read_until(b'\n', &mut line)?;
+        match line.strip_suffix(b"\r\n") {
+            Some(_) => Ok(line),
+            None if line.len() as u64 >= limit => {
+                Err(io::Error::new(ErrorKind::InvalidData, "Chunk or trailer line is too long"))
+            }
+            None => Err(ErrorKind::UnexpectedEof.into()),
+        }
+    }
+    /// Reads and parses a chunk size line
+    fn read_chunk_size(&mut self) -> io::Result<u64> {
+        // Strip the line ending and validate and strip any chunk extensions (which use the header parameter syntax)
+        let line = self.read_line(Self::CHUNK_LINE_SIZE_MAX)?;
+        let line = &line[..line.len() - 2];
+        let extensions = match line.contains(&b';') {
+            true => Some(
+                parse_header_params(line)
+                    .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid chunk extension"))?,
+            ),
+            false => None,
+        };
+        let size = extensions.as_ref().map_or(line.trim_ascii(), |extensions| extensions.value.as_ref());
+
+        // Parse the hex size
+        // Note: `from_str_radix` accepts a leading sign, so the digits are validated explicitly
+        let invalid = || io::Error::new(ErrorKind::InvalidData, "Invalid chunk size");
+        if size.is_empty() || !size.iter().all(u8::is_ascii_hexdigit) {
+            return Err(invalid());
+        }
+        let size = std::str::from_utf8(size).map_err(|_| invalid())?;
+        u64::from_str_radix(size, 16).map_err(|_| invalid())
+    }
+    /// Reads and parses the trailer section after the last chunk
+    fn read_trailers(&mut self) -> io::Result<Vec<(Data, Data)>> {
+        // Read the raw trailer section
+        let mut raw = Vec::new();
+        loop {
+            let limit = Self::TRAILER_SIZE_MAX.saturating_sub(raw.len() as u64).max(2);
+            let line = self.read_line(limit)?;
+            raw.extend_from_slice(&line);
+            if line == b"\r\n" {
+                break;
+            }
+        }
+
+        // Parse the fields
+        let (mut raw, mut trailers) = (Data::from(raw), Vec::new());
+        while !raw.eq(b"\r\n") {
+            let field =
+                <Request>::parse_field(&mut raw).map_err(|e| io::Error::new(ErrorKind::InvalidData, e.error))?;
+            trailers.push(field);
+        }
+        Ok(trailers)
+    }
+}
+impl Read for BodyReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.state {
+                State::Done => return Ok(0),
+                State::Fixed(0) => self.state = State::Done,
+                State::Fixed(remaining) => {
+                    // Read the next bytes
+                    let read = (&mut *self.stream).take(remaining).read(buf)?;
+                    if read == 0 && !buf.is_empty() {
+                        return Err(ErrorKind::UnexpectedEof.into());
+                    }
+                    self.state = State::Fixed(remaining - read as u64);
+                    return Ok(read);
+                }
+                State::ChunkStart => match self.read_chunk_size()? {
+                    0 => {
+                        // Read the trailers of the last chunk
+                        *self.trailers = Some(self.read_trailers()?);
+                        self.state = State::Done;
+                    }
+                    size => self.state = State::ChunkData(size),
+                },
+                State::ChunkData(0) => {
+                    // Consume the chunk terminator
+                    let mut terminator = [0; 2];
+                    self.stream.read_exact(&mut terminator)?;
+                    if terminator != *b"\r\n" {
+                        return Err(io::Error::new(ErrorKind::InvalidData, "Invalid chunk terminator"));
+                    }
+                    self.state = State::ChunkStart;
+                }
+                State::ChunkData(remaining) => {
+                    // Read the next bytes of the chunk
+                    let read = (&mut *self.stream).take(remaining).read(buf)?;
+                    if read == 0 && !buf.is_empty() {
+                        return Err(ErrorKind::UnexpectedEof.into());
+                    }
+                    self.state = State::ChunkData(remaining - read as u64);
+                    return Ok(read);
+                }
+            }
+        }
+    }
+}
+
+/// A body reader that owns its stream (e.g. to stream a chunked or fixed-length upstream body as response body)
+///
+/// # Note
+/// Like [`BodyReader`], a fixed-length body that ends before the announced length fails with `UnexpectedEof`.
+#[derive(Debug)]
+pub(crate) struct OwnedBodyReader {
+    /// The underlying stream
+    stream: Source,
+    /// The reader state
+    state: State,
+    /// The parsed trailer fields
+    trailers: Option<Vec<(Data, Data)>>,
+}
+impl OwnedBodyReader {
+    /// Creates a new owned body reader for a chunked or fixed-length body
+    pub fn new(stream: Source, chunked: bool, len: u64) -> Self {
+        let state = match chunked {
+            true => State::ChunkStart,
+            false => State::Fixed(len),
+        };
+        Self { stream, state, trailers: None }
+    }
+}
+impl Read for OwnedBodyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Borrow a body reader with the current state and keep the updated state
+        let mut reader = BodyReader { stream: &mut self.stream, state: self.state, trailers: &mut self.trailers };
+        let result = reader.read(buf);
+        self.state = reader.state;
+        result
+    }
+}
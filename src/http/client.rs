@@ -0,0 +1,253 @@
+//! A minimal blocking HTTP/1.1 client (e.g. for webhook callbacks and health probes from within handlers)
+
+use crate::{
+    bytes::{Data, Source},
+    error,
+    error::Error,
+    http::{connect, outbound::Signer, Body, BodyReader, OutboundRequest, Response, ResponseExt, StatusCode},
+};
+use std::{
+    io::{self, BufRead, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+/// A kept-alive connection to the server
+#[derive(Debug)]
+struct Connection {
+    /// The writing half
+    tx: TcpStream,
+    /// The buffered reading half
+    rx: Source,
+}
+
+/// A minimal blocking HTTP/1.1 client for a single server with keep-alive
+///
+/// # Note
+/// The response body is read completely into memory (up to the configured limit) and decoded if it is chunked, so that
+/// the connection can be reused for the next request; the response fields are returned as received, except for the
+/// `Content-Length` and `Transfer-Encoding` fields which describe the decoded body. If a kept-alive connection turns out
+/// to be closed by the server before any response bytes have been received, idempotent requests are retried once on a
+/// new connection.
+///
+/// # Example
+/// ```no_run
+/// # use ehttpd::http::Client;
+/// # use std::{io::Read, time::Duration};
+/// let mut client = Client::new("127.0.0.1:8080".parse().expect("invalid address"));
+/// client.set_timeout(Duration::from_secs(5));
+/// let mut response = client.send("GET", "/health", [("Accept", "text/plain")], "")?;
+///
+/// let mut body = Vec::new();
+/// response.body.source.read_to_end(&mut body)?;
+/// # Ok::<(), ehttpd::error::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Client {
+    /// The server addresses
+    addresses: Vec<SocketAddr>,
+    /// The `Host` field for requests
+    host: String,
+    /// The connect, read and write timeout
+    timeout: Duration,
+    /// The maximum size of a response body
+    body_size_max: u64,
+    /// The signer for outbound requests if any
+    signer: Option<Signer>,
+    /// The kept-alive connection if any
+    connection: Option<Connection>,
+}
+impl Client {
+    /// Creates a new client for the given server address with a timeout of 30 seconds and a body size limit of 8 MiB
+    pub fn new(address: SocketAddr) -> Self {
+        Self::with_addresses([address])
+    }
+    /// Creates a new client for a server with multiple addresses (e.g. the IPv6 and IPv4 addresses of a host name), with
+    /// a timeout of 30 seconds and a body size limit of 8 MiB
+    ///
+    /// # Note
+    /// The addresses are raced with staggered connection attempts that alternate between IPv6 and IPv4, so that a broken
+    /// route for one family does not stall the connection. The `Host` field defaults to the first address.
+    pub fn with_addresses<I>(addresses: I) -> Self
+    where
+        I: IntoIterator<Item = SocketAddr>,
+    {
+        let addresses: Vec<_> = addresses.into_iter().collect();
+        Self {
+            host: addresses.first().map(ToString::to_string).unwrap_or_default(),
+            addresses,
+            timeout: Duration::from_secs(30),
+            body_size_max: 8 * 1024 * 1024,
+            signer: None,
+            connection: None,
+        }
+    }
+
+    /// Sets the `Host` field for requests (defaults to the server address)
+    pub fn set_host<T>(&mut self, host: T)
+    where
+        T: ToString,
+    {
+        self.host = host.to_string();
+    }
+    /// Sets the connect, read and write timeout
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+    /// Sets the maximum size of a response body
+    pub fn set_body_size_max(&mut self, body_size_max: u64) {
+        self.body_size_max = body_size_max;
+    }
+    /// Sets a callback that signs or decorates every request before it is sent (e.g. to add an HMAC signature or a
+    /// service authentication field)
+    ///
+    /// # Note
+    /// The callback sees the complete request including the body; if it fails, the request is not sent.
+    pub fn set_signer<F>(&mut self, signer: F)
+    where
+        F: Fn(&mut OutboundRequest) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        self.signer = Some(Signer::new(signer));
+    }
+
+    /// Sends a request with the given method, target, additional header fields and body, and reads the response
+    ///
+    /// # Note
+    /// The `Host` and `Content-Length` fields are set automatically, before the request is passed to the signer if any.
+    pub fn send<M, T, I, K, V, B>(&mut self, method: M, target: T, fields: I, body: B) -> Result<Response, Error>
+    where
+        M: AsRef<[u8]>,
+        T: AsRef<[u8]>,
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+        B: Into<Data>,
+    {
+        // Collect the fields and sign the request
+        let (method, target, body) = (method.as_ref(), target.as_ref(), body.into());
+        let mut head_fields = vec![(Data::from(b"Host"), Data::from(self.host.clone()))];
+        let fields =
+            fields.into_iter().map(|(key, value)| (key.as_ref().to_vec().into(), value.as_ref().to_vec().into()));
+        head_fields.extend(fields);
+        if !body.is_empty() || matches!(method, b"POST" | b"PUT" | b"PATCH") {
+            head_fields.push((Data::from(b"Content-Length"), Data::from(body.len().to_string())));
+        }
+        if let Some(signer) = &self.signer {
+            let mut outbound = OutboundRequest { method, target, fields: &mut head_fields, body: Some(&body) };
+            signer.sign(&mut outbound)?;
+        }
+
+        // Serialize the request
+        let mut request = Vec::with_capacity(256 + body.len());
+        for part in [method, b" ", target, b" HTTP/1.1\r\n"] {
+            request.extend_from_slice(part);
+        }
+        for (key, value) in &head_fields {
+            for part in [key.as_ref(), b": ", value.as_ref(), b"\r\n"] {
+                request.extend_from_slice(part);
+            }
+        }
+        request.extend_from_slice(b"\r\n");
+        request.extend_from_slice(&body);
+
+        // Write the request and retry idempotent requests once if a kept-alive connection has been closed before any
+        // response bytes have been received
+        let idempotent = matches!(method, b"GET" | b"HEAD" | b"PUT" | b"DELETE" | b"OPTIONS");
+        let connection = match self.connection.take() {
+            Some(mut connection) => match Self::write_request(&mut connection, &request) {
+                Err(e) if idempotent && Self::is_stale(&e) => {
+                    let mut connection = self.connect()?;
+                    Self::write_request(&mut connection, &request)?;
+                    connection
+                }
+                result => result.map(|_| connection)?,
+            },
+            None => {
+                let mut connection = self.connect()?;
+                Self::write_request(&mut connection, &request)?;
+                connection
+            }
+        };
+
+        // Read the response
+        self.read_response(connection, method.eq_ignore_ascii_case(b"HEAD"))
+    }
+
+    /// Writes the serialized request and waits until the first response bytes are available
+    fn write_request(connection: &mut Connection, request: &[u8]) -> io::Result<()> {
+        connection.tx.write_all(request)?;
+        connection.tx.flush()?;
+        match connection.rx.fill_buf()? {
+            [] => Err(io::Error::new(ErrorKind::UnexpectedEof, "Connection has been closed before the response")),
+            _ => Ok(()),
+        }
+    }
+    /// Whether the error indicates a kept-alive connection that has been closed by the server
+    fn is_stale(error: &io::Error) -> bool {
+        use ErrorKind::{BrokenPipe, ConnectionAborted, ConnectionReset, UnexpectedEof};
+        matches!(error.kind(), UnexpectedEof | ConnectionReset | ConnectionAborted | BrokenPipe)
+    }
+    /// Reads the response, and keeps the connection alive if possible
+    fn read_response(&mut self, mut connection: Connection, head: bool) -> Result<Response, Error> {
+        // Read the response header and skip interim responses
+        let mut response: Response = loop {
+            let response = Response::read_head(&mut connection.rx)?;
+            match response.status_code()? {
+                StatusCode::SWITCHING_PROTOCOLS => return Err(error!("Protocol upgrades are not supported")),
+                status if status.is_informational() => continue,
+                _ => break response,
+            }
+        };
+
+        // Read the body and update the framing fields to the decoded body
+        let (body, keep_alive) = self.read_body(&mut connection.rx, &response, head)?;
+        if let Some(body) = body {
+            response.set_body(Body::from(Data::from(body)));
+        }
+        if keep_alive && !response.has_connection_close() {
+            self.connection = Some(connection);
+        }
+        Ok(response)
+    }
+    /// Establishes a new connection
+    fn connect(&self) -> Result<Connection, Error> {
+        let stream = connect::connect(&self.addresses, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        stream.set_nodelay(true)?;
+        Ok(Connection { tx: stream.try_clone()?, rx: Source::buffered(stream) })
+    }
+    /// Reads the response body according to the response framing and returns the body if the response has one and
+    /// whether the connection can be kept alive
+    fn read_body(
+        &self,
+        stream: &mut Source,
+        response: &Response,
+        head: bool,
+    ) -> Result<(Option<Vec<u8>>, bool), Error> {
+        // Check for responses without body
+        let status = response.status_code()?;
+        if head || status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED {
+            return Ok((None, true));
+        }
+
+        // Get the framing
+        let transfer_encoding = response.fields.iter().find(|(key, _)| key.eq_ignore_ascii_case(b"Transfer-Encoding"));
+        let (mut trailers, mut body) = (None, Vec::new());
+        let (mut reader, keep_alive): (Box<dyn Read>, _) = match (transfer_encoding, response.content_length()?) {
+            (Some((_, encoding)), _) if encoding.eq_ignore_ascii_case(b"chunked") => {
+                (Box::new(BodyReader::new(stream, true, 0, &mut trailers)), true)
+            }
+            (Some((_, encoding)), _) => return Err(error!("Unsupported transfer encoding: {encoding}")),
+            (None, Some(len)) => (Box::new(BodyReader::new(stream, false, len, &mut trailers)), true),
+            (None, None) => (Box::new(stream), false),
+        };
+
+        // Read the body up to the limit
+        reader.by_ref().take(self.body_size_max.saturating_add(1)).read_to_end(&mut body)?;
+        if body.len() as u64 > self.body_size_max {
+            return Err(error!("Response body is too large"));
+        }
+        Ok((Some(body), keep_alive))
+    }
+}
@@ -0,0 +1,78 @@
+//! Staggered dual-stack connection establishment ("Happy Eyeballs", RFC 8305)
+
+use std::{
+    io::{self, ErrorKind},
+    net::{SocketAddr, TcpStream},
+    sync::mpsc,
+    thread::Builder,
+    time::{Duration, Instant},
+};
+
+/// The delay before the next connection attempt is started if the previous attempt has neither succeeded nor failed
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Connects to the first reachable address within the given timeout
+///
+/// # Note
+/// The addresses are tried in the given order, but alternating between IPv6 and IPv4 (starting with the family of the
+/// first address). A new attempt is started every 250 milliseconds or as soon as the previous attempt fails, and the
+/// first established connection wins; so a broken route for one family does not delay the connection by the whole
+/// timeout.
+pub fn connect(addresses: &[SocketAddr], timeout: Duration) -> io::Result<TcpStream> {
+    // Connect directly if there is nothing to race
+    match addresses {
+        [] => return Err(io::Error::new(ErrorKind::InvalidInput, "No address to connect to")),
+        [address] => return TcpStream::connect_timeout(address, timeout),
+        _ => (),
+    }
+
+    // Start the attempts in interleaved order
+    let (deadline, (tx, rx)) = (Instant::now() + timeout, mpsc::channel());
+    let (mut pending, mut last_error) = (0, None);
+    for address in interleave(addresses) {
+        // Spawn the attempt
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let tx = tx.clone();
+        let builder = Builder::new().name("happy eyeballs connect".to_string());
+        builder.spawn(move || tx.send(TcpStream::connect_timeout(&address, remaining)))?;
+        pending += 1;
+
+        // Wait until the attempt delay has passed or a pending attempt has finished
+        match rx.recv_timeout(ATTEMPT_DELAY.min(remaining)) {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => (pending, last_error) = (pending - 1, Some(e)),
+            Err(_) => (),
+        }
+    }
+
+    // Wait for the remaining attempts
+    // Note: Connections that are established after the winner are closed once their send to the dropped receiver fails
+    while pending > 0 {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => (pending, last_error) = (pending - 1, Some(e)),
+            Err(_) => break,
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::new(ErrorKind::TimedOut, "Connection attempts have timed out")))
+}
+
+/// Orders the addresses so that the address families alternate, starting with the family of the first address
+fn interleave(addresses: &[SocketAddr]) -> Vec<SocketAddr> {
+    // Split the addresses by family
+    let first_is_ipv6 = addresses.first().is_some_and(SocketAddr::is_ipv6);
+    let (first, second): (Vec<_>, Vec<_>) =
+        addresses.iter().copied().partition(|address| address.is_ipv6() == first_is_ipv6);
+
+    // Alternate between the families
+    let mut ordered = Vec::with_capacity(addresses.len());
+    for index in 0..first.len().max(second.len()) {
+        ordered.extend(first.get(index));
+        ordered.extend(second.get(index));
+    }
+    ordered
+}
@@ -0,0 +1,250 @@
+//! Cross-origin resource sharing (CORS)
+
+use crate::{
+    bytes::Data,
+    error,
+    error::Error,
+    http::{Request, RequestExt, Response, ResponseExt, StatusCode},
+};
+use std::time::Duration;
+
+/// A CORS policy that answers preflight requests and decorates the responses to cross-origin requests
+///
+/// # Note
+/// Requests without an `Origin` header field are passed through untouched. Requests from origins that are not allowed
+/// are passed through without CORS header fields (so that the browser blocks the response), and preflight requests from
+/// such origins are answered with `403 Forbidden`. Credentials can only be allowed together with an explicit list of
+/// origins, since echoing any origin would allow every website to read credentialed responses.
+///
+/// # Example
+/// ```
+/// # use ehttpd::http::{Cors, Response, ResponseExt};
+/// # use std::time::Duration;
+/// let mut cors = Cors::new();
+/// cors.allow_origin("https://app.example.org");
+/// cors.set_allowed_methods(["GET", "POST"]);
+/// cors.set_max_age(Duration::from_secs(600));
+/// let handler = cors.wrap(|_| Response::new_200_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cors {
+    /// The allowed origins, or `None` if any origin is allowed
+    origins: Option<Vec<Vec<u8>>>,
+    /// The allowed methods
+    methods: Vec<Vec<u8>>,
+    /// The allowed request header fields, or `None` if any field is allowed
+    headers: Option<Vec<Vec<u8>>>,
+    /// The response header fields that are exposed to the client
+    exposed_headers: Vec<Vec<u8>>,
+    /// Whether credentials (cookies, authorization) are allowed
+    credentials: bool,
+    /// How long the preflight response may be cached
+    max_age: Option<Duration>,
+}
+impl Cors {
+    /// Creates a new policy that allows no origin yet, and the methods `GET`, `HEAD` and `POST`
+    pub fn new() -> Self {
+        Self {
+            origins: Some(Vec::new()),
+            methods: vec![b"GET".to_vec(), b"HEAD".to_vec(), b"POST".to_vec()],
+            headers: Some(Vec::new()),
+            exposed_headers: Vec::new(),
+            credentials: false,
+            max_age: None,
+        }
+    }
+
+    /// Allows the given origin (e.g. `https://app.example.org`)
+    pub fn allow_origin<T>(&mut self, origin: T)
+    where
+        T: AsRef<[u8]>,
+    {
+        if let Some(origins) = &mut self.origins {
+            origins.push(origin.as_ref().to_vec());
+        }
+    }
+    /// Allows any origin
+    ///
+    /// # Note
+    /// This fails if credentials are allowed (see [`Self::set_allow_credentials`]).
+    pub fn allow_any_origin(&mut self) -> Result<(), Error> {
+        if self.credentials {
+            return Err(error!("Cannot allow any origin together with credentials"));
+        }
+        self.origins = None;
+        Ok(())
+    }
+    /// Sets the allowed methods (defaults to `GET`, `HEAD` and `POST`)
+    pub fn set_allowed_methods<I, T>(&mut self, methods: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.methods = methods.into_iter().map(|method| method.as_ref().to_vec()).collect();
+    }
+    /// Sets the allowed request header fields (defaults to none besides the CORS-safelisted fields)
+    pub fn set_allowed_headers<I, T>(&mut self, headers: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.headers = Some(headers.into_iter().map(|header| header.as_ref().to_vec()).collect());
+    }
+    /// Allows any request header field
+    pub fn allow_any_header(&mut self) {
+        self.headers = None;
+    }
+    /// Sets the response header fields that are exposed to the client
+    pub fn set_exposed_headers<I, T>(&mut self, headers: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.exposed_headers = headers.into_iter().map(|header| header.as_ref().to_vec()).collect();
+    }
+    /// Sets whether credentials (cookies, authorization) are allowed (defaults to `false`)
+    ///
+    /// # Note
+    /// This fails if any origin is allowed (see [`Self::allow_any_origin`]); credentials require an explicit list of
+    /// origins.
+    pub fn set_allow_credentials(&mut self, allow: bool) -> Result<(), Error> {
+        if allow && self.origins.is_none() {
+            return Err(error!("Cannot allow credentials together with any origin"));
+        }
+        self.credentials = allow;
+        Ok(())
+    }
+    /// Sets how long the preflight response may be cached by the client
+    pub fn set_max_age(&mut self, max_age: Duration) {
+        self.max_age = Some(max_age);
+    }
+
+    /// Wraps a `request->response`-handler so that preflight requests are answered and the responses to cross-origin
+    /// requests are decorated
+    pub fn wrap<F>(self, handler: F) -> impl Fn(Request) -> Response + Send + Sync + 'static
+    where
+        F: Fn(Request) -> Response + Send + Sync + 'static,
+    {
+        move |request: Request| {
+            // Answer preflight requests
+            if let Some(response) = self.preflight(&request) {
+                return response;
+            }
+
+            // Handle the request and decorate the response
+            let origin = request.field("Origin").cloned();
+            let mut response = handler(request);
+            response.resolve();
+            match origin {
+                Some(origin) => self.decorate(&origin, &mut response),
+                // Vary on the origin if the response to a cross-origin request would differ
+                None if self.origins.is_some() => Self::vary_origin(&mut response),
+                None => (),
+            }
+            response
+        }
+    }
+
+    /// Answers the request if it is a preflight request (an `OPTIONS` request with `Origin` and
+    /// `Access-Control-Request-Method`)
+    pub fn preflight(&self, request: &Request) -> Option<Response> {
+        // Check if the request is a preflight request
+        let (Some(origin), Some(method)) = (request.field("Origin"), request.field("Access-Control-Request-Method"))
+        else {
+            return None;
+        };
+        if !request.method.eq(b"OPTIONS") {
+            return None;
+        }
+
+        // Validate the origin, method and header fields
+        let mut requested_headers =
+            (request.field("Access-Control-Request-Headers").map(|headers| headers.split(|byte| *byte == b',')))
+                .into_iter()
+                .flatten()
+                .map(|header| header.trim_ascii())
+                .filter(|header| !header.is_empty());
+        let allowed = self.is_allowed_origin(origin)
+            && self.methods.iter().any(|allowed| allowed == method.as_ref())
+            && requested_headers.all(|header| self.is_allowed_header(header));
+        if !allowed {
+            return Some(Response::new_403_forbidden());
+        }
+
+        // Create the preflight response
+        let mut response = Response::new(StatusCode::NO_CONTENT);
+        self.set_origin(origin, &mut response);
+        response.set_field("Access-Control-Allow-Methods", self.methods.join(&b", "[..]));
+        let headers = match &self.headers {
+            Some(headers) => headers.join(&b", "[..]),
+            None => {
+                (request.field("Access-Control-Request-Headers").map(|headers| headers.to_vec())).unwrap_or_default()
+            }
+        };
+        if !headers.is_empty() {
+            response.set_field("Access-Control-Allow-Headers", headers);
+        }
+        if let Some(max_age) = self.max_age {
+            response.set_field("Access-Control-Max-Age", max_age.as_secs().to_string());
+        }
+        Some(response)
+    }
+    /// Decorates the response to a request from the given origin with the CORS header fields if the origin is allowed
+    pub fn decorate(&self, origin: &Data, response: &mut Response) {
+        // Always vary on the origin, since the response depends on it
+        Self::vary_origin(response);
+        if !self.is_allowed_origin(origin) {
+            return;
+        }
+
+        // Set the header fields
+        self.set_origin(origin, response);
+        if !self.exposed_headers.is_empty() {
+            response.set_field("Access-Control-Expose-Headers", self.exposed_headers.join(&b", "[..]));
+        }
+    }
+
+    /// Whether the given origin is allowed
+    fn is_allowed_origin(&self, origin: &[u8]) -> bool {
+        match &self.origins {
+            Some(origins) => origins.iter().any(|allowed| allowed.eq_ignore_ascii_case(origin)),
+            None => true,
+        }
+    }
+    /// Whether the given request header field is allowed
+    fn is_allowed_header(&self, header: &[u8]) -> bool {
+        match &self.headers {
+            Some(headers) => headers.iter().any(|allowed| allowed.eq_ignore_ascii_case(header)),
+            None => true,
+        }
+    }
+    /// Sets the allowed origin and the credentials flag
+    fn set_origin(&self, origin: &Data, response: &mut Response) {
+        match self.origins.is_none() {
+            true => response.set_field("Access-Control-Allow-Origin", "*"),
+            false => response.set_field("Access-Control-Allow-Origin", origin.clone()),
+        }
+        if self.credentials {
+            response.set_field("Access-Control-Allow-Credentials", "true");
+        }
+        Self::vary_origin(response);
+    }
+    /// Adds `Origin` to the `Vary` header field
+    fn vary_origin(response: &mut Response) {
+        // Append the origin to an existing field
+        let vary = response.fields.iter_mut().find(|(key, _)| key.eq_ignore_ascii_case(b"Vary"));
+        let Some((_, value)) = vary else {
+            response.fields.push((Data::from(b"Vary"), Data::from(b"Origin")));
+            return;
+        };
+        let mut fields = value.split(|byte| *byte == b',');
+        if !fields.any(|field| field.trim_ascii().eq_ignore_ascii_case(b"Origin")) {
+            *value = Data::from([value.as_ref(), b", Origin"].concat());
+        }
+    }
+}
+impl Default for Cors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,75 @@
+//! Deferred responses that are completed by another thread
+
+use crate::http::{Response, ResponseExt, StatusCode};
+use flume::{Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+/// A response that is produced later by another thread, e.g. a background job system
+///
+/// # Note
+/// The handler returns [`Deferred::into_response`], a response that carries the deferred state. It is resolved via
+/// [`Response::resolve`], which holds the connection until the response is completed via the [`Completer`], or answers
+/// with `504 Gateway Timeout` if the timeout expires (or `500 Internal Server Error` if the completer is dropped).
+/// [`crate::reqresp`] and the wrappers that inspect the response of an inner handler resolve it, so they always see the
+/// completed response. The worker waits on a channel, so no mutex or polling is involved.
+///
+/// # Example
+/// ```
+/// # use ehttpd::http::{Deferred, Request, Response, ResponseExt};
+/// # use std::{thread, time::Duration};
+/// let handler = |_: Request| {
+///     let (deferred, completer) = Deferred::new(Duration::from_secs(30));
+///     thread::spawn(move || completer.complete(Response::new_200_ok()));
+///     deferred.into_response()
+/// };
+/// ```
+#[derive(Debug)]
+pub struct Deferred {
+    /// The receiving half of the completion channel
+    rx: Receiver<Response>,
+    /// The deadline for the completion
+    deadline: Instant,
+}
+impl Deferred {
+    /// Creates a new deferred response that must be completed within the given timeout
+    pub fn new(timeout: Duration) -> (Self, Completer) {
+        let (tx, rx) = flume::bounded(1);
+        let deadline = Instant::now() + timeout;
+        (Self { rx, deadline }, Completer { tx })
+    }
+
+    /// Turns `self` into a response that is replaced by the completed response once it is resolved
+    ///
+    /// # Note
+    /// Until the response is resolved via [`Response::resolve`], it is a `500 Internal Server Error` response.
+    pub fn into_response(self) -> Response {
+        let mut response = Response::new_500_internalservererror();
+        response.body.deferred = Some(self);
+        response
+    }
+
+    /// Waits until the response is completed or the deadline has passed
+    pub(crate) fn wait(self) -> Response {
+        match self.rx.recv_deadline(self.deadline) {
+            Ok(response) => response,
+            Err(RecvTimeoutError::Timeout) => Response::new(StatusCode::GATEWAY_TIMEOUT),
+            Err(RecvTimeoutError::Disconnected) => Response::new_500_internalservererror(),
+        }
+    }
+}
+
+/// The completing half of a deferred response, which can be sent to another thread
+#[derive(Debug, Clone)]
+pub struct Completer {
+    /// The sending half of the completion channel
+    tx: Sender<Response>,
+}
+impl Completer {
+    /// Completes the deferred response
+    ///
+    /// # Note
+    /// If the deadline has already passed or the response has already been completed, the response is discarded.
+    pub fn complete(self, response: Response) {
+        let _ = self.tx.try_send(response);
+    }
+}
@@ -0,0 +1,164 @@
+//! Streaming body digests and the validation of the digest header fields
+
+use crate::{
+    bytes::DataParseExt,
+    error,
+    error::Error,
+    http::{base64, Request, RequestExt},
+};
+use std::io::{self, Read};
+
+/// A pluggable digest algorithm (e.g. SHA-256 from a crypto crate of your choice)
+pub trait DigestAlgorithm {
+    /// The algorithm name as used in the digest header fields (e.g. `sha-256` or `md5`)
+    fn name(&self) -> &str;
+    /// Feeds the given data into the digest
+    fn update(&mut self, data: &[u8]);
+    /// The digest over all data fed so far
+    fn finalize(&self) -> Vec<u8>;
+}
+
+/// A CRC32C checksum (Castagnoli polynomial), which is registered as `crc32c` for the digest header fields
+///
+/// # Note
+/// CRC32C only detects accidental corruption; use a cryptographic digest if the body may have been tampered with.
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32c {
+    /// The current (inverted) CRC state
+    state: u32,
+}
+impl Crc32c {
+    /// The reflected Castagnoli polynomial
+    const POLYNOMIAL: u32 = 0x82F6_3B78;
+
+    /// Creates a new checksum
+    pub const fn new() -> Self {
+        Self { state: !0 }
+    }
+
+    /// The current checksum value
+    pub fn value(&self) -> u32 {
+        !self.state
+    }
+}
+impl Default for Crc32c {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl DigestAlgorithm for Crc32c {
+    fn name(&self) -> &str {
+        "crc32c"
+    }
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state ^= u32::from(*byte);
+            for _ in 0..8 {
+                let mask = (self.state & 1).wrapping_neg();
+                self.state = (self.state >> 1) ^ (Self::POLYNOMIAL & mask);
+            }
+        }
+    }
+    fn finalize(&self) -> Vec<u8> {
+        self.value().to_be_bytes().to_vec()
+    }
+}
+
+/// A reader that computes a digest over all data read from the underlying reader, so that an uploaded body can be
+/// verified while it is consumed
+#[derive(Debug)]
+pub struct DigestReader<R, D> {
+    /// The underlying reader
+    inner: R,
+    /// The digest
+    digest: D,
+}
+impl<R, D> DigestReader<R, D> {
+    /// Wraps the given reader
+    pub fn new(inner: R, digest: D) -> Self {
+        Self { inner, digest }
+    }
+
+    /// The digest over all data read so far
+    pub fn digest(&self) -> &D {
+        &self.digest
+    }
+    /// Returns the underlying reader and the digest
+    pub fn into_inner(self) -> (R, D) {
+        (self.inner, self.digest)
+    }
+
+    /// Verifies the digest over all data read so far against the expected digest
+    ///
+    /// # Note
+    /// Since the reader usually borrows the request stream, the expected digest should be taken from the request via
+    /// [`expected_digest`] before the body is read.
+    pub fn verify(&self, expected: &[u8]) -> Result<(), Error>
+    where
+        D: DigestAlgorithm,
+    {
+        if expected != self.digest.finalize() {
+            return Err(error!("Mismatching {} digest", self.digest.name()));
+        }
+        Ok(())
+    }
+}
+impl<R, D> Read for DigestReader<R, D>
+where
+    R: Read,
+    D: DigestAlgorithm,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.digest.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+/// Gets the expected digest for the given algorithm (case-insensitive) from the request's digest fields if any
+///
+/// # Note
+/// The fields are searched in the following order:
+/// 1. `Content-Digest` and `Repr-Digest` (RFC 9530; e.g. `sha-256=:<base64>:`)
+/// 2. `Digest` (RFC 3230; e.g. `sha-256=<base64>`)
+/// 3. `Content-MD5` (RFC 1864; only for the `md5` algorithm)
+pub fn expected_digest(request: &Request, algorithm: &str) -> Result<Option<Vec<u8>>, Error> {
+    for field in ["Content-Digest", "Repr-Digest", "Digest"] {
+        // Get the field if any
+        let Some(value) = request.field(field) else {
+            continue;
+        };
+
+        // Find the algorithm within the dictionary
+        for member in value.split(|byte| *byte == b',') {
+            let Some((name, encoded)) = split_member(member) else {
+                return Err(error!("Invalid {field} field"));
+            };
+            if !name.eq_ignore_ascii_case(algorithm.as_bytes()) {
+                continue;
+            }
+
+            // Decode the digest and strip the byte-sequence delimiters of structured fields
+            let encoded = encoded.strip_prefix(b":").and_then(|encoded| encoded.strip_suffix(b":")).unwrap_or(encoded);
+            let digest = base64::decode(encoded).ok_or_else(|| error!("Invalid {field} field"))?;
+            return Ok(Some(digest));
+        }
+    }
+
+    // Fall back to `Content-MD5`
+    match request.field("Content-MD5") {
+        Some(value) if algorithm.eq_ignore_ascii_case("md5") => {
+            let digest = base64::decode(&value.trimmed()).ok_or_else(|| error!("Invalid Content-MD5 field"))?;
+            Ok(Some(digest))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Splits a dictionary member into the trimmed name and value
+fn split_member(member: &[u8]) -> Option<(&[u8], &[u8])> {
+    let member = member.trim_ascii();
+    let separator = member.iter().position(|byte| *byte == b'=')?;
+    let (name, value) = member.split_at(separator);
+    Some((name.trim_ascii(), value[1..].trim_ascii()))
+}
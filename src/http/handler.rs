@@ -0,0 +1,114 @@
+//! Composable `request->response`-handlers
+
+use crate::http::{Request, Response, ResponseExt};
+
+/// A composable `request->response`-handler that may decline a request so that another handler can handle it instead
+///
+/// # Note
+/// This trait is implemented for all `Fn(Request) -> Response` closures, which never decline a request.
+pub trait Handler: Send + Sync + 'static {
+    /// Handles the request, or gives it back if the handler declines to handle it
+    #[allow(clippy::result_large_err)]
+    fn handle<'a>(&self, request: Request<'a>) -> Result<Response, Request<'a>>;
+
+    /// Passes all requests that are declined by `self` to `other`
+    fn or_else<O>(self, other: O) -> OrElse<Self, O>
+    where
+        Self: Sized,
+        O: Handler,
+    {
+        OrElse { handler: self, other }
+    }
+    /// Maps all responses of `self` with the given function
+    fn map_response<M>(self, map: M) -> MapResponse<Self, M>
+    where
+        Self: Sized,
+        M: Fn(Response) -> Response + Send + Sync + 'static,
+    {
+        MapResponse { handler: self, map }
+    }
+    /// Declines all requests that do not match the given predicate
+    fn filter<P>(self, predicate: P) -> Filter<Self, P>
+    where
+        Self: Sized,
+        P: Fn(&Request) -> bool + Send + Sync + 'static,
+    {
+        Filter { handler: self, predicate }
+    }
+
+    /// Turns `self` into a plain `request->response`-handler that answers all declined requests with `404 Not Found`
+    fn into_fn(self) -> impl Fn(Request) -> Response + Send + Sync + 'static
+    where
+        Self: Sized,
+    {
+        move |request: Request| self.handle(request).unwrap_or_else(|_| Response::new_404_notfound())
+    }
+}
+impl<F> Handler for F
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    fn handle<'a>(&self, request: Request<'a>) -> Result<Response, Request<'a>> {
+        Ok(self(request))
+    }
+}
+
+/// A handler that passes all requests that are declined by the first handler to the second handler
+#[derive(Debug, Clone)]
+pub struct OrElse<H, O> {
+    /// The first handler
+    handler: H,
+    /// The fallback handler
+    other: O,
+}
+impl<H, O> Handler for OrElse<H, O>
+where
+    H: Handler,
+    O: Handler,
+{
+    #[allow(clippy::result_large_err)]
+    fn handle<'a>(&self, request: Request<'a>) -> Result<Response, Request<'a>> {
+        self.handler.handle(request).or_else(|request| self.other.handle(request))
+    }
+}
+
+/// A handler that maps all responses of the underlying handler
+#[derive(Debug, Clone)]
+pub struct MapResponse<H, M> {
+    /// The underlying handler
+    handler: H,
+    /// The mapping function
+    map: M,
+}
+impl<H, M> Handler for MapResponse<H, M>
+where
+    H: Handler,
+    M: Fn(Response) -> Response + Send + Sync + 'static,
+{
+    fn handle<'a>(&self, request: Request<'a>) -> Result<Response, Request<'a>> {
+        let mut response = self.handler.handle(request)?;
+        response.resolve();
+        Ok((self.map)(response))
+    }
+}
+
+/// A handler that declines all requests that do not match a predicate
+#[derive(Debug, Clone)]
+pub struct Filter<H, P> {
+    /// The underlying handler
+    handler: H,
+    /// The predicate
+    predicate: P,
+}
+impl<H, P> Handler for Filter<H, P>
+where
+    H: Handler,
+    P: Fn(&Request) -> bool + Send + Sync + 'static,
+{
+    fn handle<'a>(&self, request: Request<'a>) -> Result<Response, Request<'a>> {
+        match (self.predicate)(&request) {
+            true => self.handler.handle(request),
+            false => Err(request),
+        }
+    }
+}
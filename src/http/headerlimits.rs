@@ -0,0 +1,41 @@
+//! Runtime limits for request headers
+
+use std::time::Duration;
+
+/// Runtime limits for parsing request headers (see [`crate::http::Request::from_stream_with_limits`])
+///
+/// # Note
+/// Requests that exceed a limit are rejected as parse failure and counted as
+/// [`crate::http::ParseFailure::HeaderTooLarge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HeaderLimits {
+    /// The maximum size of the entire header, including the start line and the terminating empty line
+    pub size_max: usize,
+    /// The maximum size of a single header field line
+    pub field_size_max: usize,
+    /// The maximum amount of header fields
+    pub field_count_max: usize,
+    /// The deadline to receive the entire header, starting when the server begins to wait for the request, if any
+    ///
+    /// # Note
+    /// This bounds the time a slowly trickling client can occupy a worker. If the deadline is exceeded after the first
+    /// header byte has been received, the request is answered with `408 Request Timeout` by [`crate::reqresp`]; idle
+    /// connections are closed silently.
+    pub timeout: Option<Duration>,
+}
+impl HeaderLimits {
+    /// The default maximum amount of header fields
+    const FIELD_COUNT_MAX: usize = 100;
+
+    /// Creates new limits with the given total header size, which also limits the size of a single field, and a default
+    /// maximum of 100 fields without deadline
+    pub const fn new(size_max: usize) -> Self {
+        Self { size_max, field_size_max: size_max, field_count_max: Self::FIELD_COUNT_MAX, timeout: None }
+    }
+}
+impl Default for HeaderLimits {
+    fn default() -> Self {
+        Self::new(4096)
+    }
+}
@@ -0,0 +1,183 @@
+//! A header field map with fast case-insensitive lookup
+
+use crate::bytes::Data;
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    mem,
+};
+
+/// A header field map with case-insensitive lookup, multi-value fields and preserved insertion order
+///
+/// # Note
+/// Lookups are `O(1)` via an index of case-insensitive name hashes over the fields and do not allocate; removals are
+/// `O(n)` since they rebuild the index. The map converts from and into the `Vec<(Data, Data)>` fields of
+/// [`crate::http::Request`] and [`crate::http::Response`].
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMap {
+    /// The fields in insertion order
+    fields: Vec<(Data, Data)>,
+    /// The field positions by case-insensitive name hash
+    ///
+    /// # Note
+    /// Names with colliding hashes share the same entry, so the positions must be filtered by name.
+    index: HashMap<u64, Vec<usize>>,
+    /// The hasher state for the name hashes
+    state: RandomState,
+}
+impl HeaderMap {
+    /// Creates a new empty header map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The amount of fields
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+    /// Whether the map is empty or not
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+    /// Whether the map contains a field with the given name or not
+    pub fn contains<N>(&self, name: N) -> bool
+    where
+        N: AsRef<[u8]>,
+    {
+        self.positions(name.as_ref()).next().is_some()
+    }
+
+    /// Gets the value of the first field with the given name
+    pub fn get<N>(&self, name: N) -> Option<&Data>
+    where
+        N: AsRef<[u8]>,
+    {
+        let position = self.positions(name.as_ref()).next()?;
+        Some(&self.fields[position].1)
+    }
+    /// Gets the values of all fields with the given name in insertion order
+    pub fn get_all<N>(&self, name: N) -> impl Iterator<Item = &Data>
+    where
+        N: AsRef<[u8]>,
+    {
+        let positions = self.index.get(&self.hash(name.as_ref())).map(Vec::as_slice).unwrap_or_default();
+        let positions =
+            positions.iter().filter(move |position| self.fields[**position].0.eq_ignore_ascii_case(name.as_ref()));
+        positions.map(|position| &self.fields[*position].1)
+    }
+    /// Iterates over all fields in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &(Data, Data)> {
+        self.fields.iter()
+    }
+
+    /// Appends a field, keeping all existing fields with the same name (e.g. for `Set-Cookie`)
+    pub fn append<K, V>(&mut self, name: K, value: V)
+    where
+        K: Into<Data>,
+        V: Into<Data>,
+    {
+        let name = name.into();
+        let positions = self.index.entry(self.hash(&name)).or_default();
+        positions.push(self.fields.len());
+        self.fields.push((name, value.into()));
+    }
+    /// Sets a field, replacing all existing fields with the same name
+    ///
+    /// # Note
+    /// The field keeps the position of the first replaced field, or is appended if there is no such field.
+    pub fn insert<K, V>(&mut self, name: K, value: V)
+    where
+        K: Into<Data>,
+        V: Into<Data>,
+    {
+        // Append the field if it is new
+        let name = name.into();
+        let positions: Vec<_> = self.positions(&name).collect();
+        let Some((first, redundant)) = positions.split_first() else {
+            return self.append(name, value);
+        };
+
+        // Replace the first field and remove the others back to front so that the positions stay valid
+        self.fields[*first] = (name, value.into());
+        if !redundant.is_empty() {
+            for position in redundant.iter().rev() {
+                self.fields.remove(*position);
+            }
+            self.reindex();
+        }
+    }
+    /// Removes all fields with the given name and returns their values
+    pub fn remove<N>(&mut self, name: N) -> Vec<Data>
+    where
+        N: AsRef<[u8]>,
+    {
+        // Check if there are any fields to remove
+        let name = name.as_ref();
+        if !self.contains(name) {
+            return Vec::new();
+        }
+
+        // Remove the fields
+        let (removed, fields): (Vec<_>, Vec<_>) =
+            mem::take(&mut self.fields).into_iter().partition(|(key, _)| key.eq_ignore_ascii_case(name));
+        self.fields = fields;
+        self.reindex();
+        removed.into_iter().map(|(_, value)| value).collect()
+    }
+
+    /// The positions of all fields with the given name in insertion order
+    fn positions<'a>(&'a self, name: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let positions = self.index.get(&self.hash(name)).map(Vec::as_slice).unwrap_or_default();
+        let positions = positions.iter().copied();
+        positions.filter(move |position| self.fields[*position].0.eq_ignore_ascii_case(name))
+    }
+    /// Computes the case-insensitive hash of the given name without allocating
+    fn hash(&self, name: &[u8]) -> u64 {
+        let mut hasher = self.state.build_hasher();
+        for byte in name {
+            hasher.write_u8(byte.to_ascii_lowercase());
+        }
+        hasher.finish()
+    }
+    /// Rebuilds the index from the fields
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (position, (key, _)) in self.fields.iter().enumerate() {
+            let hash = self.hash(key);
+            self.index.entry(hash).or_default().push(position);
+        }
+    }
+}
+impl From<Vec<(Data, Data)>> for HeaderMap {
+    fn from(fields: Vec<(Data, Data)>) -> Self {
+        let mut this = Self { fields, ..Default::default() };
+        this.reindex();
+        this
+    }
+}
+impl From<HeaderMap> for Vec<(Data, Data)> {
+    fn from(map: HeaderMap) -> Self {
+        map.fields
+    }
+}
+impl<K, V> FromIterator<(K, V)> for HeaderMap
+where
+    K: Into<Data>,
+    V: Into<Data>,
+{
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut this = Self::new();
+        for (name, value) in iter {
+            this.append(name, value);
+        }
+        this
+    }
+}
+impl IntoIterator for HeaderMap {
+    type Item = (Data, Data);
+    type IntoIter = std::vec::IntoIter<(Data, Data)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.into_iter()
+    }
+}
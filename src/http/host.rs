@@ -0,0 +1,131 @@
+//! A normalized HTTP host
+
+use crate::{error, error::Error};
+use std::{
+    fmt::{self, Display, Formatter},
+    net::{IpAddr, Ipv6Addr},
+    str::{self, FromStr},
+};
+
+/// A normalized host with an optional port, e.g. from a `Host` header field or a configured hostname
+///
+/// # Normalization
+/// Hostnames are lowercased and a trailing dot is removed; IP addresses are converted into their canonical form and IPv6
+/// addresses are stored without brackets. An empty port (e.g. `example.org:`) is treated like a missing port.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Host {
+    /// The normalized hostname or IP address
+    pub name: String,
+    /// The port if any
+    pub port: Option<u16>,
+}
+impl Host {
+    /// Parses and normalizes a host
+    ///
+    /// # Note
+    /// Bare IPv6 addresses without brackets are accepted for convenience when parsing configured hostnames; they cannot
+    /// carry a port.
+    pub fn parse<T>(raw: T) -> Result<Self, Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        // Split the host into name and port
+        let raw = str::from_utf8(raw.as_ref())?.trim();
+        let (name, port) = if let Some(bracketed) = raw.strip_prefix('[') {
+            // Split a bracketed IPv6 address
+            let Some((address, port)) = bracketed.split_once(']') else {
+                return Err(error!("Unterminated IPv6 host: {raw}"));
+            };
+            let Ok(address) = Ipv6Addr::from_str(address) else {
+                return Err(error!("Invalid IPv6 host: {raw}"));
+            };
+            match port {
+                "" => (address.to_string(), ""),
+                port => match port.strip_prefix(':') {
+                    Some(port) => (address.to_string(), port),
+                    None => return Err(error!("Invalid host: {raw}")),
+                },
+            }
+        } else if raw.matches(':').count() > 1 {
+            // Accept bare IPv6 addresses
+            let Ok(address) = Ipv6Addr::from_str(raw) else {
+                return Err(error!("Invalid IPv6 host: {raw}"));
+            };
+            (address.to_string(), "")
+        } else {
+            // Split a hostname or IPv4 address
+            let (name, port) = raw.split_once(':').unwrap_or((raw, ""));
+            (Self::normalize_name(name)?, port)
+        };
+
+        // Parse the port
+        let port = match port {
+            "" => None,
+            port => Some(port.parse()?),
+        };
+        Ok(Self { name, port })
+    }
+
+    /// Whether `self` refers to the same host as `other`
+    ///
+    /// # Note
+    /// Missing ports are replaced with the given default port (e.g. `80` for `http` or `443` for `https`) before
+    /// comparison, so `example.org` matches `example.org:80` if the default port is `80`.
+    pub fn matches(&self, other: &Self, default_port: u16) -> bool {
+        self.name == other.name && self.port.unwrap_or(default_port) == other.port.unwrap_or(default_port)
+    }
+    /// Whether `self` has the given hostname, ignoring the port
+    ///
+    /// # Note
+    /// The hostname is normalized before comparison; invalid hostnames never match.
+    pub fn is_name<T>(&self, name: T) -> bool
+    where
+        T: AsRef<[u8]>,
+    {
+        match Self::parse(name) {
+            Ok(Self { name, port: None }) => self.name == name,
+            _ => false,
+        }
+    }
+
+    /// Validates and normalizes a hostname or IPv4 address
+    fn normalize_name(name: &str) -> Result<String, Error> {
+        // Remove the trailing dot of a fully-qualified name
+        let name = name.strip_suffix('.').unwrap_or(name);
+        if name.is_empty() {
+            return Err(error!("Empty hostname"));
+        }
+
+        // Validate the hostname
+        let is_valid = |char: char| char.is_ascii_alphanumeric() || matches!(char, '-' | '.' | '_');
+        if !name.chars().all(is_valid) {
+            return Err(error!("Invalid hostname: {name}"));
+        }
+
+        // Canonicalize IP addresses and lowercase names
+        match IpAddr::from_str(name) {
+            Ok(address) => Ok(address.to_string()),
+            Err(_) => Ok(name.to_ascii_lowercase()),
+        }
+    }
+}
+impl FromStr for Host {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+impl Display for Host {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // Bracket IPv6 addresses
+        match self.name.contains(':') {
+            true => write!(f, "[{}]", self.name)?,
+            false => write!(f, "{}", self.name)?,
+        }
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,330 @@
+//! Request deduplication via `Idempotency-Key`
+
+use crate::{
+    bytes::{Data, Source},
+    http::{Request, RequestExt, Response, ResponseExt},
+};
+use std::{
+    collections::{hash_map::RandomState, HashMap, VecDeque},
+    fmt::{self, Debug, Formatter},
+    hash::BuildHasher,
+    io::Read,
+    mem,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// A stored header field
+type StoredField = (Arc<[u8]>, Arc<[u8]>);
+
+/// A stored response
+#[derive(Debug)]
+struct Stored {
+    /// The HTTP version
+    version: Arc<[u8]>,
+    /// The response status code
+    status: Arc<[u8]>,
+    /// The response status reason
+    reason: Arc<[u8]>,
+    /// The response header fields
+    fields: Vec<StoredField>,
+    /// The response body
+    body: Arc<Vec<u8>>,
+}
+
+/// An entry in the response store
+#[derive(Debug)]
+enum Entry {
+    /// The request with the given body fingerprint is currently being handled
+    InFlight { fingerprint: u64 },
+    /// The response for the request with the given body fingerprint has been stored until the given deadline
+    Done { stored: Arc<Stored>, fingerprint: u64, expires: Instant },
+}
+
+/// The response store
+#[derive(Debug, Default)]
+struct Store {
+    /// The entries by scoped key
+    entries: HashMap<Vec<u8>, Entry>,
+    /// The expiry deadlines of the stored responses in insertion order, which is also the expiry order since all
+    /// responses have the same time-to-live
+    expiries: VecDeque<(Instant, Vec<u8>)>,
+}
+impl Store {
+    /// Removes the expired responses
+    fn purge(&mut self, now: Instant) {
+        while let Some((expires, _)) = self.expiries.front().filter(|(expires, _)| *expires <= now) {
+            // Remove the entry unless it has been replaced in the meantime
+            let expires = *expires;
+            let (_, key) = self.expiries.pop_front().expect("missing expiry");
+            if matches!(self.entries.get(&key), Some(Entry::Done { expires: current, .. }) if *current == expires) {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+/// A response store that replays the response for retries of requests with the same `Idempotency-Key`
+///
+/// # Note
+/// Keys are scoped to the client (see [`Self::wrap`]), the request method and the target, so that a client cannot
+/// replay the responses of another client by reusing or guessing its key. The request body is buffered up to the
+/// configured limit and fingerprinted; retries with a different body are answered with `422 Unprocessable Content`, and
+/// retries that arrive while the original request is still being handled are answered with `409 Conflict`. Server
+/// errors (`5xx`) and responses with a body larger than the configured limit are not stored, so that the request can be
+/// retried.
+///
+/// # Example
+/// ```
+/// # use ehttpd::http::{Idempotency, Request, RequestExt, Response, ResponseExt};
+/// # use std::time::Duration;
+/// let idempotency = Idempotency::new(Duration::from_secs(24 * 60 * 60));
+/// let scope = |request: &Request| Some(request.field("Authorization")?.to_vec());
+/// let handler = idempotency.wrap(scope, |_| Response::new_200_ok());
+/// ```
+pub struct Idempotency {
+    /// The time-to-live of stored responses
+    ttl: Duration,
+    /// The maximum amount of stored responses
+    capacity: usize,
+    /// The maximum size of a stored response body
+    body_size_max: usize,
+    /// The maximum size of a fingerprinted request body
+    request_size_max: usize,
+    /// The hasher for request body fingerprints
+    hasher: RandomState,
+    /// The response store
+    store: Mutex<Store>,
+}
+impl Idempotency {
+    /// The default maximum amount of stored responses
+    const CAPACITY_DEFAULT: usize = 4096;
+    /// The default maximum size of a stored response body
+    const BODY_SIZE_MAX_DEFAULT: usize = 64 * 1024;
+    /// The default maximum size of a fingerprinted request body
+    const REQUEST_SIZE_MAX_DEFAULT: usize = 64 * 1024;
+
+    /// Creates a new response store where responses expire after the given time-to-live
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            capacity: Self::CAPACITY_DEFAULT,
+            body_size_max: Self::BODY_SIZE_MAX_DEFAULT,
+            request_size_max: Self::REQUEST_SIZE_MAX_DEFAULT,
+            hasher: RandomState::new(),
+            store: Mutex::default(),
+        }
+    }
+
+    /// Sets the maximum amount of stored responses (defaults to `4096`)
+    ///
+    /// # Note
+    /// If the store is full, requests are handled without deduplication until entries expire.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+    }
+    /// Sets the maximum size of a stored response body (defaults to 64 KiB)
+    pub fn set_body_size_max(&mut self, size: usize) {
+        self.body_size_max = size;
+    }
+    /// Sets the maximum size of a request body with idempotency key (defaults to 64 KiB)
+    ///
+    /// # Note
+    /// Larger request bodies cannot be fingerprinted and are answered with `413 Payload Too Large`.
+    pub fn set_request_size_max(&mut self, size: usize) {
+        self.request_size_max = size;
+    }
+
+    /// Wraps a `request->response`-handler so that retries with the same `Idempotency-Key` are answered with the stored
+    /// response instead of being passed to the handler again
+    ///
+    /// # Note
+    /// `scope` identifies the client, e.g. the authenticated principal or the peer address; requests without scope are
+    /// passed through without deduplication.
+    pub fn wrap<S, F>(self, scope: S, handler: F) -> impl Fn(Request) -> Response + Send + Sync + 'static
+    where
+        S: Fn(&Request) -> Option<Vec<u8>> + Send + Sync + 'static,
+        F: Fn(Request) -> Response + Send + Sync + 'static,
+    {
+        move |mut request: Request| {
+            // Requests without idempotency key or scope are passed through
+            let Some(key) = Self::scoped_key(&request, &scope) else {
+                return handler(request);
+            };
+
+            // Buffer and fingerprint the request body
+            let mut body = Vec::new();
+            let limit = self.request_size_max as u64 + 1;
+            let read = request.body().and_then(|reader| Ok(reader.take(limit).read_to_end(&mut body)?));
+            match read {
+                Err(_) => return Response::new_status_reason(400, "Bad Request"),
+                Ok(_) if body.len() > self.request_size_max => {
+                    return Response::new_status_reason(413, "Payload Too Large")
+                }
+                Ok(_) => (),
+            }
+            let fingerprint = self.hasher.hash_one(&body);
+            let mut body = Source::from(body);
+            let request = Self::with_body(request, &mut body);
+
+            // Replay the stored response or mark the request as in-flight
+            let mut guard = match self.begin(&key, fingerprint) {
+                Begin::Replay(stored) => return Self::replay(&stored),
+                Begin::Conflict => return Response::new_status_reason(409, "Conflict"),
+                Begin::Mismatch => return Response::new_status_reason(422, "Unprocessable Content"),
+                Begin::Bypass => return handler(request),
+                Begin::Handle => InFlight { store: &self.store, key: Some(key) },
+            };
+
+            // Handle the request and store the response if possible
+            let mut response = handler(request);
+            response.resolve();
+            if let Some(stored) = self.buffer(&mut response) {
+                let expires = Instant::now() + self.ttl;
+                let key = guard.key.take().expect("missing in-flight key");
+                let mut store = self.store();
+                store.expiries.push_back((expires, key.clone()));
+                store.entries.insert(key, Entry::Done { stored, fingerprint, expires });
+            }
+            response
+        }
+    }
+
+    /// Gets the idempotency key scoped to the client, the request method and the target if any
+    fn scoped_key<S>(request: &Request, scope: &S) -> Option<Vec<u8>>
+    where
+        S: Fn(&Request) -> Option<Vec<u8>>,
+    {
+        // Get the key and the scope
+        let key = request.field("Idempotency-Key")?;
+        let scope = scope(request)?;
+
+        // Prefix the scope with its length since it may contain arbitrary bytes
+        let scope_len = scope.len().to_string();
+        let parts = [scope_len.as_bytes(), b":", &scope, &request.method, b" ", &request.target, b"\n", key];
+        Some(parts.concat())
+    }
+    /// Replaces the request body with the given buffered body
+    fn with_body<'a>(mut request: Request, body: &'a mut Source) -> Request<'a> {
+        // Replace the framing fields with the length of the buffered body
+        let len = match body {
+            Source::Data(data) => data.get_ref().len(),
+            _ => unreachable!("buffered body is not data-backed"),
+        };
+        request.fields.retain(|(key, _)| {
+            !key.eq_ignore_ascii_case(b"Content-Length") && !key.eq_ignore_ascii_case(b"Transfer-Encoding")
+        });
+        if len > 0 {
+            request.fields.push((Data::from(b"Content-Length"), Data::from(len.to_string())));
+        }
+        request.with_stream(body)
+    }
+    /// Looks up the scoped key and marks the request as in-flight if it is unknown
+    fn begin(&self, key: &[u8], fingerprint: u64) -> Begin {
+        // Purge the expired entries
+        let mut store = self.store();
+        store.purge(Instant::now());
+
+        // Check the entry
+        match store.entries.get(key) {
+            Some(Entry::Done { fingerprint: stored, .. } | Entry::InFlight { fingerprint: stored })
+                if *stored != fingerprint =>
+            {
+                Begin::Mismatch
+            }
+            Some(Entry::Done { stored, .. }) => Begin::Replay(stored.clone()),
+            Some(Entry::InFlight { .. }) => Begin::Conflict,
+            None if store.entries.len() >= self.capacity => Begin::Bypass,
+            None => {
+                store.entries.insert(key.to_vec(), Entry::InFlight { fingerprint });
+                Begin::Handle
+            }
+        }
+    }
+    /// Buffers the response body so that it can be stored, or returns `None` if the response cannot be stored
+    fn buffer(&self, response: &mut Response) -> Option<Arc<Stored>> {
+        // Server errors are not stored so that the request can be retried
+        if response.status_code().is_ok_and(|status| status.is_server_error()) {
+            return None;
+        }
+
+        // Buffer the body up to the limit
+        let mut body = Vec::new();
+        let limit = self.body_size_max as u64 + 1;
+        if (&mut response.body.source).take(limit).read_to_end(&mut body).is_err() {
+            // The body is not available anymore
+            *response = Response::new_500_internalservererror();
+            return None;
+        }
+        if body.len() > self.body_size_max {
+            // Restore the body
+            let remainder = mem::take(&mut response.body.source);
+            response.body.source = Source::chain([Source::from(body), remainder]);
+            return None;
+        }
+
+        // Store the response
+        let body = Arc::new(body);
+        response.body.source = Source::from(Data::from(body.clone()));
+        let (version, status, reason) =
+            (Arc::from(&*response.version), Arc::from(&*response.status), Arc::from(&*response.reason));
+        let fields = response.fields.iter().map(|(key, value)| (Arc::from(&**key), Arc::from(&**value))).collect();
+        Some(Arc::new(Stored { version, status, reason, fields, body }))
+    }
+    /// Creates a response from a stored response
+    fn replay(stored: &Stored) -> Response {
+        let (version, status, reason) = (stored.version.clone(), stored.status.clone(), stored.reason.clone());
+        let mut response = Response::from_parts(Data::from(version), Data::from(status), Data::from(reason));
+        response.fields =
+            stored.fields.iter().map(|(key, value)| (Data::from(key.clone()), Data::from(value.clone()))).collect();
+        response.set_body_data(stored.body.clone());
+        response.set_field("Idempotent-Replayed", "true");
+        response
+    }
+
+    /// The response store
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+impl Debug for Idempotency {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Idempotency")
+            .field("ttl", &self.ttl)
+            .field("capacity", &self.capacity)
+            .field("body_size_max", &self.body_size_max)
+            .field("request_size_max", &self.request_size_max)
+            .field("entries", &self.store().entries.len())
+            .finish()
+    }
+}
+
+/// The result of a key lookup
+enum Begin {
+    /// The stored response should be replayed
+    Replay(Arc<Stored>),
+    /// The original request is still being handled
+    Conflict,
+    /// The request body differs from the body of the original request
+    Mismatch,
+    /// The store is full, so the request is handled without deduplication
+    Bypass,
+    /// The request has been marked as in-flight and should be handled
+    Handle,
+}
+
+/// A guard that removes the in-flight entry if the response is not stored (e.g. if the handler panics)
+struct InFlight<'a> {
+    /// The response store
+    store: &'a Mutex<Store>,
+    /// The scoped key if the entry has not been stored yet
+    key: Option<Vec<u8>>,
+}
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            let mut store = self.store.lock().unwrap_or_else(PoisonError::into_inner);
+            store.entries.remove(&key);
+        }
+    }
+}
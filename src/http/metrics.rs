@@ -0,0 +1,102 @@
+//! Request parsing metrics
+
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// The global request parsing counters
+static COUNTERS: Counters = Counters {
+    parsed: AtomicU64::new(0),
+    io: AtomicU64::new(0),
+    header_too_large: AtomicU64::new(0),
+    bad_start_line: AtomicU64::new(0),
+    unsupported_version: AtomicU64::new(0),
+    bad_field: AtomicU64::new(0),
+};
+
+/// The request parsing counters
+struct Counters {
+    /// See [`ParseMetrics::parsed`]
+    parsed: AtomicU64,
+    /// See [`ParseMetrics::io`]
+    io: AtomicU64,
+    /// See [`ParseMetrics::header_too_large`]
+    header_too_large: AtomicU64,
+    /// See [`ParseMetrics::bad_start_line`]
+    bad_start_line: AtomicU64,
+    /// See [`ParseMetrics::unsupported_version`]
+    unsupported_version: AtomicU64,
+    /// See [`ParseMetrics::bad_field`]
+    bad_field: AtomicU64,
+}
+
+/// The class of a request parse failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParseFailure {
+    /// The header could not be read due to an I/O error (e.g. a timeout or a reset connection)
+    Io,
+    /// The header exceeds the size limit
+    HeaderTooLarge,
+    /// The start line is malformed or truncated
+    BadStartLine,
+    /// The HTTP version is not supported
+    UnsupportedVersion,
+    /// A header field is malformed or truncated
+    BadField,
+}
+
+/// A snapshot of the process-wide request parsing metrics
+///
+/// # Note
+/// Classifying parse failures allows operators to tell scanners and attacks (e.g. lots of bad start lines or oversized
+/// headers) apart from misbehaving but legitimate clients.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParseMetrics {
+    /// The amount of successfully parsed requests
+    pub parsed: u64,
+    /// The amount of headers that could not be read due to an I/O error
+    pub io: u64,
+    /// The amount of headers that exceeded the size limit
+    pub header_too_large: u64,
+    /// The amount of malformed or truncated start lines
+    pub bad_start_line: u64,
+    /// The amount of requests with an unsupported HTTP version
+    pub unsupported_version: u64,
+    /// The amount of malformed or truncated header fields
+    pub bad_field: u64,
+}
+impl ParseMetrics {
+    /// Takes a snapshot of the current metrics
+    pub fn snapshot() -> Self {
+        Self {
+            parsed: COUNTERS.parsed.load(Relaxed),
+            io: COUNTERS.io.load(Relaxed),
+            header_too_large: COUNTERS.header_too_large.load(Relaxed),
+            bad_start_line: COUNTERS.bad_start_line.load(Relaxed),
+            unsupported_version: COUNTERS.unsupported_version.load(Relaxed),
+            bad_field: COUNTERS.bad_field.load(Relaxed),
+        }
+    }
+
+    /// The total amount of parse failures
+    pub fn failures(&self) -> u64 {
+        let failures = [self.io, self.header_too_large, self.bad_start_line, self.unsupported_version, self.bad_field];
+        failures.iter().fold(0, |sum, count| sum.saturating_add(*count))
+    }
+
+    /// Records a successfully parsed request
+    pub(crate) fn record_parsed() {
+        COUNTERS.parsed.fetch_add(1, Relaxed);
+    }
+    /// Records a parse failure
+    pub(crate) fn record_failure(failure: ParseFailure) {
+        let counter = match failure {
+            ParseFailure::Io => &COUNTERS.io,
+            ParseFailure::HeaderTooLarge => &COUNTERS.header_too_large,
+            ParseFailure::BadStartLine => &COUNTERS.bad_start_line,
+            ParseFailure::UnsupportedVersion => &COUNTERS.unsupported_version,
+            ParseFailure::BadField => &COUNTERS.bad_field,
+        };
+        counter.fetch_add(1, Relaxed);
+    }
+}
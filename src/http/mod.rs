@@ -1,8 +1,65 @@
 //! A HTTP adapter
 
+mod accesslog;
+mod base64;
+mod benchmark;
+mod body;
+mod chunked;
+mod client;
+mod connect;
+mod cors;
+mod deferred;
+mod digest;
+mod handler;
+mod headerlimits;
+mod headermap;
+mod host;
+mod idempotency;
+mod metrics;
+mod outbound;
+mod params;
+mod proxy;
+mod redirects;
+mod reports;
 mod request;
 mod requestext;
 mod response;
 mod responseext;
+mod resume;
+mod security;
+mod servertiming;
+mod status;
+#[cfg(feature = "template")]
+pub mod template;
+mod wellknown;
 
-pub use crate::http::{request::Request, requestext::RequestExt, response::Response, responseext::ResponseExt};
+pub use crate::http::{
+    accesslog::{access_log, AccessLogFormat, AccessLogRecord},
+    benchmark::benchmark,
+    body::{Body, Framing},
+    chunked::BodyReader,
+    client::Client,
+    cors::Cors,
+    deferred::{Completer, Deferred},
+    digest::{expected_digest, Crc32c, DigestAlgorithm, DigestReader},
+    handler::{Filter, Handler, MapResponse, OrElse},
+    headerlimits::HeaderLimits,
+    headermap::HeaderMap,
+    host::Host,
+    idempotency::Idempotency,
+    metrics::{ParseFailure, ParseMetrics},
+    outbound::OutboundRequest,
+    params::{parse_header_params, HeaderParams},
+    proxy::Proxy,
+    redirects::Redirects,
+    reports::{report_endpoint, Report, ReportKind},
+    request::Request,
+    requestext::RequestExt,
+    response::Response,
+    responseext::ResponseExt,
+    resume::{clear_partial_write_hook, file_etag, set_partial_write_hook, PartialWrite},
+    security::{FrameOptions, SecurityHeaders},
+    servertiming::server_timing,
+    status::StatusCode,
+    wellknown::WellKnown,
+};
@@ -0,0 +1,54 @@
+//! A hook to sign or decorate the outbound requests of the client and the proxy
+
+use crate::{bytes::Data, error::Error};
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
+
+/// A callback that signs or decorates an outbound request
+type SignerCallback = Arc<dyn Fn(&mut OutboundRequest) -> Result<(), Error> + Send + Sync + 'static>;
+
+/// An outbound request of a [`crate::http::Client`] or [`crate::http::Proxy`] that is about to be sent
+///
+/// # Note
+/// The fields already contain the `Host` field and the framing fields (`Content-Length` or `Transfer-Encoding`); the
+/// signer may add, modify or remove fields, but must not change the framing.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct OutboundRequest<'a> {
+    /// The request method
+    pub method: &'a [u8],
+    /// The request target
+    pub target: &'a [u8],
+    /// The request header fields
+    pub fields: &'a mut Vec<(Data, Data)>,
+    /// The request body if it is available in memory, or `None` if it is streamed (e.g. for proxied requests)
+    pub body: Option<&'a [u8]>,
+}
+
+/// A shareable signer for outbound requests
+#[derive(Clone)]
+pub(crate) struct Signer {
+    /// The signer callback
+    callback: SignerCallback,
+}
+impl Signer {
+    /// Creates a new signer from the given callback
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&mut OutboundRequest) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        Self { callback: Arc::new(callback) }
+    }
+
+    /// Signs or decorates the given request
+    pub fn sign(&self, request: &mut OutboundRequest) -> Result<(), Error> {
+        (self.callback)(request)
+    }
+}
+impl Debug for Signer {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Signer").finish_non_exhaustive()
+    }
+}
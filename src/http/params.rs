@@ -0,0 +1,109 @@
+//! A parser for header field values with parameters
+
+use crate::{
+    bytes::{is_token, Data},
+    error,
+    error::Error,
+};
+
+/// A header field value with its parameters, e.g. `text/html; charset="utf-8"` or `attachment; filename=report.pdf`
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct HeaderParams {
+    /// The value before the first parameter, e.g. the media type of a `Content-Type` field
+    pub value: Data,
+    /// The parameters in their original order, with unquoted values; flags without value (e.g. `HttpOnly`) have an empty
+    /// value
+    pub params: Vec<(Data, Data)>,
+}
+impl HeaderParams {
+    /// Gets the value of the first parameter with the given name (case-insensitive)
+    pub fn param<N>(&self, name: N) -> Option<&Data>
+    where
+        N: AsRef<[u8]>,
+    {
+        let (_, value) = self.params.iter().find(|(key, _)| key.eq_ignore_ascii_case(name.as_ref()))?;
+        Some(value)
+    }
+}
+
+/// Parses a header field value with `;`-separated parameters (e.g. `Content-Type`, `Content-Disposition` or `Set-Cookie`)
+///
+/// # Syntax
+/// Parameters are `name=value` pairs, where the name is a token and the value is either a token or a quoted-string with
+/// backslash escapes (RFC 9110); whitespace around names, values and separators is ignored, and empty parameters are
+/// skipped. Semicolons within quoted-strings do not separate parameters.
+///
+/// # Note
+/// `Set-Cookie` attributes with non-token values (e.g. `Path=/` or `Expires`) are rejected unless they are quoted.
+pub fn parse_header_params<T>(value: T) -> Result<HeaderParams, Error>
+where
+    T: AsRef<[u8]>,
+{
+    // Split the segments
+    let raw = value.as_ref();
+    let mut segments = split_unquoted(raw)?.into_iter();
+    let value = segments.next().unwrap_or_default().trim_ascii();
+
+    // Parse the parameters
+    let mut params = Vec::new();
+    for segment in segments.map(<[u8]>::trim_ascii).filter(|segment| !segment.is_empty()) {
+        let (name, value) = match segment.iter().position(|byte| *byte == b'=') {
+            Some(split) => (segment[..split].trim_ascii(), unquote(segment[split + 1..].trim_ascii())?),
+            None => (segment, Vec::new()),
+        };
+        if !is_token(name) {
+            return Err(error!("Invalid header parameter name: {}", String::from_utf8_lossy(raw)));
+        }
+        params.push((Data::from(name.to_vec()), Data::from(value)));
+    }
+    Ok(HeaderParams { value: Data::from(value.to_vec()), params })
+}
+
+/// Splits the value at all semicolons that are not within a quoted-string
+fn split_unquoted(value: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    let (mut segments, mut start, mut quoted, mut escaped) = (Vec::new(), 0, false, false);
+    for (index, byte) in value.iter().enumerate() {
+        match (*byte, quoted, escaped) {
+            (_, true, true) => escaped = false,
+            (b'\\', true, false) => escaped = true,
+            (b'"', _, _) => quoted = !quoted,
+            (b';', false, _) => {
+                segments.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    if quoted {
+        return Err(error!("Unterminated quoted-string in header value: {}", String::from_utf8_lossy(value)));
+    }
+    segments.push(&value[start..]);
+    Ok(segments)
+}
+/// Unquotes a parameter value if it is a quoted-string
+fn unquote(value: &[u8]) -> Result<Vec<u8>, Error> {
+    // Return tokens as-is
+    let Some(quoted) = value.strip_prefix(b"\"") else {
+        if !is_token(value) {
+            return Err(error!("Invalid token in header value: {}", String::from_utf8_lossy(value)));
+        }
+        return Ok(value.to_vec());
+    };
+    let Some(quoted) = quoted.strip_suffix(b"\"") else {
+        return Err(error!("Invalid quoted-string in header value: {}", String::from_utf8_lossy(value)));
+    };
+
+    // Remove the escapes
+    let (mut unquoted, mut escaped) = (Vec::with_capacity(quoted.len()), false);
+    for byte in quoted {
+        match (*byte, escaped) {
+            (b'\\', false) => escaped = true,
+            (byte, _) => {
+                unquoted.push(byte);
+                escaped = false;
+            }
+        }
+    }
+    Ok(unquoted)
+}
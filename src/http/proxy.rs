@@ -0,0 +1,308 @@
+//! A minimal reverse proxy that forwards requests to an upstream server
+
+use crate::{
+    bytes::{Data, Source},
+    error,
+    error::Error,
+    http::{
+        chunked::OwnedBodyReader, connect, outbound::Signer, Body, Framing, OutboundRequest, Request, RequestExt,
+        Response, ResponseExt, StatusCode,
+    },
+};
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::SocketAddr,
+    time::Duration,
+};
+
+/// The hop-by-hop header fields that are not forwarded
+const HOP_BY_HOP: &[&[u8]] = &[
+    b"Connection",
+    b"Keep-Alive",
+    b"Proxy-Connection",
+    b"Proxy-Authenticate",
+    b"Proxy-Authorization",
+    b"TE",
+    b"Trailer",
+    b"Transfer-Encoding",
+    b"Upgrade",
+    b"Expect",
+];
+
+/// A reverse proxy that forwards requests to an upstream server and relays the upstream response
+///
+/// # Note
+/// Every request uses a new upstream connection with `Connection: close`. Both bodies are streamed without buffering;
+/// chunked bodies are decoded and re-framed: chunked for HTTP/1.1 clients, and delimited by closing the connection for
+/// HTTP/1.0 clients. If the upstream closes the connection before a fixed-length body is complete, writing the response
+/// fails so that the client connection is closed. The `Host` field is set to the upstream host (the original host is
+/// passed as `X-Forwarded-Host` if the client has sent one), and the peer address is appended to `X-Forwarded-For`. On Linux, fixed-length and
+/// close-delimited bodies are moved from the upstream to the client connection via `splice` when the response is
+/// written by the server.
+///
+/// # Example
+/// ```no_run
+/// # use ehttpd::http::Proxy;
+/// # use std::time::Duration;
+/// let mut proxy = Proxy::new("127.0.0.1:8080".parse().expect("invalid address"));
+/// proxy.set_timeout(Duration::from_secs(10));
+/// let handler = proxy.into_fn();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Proxy {
+    /// The upstream addresses
+    upstream: Vec<SocketAddr>,
+    /// The `Host` field for upstream requests, or `None` to preserve the original field
+    host: Option<String>,
+    /// The connect, read and write timeout
+    timeout: Duration,
+    /// The signer for upstream requests if any
+    signer: Option<Signer>,
+}
+impl Proxy {
+    /// Creates a new proxy for the given upstream address with a timeout of 30 seconds
+    pub fn new(upstream: SocketAddr) -> Self {
+        Self::with_addresses([upstream])
+    }
+    /// Creates a new proxy for an upstream with multiple addresses (e.g. the IPv6 and IPv4 addresses of a host name) with
+    /// a timeout of 30 seconds
+    ///
+    /// # Note
+    /// The addresses are raced like [`crate::http::Client::with_addresses`] does. The `Host` field defaults to the first
+    /// address.
+    pub fn with_addresses<I>(upstream: I) -> Self
+    where
+        I: IntoIterator<Item = SocketAddr>,
+    {
+        let upstream: Vec<_> = upstream.into_iter().collect();
+        let host = upstream.first().map(ToString::to_string);
+        Self { upstream, host, timeout: Duration::from_secs(30), signer: None }
+    }
+
+    /// Sets the `Host` field for upstream requests (defaults to the upstream address), or `None` to preserve the original
+    /// field
+    pub fn set_host<T>(&mut self, host: Option<T>)
+    where
+        T: ToString,
+    {
+        self.host = host.map(|host| host.to_string());
+    }
+    /// Sets the connect, read and write timeout for the upstream connection
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+    /// Sets a callback that signs or decorates every upstream request before it is sent (e.g. to front an internal
+    /// service that requires authenticated requests)
+    ///
+    /// # Note
+    /// Since the request body is streamed to the upstream, the callback only sees the request header. If the callback
+    /// fails, the request is not forwarded.
+    pub fn set_signer<F>(&mut self, signer: F)
+    where
+        F: Fn(&mut OutboundRequest) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        self.signer = Some(Signer::new(signer));
+    }
+
+    /// Forwards the request to the upstream and returns the upstream response with a body that streams from the
+    /// upstream connection
+    pub fn forward(&self, request: &mut Request) -> Result<Response, Error> {
+        // Serialize the request header before connecting, so that failing signers do not open a connection
+        let chunked = request.field("Transfer-Encoding").is_some();
+        let content_length = request.content_length()?;
+        let head = self.request_head(request, chunked, content_length)?;
+
+        // Connect to the upstream
+        let stream = connect::connect(&self.upstream, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        let mut tx = stream.try_clone()?;
+
+        // Write the request header and body
+        tx.write_all(&head)?;
+        match (chunked, content_length) {
+            (true, _) => Self::write_chunked(&mut request.body()?, &mut tx)?,
+            (false, Some(_)) => {
+                io::copy(&mut request.body()?, &mut tx)?;
+            }
+            (false, None) => (),
+        }
+        tx.flush()?;
+
+        // Read the response header and skip interim responses
+        let mut rx = Source::buffered(stream);
+        let mut response: Response = loop {
+            let response = Response::read_head(&mut rx)?;
+            match response.status_code()? {
+                StatusCode::SWITCHING_PROTOCOLS => return Err(error!("Upstream protocol upgrades are not supported")),
+                status if status.is_informational() => continue,
+                _ => break response,
+            }
+        };
+
+        // Remove the hop-by-hop fields
+        // Note: A close-delimited body keeps its (non-chunked) transfer coding, since it is relayed as-is
+        let framing = Self::response_framing(request, &response)?;
+        let connection = response.fields.iter().find(|(key, _)| key.eq_ignore_ascii_case(b"Connection"));
+        let connection = connection.map(|(_, value)| value.clone());
+        let relay_transfer_encoding = matches!(framing, Some((Framing::Close, _)));
+        response.fields.retain(|(key, _)| match key.eq_ignore_ascii_case(b"Transfer-Encoding") {
+            true => relay_transfer_encoding,
+            false => !Self::is_hop_by_hop(key) && !Self::is_connection_option(connection.as_ref(), key),
+        });
+
+        // Set the body according to the response framing and the client version
+        match framing {
+            None => response.body = Body::empty(),
+            Some((Framing::Fixed, Some(len))) => response.body = Body::new(rx, Some(len)),
+            Some((Framing::Chunked, _)) if request.version.eq(b"HTTP/1.1") => {
+                let body = OwnedBodyReader::new(rx, true, 0);
+                response.set_body(Body::new(Source::from_other(body), None));
+            }
+            Some((Framing::Chunked, _)) => {
+                let body = OwnedBodyReader::new(rx, true, 0);
+                response.set_body(Body::with_framing(Source::from_other(body), None, Framing::Close));
+            }
+            Some((Framing::Fixed | Framing::Close, _)) => {
+                response.body = Body::with_framing(rx, None, Framing::Close);
+                response.set_connection_close();
+            }
+        }
+        Ok(response)
+    }
+    /// Turns `self` into a `request->response`-handler that answers upstream failures with `502 Bad Gateway` or
+    /// `504 Gateway Timeout`
+    pub fn into_fn(self) -> impl Fn(Request) -> Response + Send + Sync + 'static {
+        move |mut request: Request| match self.forward(&mut request) {
+            Ok(response) => response,
+            Err(e) if matches!(e.io_kind(), Some(ErrorKind::TimedOut | ErrorKind::WouldBlock)) => {
+                Response::new(StatusCode::GATEWAY_TIMEOUT)
+            }
+            Err(_) => Response::new(StatusCode::BAD_GATEWAY),
+        }
+    }
+
+    /// Serializes the request header for the upstream
+    fn request_head(&self, request: &Request, chunked: bool, content_length: Option<u64>) -> Result<Vec<u8>, Error> {
+        // Write the request line
+        let mut head = Vec::with_capacity(request.header.len() + 128);
+        head.extend_from_slice(&request.method);
+        head.extend_from_slice(b" ");
+        head.extend_from_slice(&request.target);
+        head.extend_from_slice(b" HTTP/1.1\r\n");
+
+        // Collect the end-to-end fields and rewrite the forwarding fields
+        let mut fields: Vec<(Data, Data)> = (request.fields.iter())
+            .filter(|(key, _)| {
+                !Self::is_hop_by_hop(key) && !Self::is_connection_option(request.field("Connection"), key)
+            })
+            .filter(|(key, _)| !key.eq_ignore_ascii_case(b"Content-Length"))
+            .cloned()
+            .collect();
+        if let Some(host) = &self.host {
+            // Note: The upstream request is HTTP/1.1, so the `Host` field is sent even if the client has sent none
+            fields.retain(|(key, _)| {
+                !key.eq_ignore_ascii_case(b"Host") && !key.eq_ignore_ascii_case(b"X-Forwarded-Host")
+            });
+            if let Some(original) = request.field("Host") {
+                fields.push((Data::from(b"X-Forwarded-Host"), original.clone()));
+            }
+            fields.push((Data::from(b"Host"), Data::from(host.clone())));
+        }
+        if let Some(peer) = request.peer {
+            let forwarded_for = match request.field("X-Forwarded-For") {
+                Some(existing) => format!("{}, {}", String::from_utf8_lossy(existing), peer.ip()),
+                None => peer.ip().to_string(),
+            };
+            fields.retain(|(key, _)| !key.eq_ignore_ascii_case(b"X-Forwarded-For"));
+            fields.push((Data::from(b"X-Forwarded-For"), Data::from(forwarded_for)));
+        }
+
+        // Add the framing and sign the request
+        match (chunked, content_length) {
+            (true, _) => fields.push((Data::from(b"Transfer-Encoding"), Data::from(b"chunked"))),
+            (false, Some(len)) => fields.push((Data::from(b"Content-Length"), Data::from(len.to_string()))),
+            (false, None) => (),
+        }
+        fields.push((Data::from(b"Connection"), Data::from(b"close")));
+        if let Some(signer) = &self.signer {
+            let (method, target) = (&request.method, &request.target);
+            let mut outbound = OutboundRequest { method, target, fields: &mut fields, body: None };
+            signer.sign(&mut outbound)?;
+        }
+
+        // Write the fields
+        for (key, value) in &fields {
+            head.extend_from_slice(key);
+            head.extend_from_slice(b": ");
+            head.extend_from_slice(value);
+            head.extend_from_slice(b"\r\n");
+        }
+        head.extend_from_slice(b"\r\n");
+        Ok(head)
+    }
+    /// Writes the decoded body as chunked body
+    fn write_chunked<R, W>(body: &mut R, stream: &mut W) -> io::Result<()>
+    where
+        R: Read,
+        W: Write,
+    {
+        let mut buf = [0; 4096];
+        loop {
+            // Read the next chunk
+            let len = match body.read(&mut buf) {
+                Ok(len) => len,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            // Write the chunk or the terminating zero-length chunk
+            write!(stream, "{len:x}\r\n")?;
+            stream.write_all(&buf[..len])?;
+            stream.write_all(b"\r\n")?;
+            if len == 0 {
+                return Ok(());
+            }
+        }
+    }
+    /// The framing of the upstream response body, or `None` if the response has no body
+    ///
+    /// # Note
+    /// Chunked bodies yield `Framing::Chunked`; bodies that are delimited by closing the connection yield
+    /// `Framing::Close`.
+    fn response_framing(request: &Request, response: &Response) -> Result<Option<(Framing, Option<u64>)>, Error> {
+        // Check for responses without body
+        let status = response.status_code()?;
+        if request.method.eq(b"HEAD") || status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+
+        // Get the framing
+        let transfer_encoding = response.fields.iter().find(|(key, _)| key.eq_ignore_ascii_case(b"Transfer-Encoding"));
+        let Some((_, transfer_encoding)) = transfer_encoding else {
+            return match response.content_length()? {
+                Some(len) => Ok(Some((Framing::Fixed, Some(len)))),
+                None => Ok(Some((Framing::Close, None))),
+            };
+        };
+
+        // Check whether the final transfer coding is chunked
+        let mut codings = transfer_encoding.rsplit(|byte| *byte == b',');
+        match codings.next().is_some_and(|coding| coding.trim_ascii().eq_ignore_ascii_case(b"chunked")) {
+            true => Ok(Some((Framing::Chunked, None))),
+            false => Ok(Some((Framing::Close, None))),
+        }
+    }
+
+    /// Whether the given field is a hop-by-hop field
+    fn is_hop_by_hop(key: &[u8]) -> bool {
+        HOP_BY_HOP.iter().any(|hop_by_hop| key.eq_ignore_ascii_case(hop_by_hop))
+    }
+    /// Whether the given field is listed as connection option in the given `Connection` field
+    fn is_connection_option(connection: Option<&Data>, key: &[u8]) -> bool {
+        let Some(connection) = connection else {
+            return false;
+        };
+        connection.split(|byte| *byte == b',').any(|option| option.trim_ascii().eq_ignore_ascii_case(key))
+    }
+}
@@ -0,0 +1,194 @@
+//! A host- and path-based redirect table
+
+use crate::{
+    error,
+    error::Error,
+    http::{Host, Request, RequestExt, Response, ResponseExt, StatusCode},
+};
+
+/// A host pattern of a redirect rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostPattern {
+    /// Matches any host (`*`)
+    Any,
+    /// Matches all subdomains of the given name (`*.example.org`), but not the name itself
+    Subdomains(String),
+    /// Matches the given name exactly
+    Exact(String),
+}
+impl HostPattern {
+    /// Parses a host pattern
+    fn parse(pattern: &str) -> Result<Self, Error> {
+        match pattern.strip_prefix("*.") {
+            _ if pattern == "*" => Ok(Self::Any),
+            Some(parent) => Ok(Self::Subdomains(Host::parse(parent)?.name)),
+            None => Ok(Self::Exact(Host::parse(pattern)?.name)),
+        }
+    }
+    /// Whether the pattern matches the given host if any
+    fn matches(&self, host: Option<&Host>) -> bool {
+        match (self, host) {
+            (Self::Any, _) => true,
+            (Self::Subdomains(parent), Some(host)) => {
+                host.name.strip_suffix(parent.as_str()).is_some_and(|subdomain| subdomain.ends_with('.'))
+            }
+            (Self::Exact(name), Some(host)) => host.name == *name,
+            (_, None) => false,
+        }
+    }
+}
+
+/// A path pattern of a redirect rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathPattern {
+    /// Matches any path (`*`); the entire request target is appended to the redirect target
+    Any,
+    /// Matches all paths below the given prefix (`/old/*`); the remainder is appended to the redirect target
+    Prefix(Vec<u8>),
+    /// Matches the given path exactly; only the query is appended to the redirect target
+    Exact(Vec<u8>),
+}
+impl PathPattern {
+    /// Parses a path pattern
+    fn parse(pattern: &str) -> Self {
+        match pattern.strip_suffix('*') {
+            _ if pattern == "*" => Self::Any,
+            Some(prefix) => Self::Prefix(prefix.as_bytes().to_vec()),
+            None => Self::Exact(pattern.as_bytes().to_vec()),
+        }
+    }
+    /// Returns the suffix to append to the redirect target if the pattern matches the given path and query
+    ///
+    /// # Note
+    /// Leading slashes of the suffix are collapsed into a single slash, so that the suffix cannot turn a relative target
+    /// into a protocol-relative URL (e.g. `/` and `/evil.example` into `//evil.example`).
+    fn matches(&self, path: &[u8], query: &[u8]) -> Option<Vec<u8>> {
+        let suffix = match self {
+            Self::Any => path,
+            Self::Prefix(prefix) => path.strip_prefix(prefix.as_slice())?,
+            Self::Exact(exact) => (path == exact.as_slice()).then_some(&[][..])?,
+        };
+        let slashes = suffix.iter().take_while(|byte| **byte == b'/').count();
+        Some([&suffix[slashes.saturating_sub(1)..], query].concat())
+    }
+}
+
+/// A redirect table that is evaluated before the request is passed to the application handler (e.g. for `www`-to-apex
+/// redirects or legacy URL migrations)
+///
+/// # Patterns
+/// - Hosts are either `*` (any host, including requests without `Host`), `*.example.org` (all subdomains) or an exact
+///   hostname; ports are ignored.
+/// - Paths are either `*` (any path; the entire target is appended to the redirect target), `/old/*` (a prefix; the
+///   remainder is appended) or an exact path. The query is always preserved.
+/// - Redirect targets are either absolute `http`/`https` URLs or origin-relative paths starting with a single `/`.
+///
+/// Only requests with an origin-form target (i.e. a path starting with a single `/`) are redirected, so that the
+/// appended path cannot change the authority of the redirect target.
+///
+/// Rules are evaluated in insertion order, and the first matching rule wins.
+///
+/// # Example
+/// ```
+/// # use ehttpd::http::{Redirects, Response, ResponseExt, StatusCode};
+/// let mut redirects = Redirects::new();
+/// redirects.add("www.example.org", "*", "https://example.org", StatusCode::PERMANENT_REDIRECT)?;
+/// redirects.add("example.org", "/blog/*", "https://blog.example.org/", StatusCode::MOVED_PERMANENTLY)?;
+/// let handler = redirects.wrap(|_| Response::new_200_ok());
+/// # Ok::<(), ehttpd::error::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Redirects {
+    /// The redirect rules
+    rules: Vec<(HostPattern, PathPattern, Vec<u8>, StatusCode)>,
+}
+impl Redirects {
+    /// Creates a new empty redirect table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a redirect rule from the given host and path pattern to the target URL with the given status
+    ///
+    /// # Note
+    /// The status must be `301`, `302`, `303`, `307` or `308`; note that only `307` and `308` guarantee that the method
+    /// and body are preserved.
+    pub fn add<H, P, T>(&mut self, host: H, path: P, target: T, status: StatusCode) -> Result<(), Error>
+    where
+        H: AsRef<str>,
+        P: AsRef<str>,
+        T: AsRef<[u8]>,
+    {
+        // Validate the status
+        if !matches!(status.as_u16(), 301 | 302 | 303 | 307 | 308) {
+            return Err(error!("Invalid redirect status: {status}"));
+        }
+
+        // Validate the target
+        let target = target.as_ref();
+        let absolute = [&b"https://"[..], b"http://"].iter().any(|scheme| {
+            target.get(..scheme.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+                && target.len() > scheme.len()
+        });
+        if !absolute && !is_origin_form(target) {
+            return Err(error!("Invalid redirect target: {}", String::from_utf8_lossy(target)));
+        }
+
+        // Parse the patterns
+        let host = HostPattern::parse(host.as_ref())?;
+        let path = PathPattern::parse(path.as_ref());
+        self.rules.push((host, path, target.to_vec(), status));
+        Ok(())
+    }
+
+    /// Answers the request with a redirect if a rule matches, or returns `None` otherwise
+    ///
+    /// # Note
+    /// Requests with an invalid `Host` field only match rules with the host pattern `*`.
+    pub fn handle(&self, request: &Request) -> Option<Response> {
+        // Only redirect origin-form targets
+        let target = request.target.as_ref();
+        if !is_origin_form(target) {
+            return None;
+        }
+
+        // Split the target and parse the host
+        let (path, query) = target.split_at(target.iter().position(|byte| *byte == b'?').unwrap_or(target.len()));
+        let host = request.host().ok().flatten();
+
+        // Find the first matching rule
+        let (location, status) = self.rules.iter().find_map(|(host_pattern, path_pattern, location, status)| {
+            let mut suffix = path_pattern.matches(path, query).filter(|_| host_pattern.matches(host.as_ref()))?;
+            match (location.ends_with(b"/"), suffix.first()) {
+                // Avoid a double slash between the target and the suffix
+                (true, Some(b'/')) => {
+                    suffix.remove(0);
+                }
+                // Separate the suffix so that it cannot extend the authority of the target (e.g. `@evil.example`)
+                (false, Some(byte)) if *byte != b'/' && *byte != b'?' => suffix.insert(0, b'/'),
+                _ => (),
+            }
+            Some(([location.as_slice(), &suffix].concat(), *status))
+        })?;
+
+        // Create the redirect
+        let mut response = Response::new(status);
+        response.set_field("Location", location);
+        Some(response)
+    }
+    /// Wraps a `request->response`-handler so that redirects are answered before the request is passed to the handler
+    pub fn wrap<F>(self, handler: F) -> impl Fn(Request) -> Response + Send + Sync + 'static
+    where
+        F: Fn(Request) -> Response + Send + Sync + 'static,
+    {
+        move |request: Request| match self.handle(&request) {
+            Some(response) => response,
+            None => handler(request),
+        }
+    }
+}
+
+/// Whether the given target is an origin-relative path that starts with a single `/`
+fn is_origin_form(target: &[u8]) -> bool {
+    target.starts_with(b"/") && !target.starts_with(b"//")
+}
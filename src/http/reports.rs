@@ -0,0 +1,108 @@
+//! A report ingestion endpoint for browser reports (CSP violations, Network Error Logging, crash reports etc.)
+
+use crate::{
+    bytes::{Data, DataParseExt},
+    http::{Request, RequestExt, Response, ResponseExt},
+};
+use std::{io::Read, net::SocketAddr};
+
+/// The kind of a report payload, determined by its content type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    /// A legacy CSP violation report (`application/csp-report`)
+    CspReport,
+    /// A Reporting API batch (`application/reports+json`), e.g. CSP, NEL, crash or deprecation reports
+    Reports,
+    /// A generic JSON report (`application/json`)
+    Json,
+}
+impl ReportKind {
+    /// Gets the report kind for the given media type
+    fn from_media_type(media_type: &Data) -> Option<Self> {
+        match media_type.to_lowercase().as_ref() {
+            b"application/csp-report" => Some(Self::CspReport),
+            b"application/reports+json" => Some(Self::Reports),
+            b"application/json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A validated report
+#[derive(Debug)]
+pub struct Report {
+    /// The report kind
+    pub kind: ReportKind,
+    /// The raw JSON payload
+    pub payload: Data,
+    /// The peer address if known
+    pub peer: Option<SocketAddr>,
+}
+
+/// Creates a `request->response`-handler that accepts JSON report payloads up to `size_max` bytes and forwards them to
+/// the given callback
+///
+/// # Note
+/// The payload is only checked for well-formed JSON (or, without the `json` feature, for an enclosing object or array);
+/// it is up to the callback to interpret it. The handler answers with `204 No Content` on success, `405` for other methods
+/// than `POST`, `411` if the length is unknown, `413` if the payload is too large, `415` for unsupported content types,
+/// and `400` for malformed payloads.
+pub fn report_endpoint<F>(size_max: u64, callback: F) -> impl Fn(Request) -> Response + Send + Sync + 'static
+where
+    F: Fn(Report) + Send + Sync + 'static,
+{
+    move |request: Request| {
+        // Validate the request
+        // Note: Rejected requests close the connection since the payload is not consumed
+        let reject = |mut response: Response| {
+            response.set_connection_close();
+            response
+        };
+        if !request.method.eq(b"POST") {
+            return reject(Response::new_405_methodnotallowed());
+        }
+        // Note: Parameters like `charset` are ignored
+        let content_type = request.content_type().ok().flatten();
+        let Some(kind) = content_type.and_then(|type_| ReportKind::from_media_type(&type_.value)) else {
+            return reject(Response::new_status_reason(415, "Unsupported Media Type"));
+        };
+        let len = match request.content_length() {
+            Ok(Some(len)) if len > size_max => return reject(Response::new_413_payloadtoolarge()),
+            Ok(Some(len)) => len,
+            Ok(None) => return reject(Response::new_status_reason(411, "Length Required")),
+            Err(_) => return reject(Response::new_400_badrequest()),
+        };
+
+        // Read the payload
+        let mut payload = Vec::new();
+        let Ok(read) = request.stream.take(len).read_to_end(&mut payload) else {
+            return reject(Response::new_400_badrequest());
+        };
+        if read as u64 != len || !is_json(&payload) {
+            return Response::new_400_badrequest();
+        }
+
+        // Forward the report
+        callback(Report { kind, payload: Data::from(payload), peer: request.peer });
+        Response::new_status_reason(204, "No Content")
+    }
+}
+
+/// Whether the given payload is a well-formed JSON value
+#[cfg(feature = "json")]
+fn is_json(payload: &[u8]) -> bool {
+    serde_json::from_slice::<serde::de::IgnoredAny>(payload).is_ok()
+}
+/// Whether the given payload looks like a JSON object or array
+///
+/// # Note
+/// Without the `json` feature, only the UTF-8 encoding and the enclosing brackets are checked.
+#[cfg(not(feature = "json"))]
+fn is_json(payload: &[u8]) -> bool {
+    // Validate the encoding and get the enclosing characters
+    let Ok(payload) = std::str::from_utf8(payload) else {
+        return false;
+    };
+    let payload = payload.trim_matches([' ', '\t', '\r', '\n']);
+    matches!((payload.chars().next(), payload.chars().last()), (Some('{'), Some('}')) | (Some('['), Some(']')))
+}
@@ -4,10 +4,33 @@ use crate::{
     bytes::{Data, DataParseExt, Source},
     error,
     error::Error,
+    http::{
+        metrics::{ParseFailure, ParseMetrics},
+        BodyReader, HeaderLimits, HeaderMap, RequestExt,
+    },
+    timing::PhaseTimings,
+    ConnectionInfo,
 };
-use std::io::Read;
+use std::{
+    borrow::Cow,
+    cell::{OnceCell, RefCell},
+    io::{self, BufRead, ErrorKind},
+    mem,
+    net::SocketAddr,
+    time::Instant,
+};
+
+thread_local! {
+    /// The per-thread pool of header read buffers; the header is copied into an exactly sized backing, so the read
+    /// buffers are reclaimed immediately and never shared with a request
+    static HEADER_POOL: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
 
 /// A HTTP request
+///
+/// # Note
+/// The header is read into a single `Data::ArcVec`; all parsed components (method, target, version, field keys and
+/// values) are zero-copy subcopies that share the same backing.
 #[derive(Debug)]
 pub struct Request<'a, const HEADER_SIZE_MAX: usize = 4096> {
     /// The raw header bytes
@@ -20,14 +43,51 @@ pub struct Request<'a, const HEADER_SIZE_MAX: usize = 4096> {
     pub version: Data,
     /// The ranges of the key/value fields within the header
     pub fields: Vec<(Data, Data)>,
+    /// The peer address if known
+    pub peer: Option<SocketAddr>,
+    /// The phase timings up to and including the parsing of this request
+    pub timings: PhaseTimings,
     /// The connection stream
     pub stream: &'a mut Source,
+    /// The trailer fields of a chunked body once it has been read completely
+    trailers: Option<Vec<(Data, Data)>>,
+    /// The lazily built header map of the fields
+    header_map: OnceCell<HeaderMap>,
 }
 impl<'a, const HEADER_SIZE_MAX: usize> Request<'a, HEADER_SIZE_MAX> {
+    /// The maximum amount of header buffers per thread
+    const HEADER_POOL_SIZE_MAX: usize = 8;
+
+    /// The header fields whose values are redacted by the diagnostic formatters
+    const REDACTED_FIELDS: &'static [&'static str] =
+        &["Authorization", "Proxy-Authorization", "Cookie", "X-Api-Key", "X-Auth-Token"];
+
     /// Reads a HTTP request from a readable `stream`
+    ///
+    /// # Note
+    /// The header limits of the current connection are used if they have been configured via
+    /// [`crate::Server::set_header_limits`]; otherwise, the header size is limited to `HEADER_SIZE_MAX`. Unbuffered
+    /// streams are wrapped in place via [`Source::buffered`], so bytes that have been read ahead remain in `stream`.
+    ///
+    /// Both limits also cap the amount of header fields to 100 by default; requests with more fields were accepted by
+    /// earlier versions and are now rejected (see [`HeaderLimits::field_count_max`]).
     pub fn from_stream(stream: &'a mut Source) -> Result<Option<Self>, Error> {
+        let limits = ConnectionInfo::with_current(|info| info.header_limits).flatten();
+        Self::from_stream_with_limits(stream, limits.unwrap_or(HeaderLimits::new(HEADER_SIZE_MAX)))
+    }
+    /// Reads a HTTP request from a readable `stream` with the given header limits
+    ///
+    /// # Note
+    /// Unbuffered streams are wrapped in place via [`Source::buffered`].
+    pub fn from_stream_with_limits(stream: &'a mut Source, limits: HeaderLimits) -> Result<Option<Self>, Error> {
+        // Buffer the stream if necessary
+        if !matches!(stream, Source::Empty | Source::Data(_) | Source::Buffered(_)) {
+            *stream = Source::buffered(mem::take(stream));
+        }
+
         // Read the raw header or return `None` if the connection has been closed
-        let header = Self::read_header(stream)?;
+        let (start, mut first_byte) = (Instant::now(), None);
+        let header = Self::read_header(stream, &limits, &mut first_byte)?;
         if header.is_empty() {
             return Ok(None);
         }
@@ -35,43 +95,315 @@ impl<'a, const HEADER_SIZE_MAX: usize> Request<'a, HEADER_SIZE_MAX> {
         // Parse the start line
         let mut header_parsing = header.clone();
         let (method, target, version) = {
-            let (method, target, version) = Self::parse_start_line(&mut header_parsing)?;
+            let start_line = Self::parse_start_line(&mut header_parsing);
+            let (method, target, version) =
+                start_line.inspect_err(|_| ParseMetrics::record_failure(ParseFailure::BadStartLine))?;
             (method.trimmed(), target.trimmed(), version.trimmed())
         };
+        if !method.is_token() {
+            ParseMetrics::record_failure(ParseFailure::BadStartLine);
+            return Err(error!("Invalid HTTP method: {method}"));
+        }
+        if !version.eq(b"HTTP/1.1") && !version.eq(b"HTTP/1.0") {
+            ParseMetrics::record_failure(ParseFailure::UnsupportedVersion);
+            return Err(error!("Unsupported HTTP version: {version}"));
+        }
 
         // Parse the fields
         let mut fields = Vec::new();
         while !header_parsing.eq(b"\r\n") {
-            // Parse field
-            let (key, value) = Self::parse_field(&mut header_parsing)?;
+            // Validate the field count
+            if fields.len() >= limits.field_count_max {
+                ParseMetrics::record_failure(ParseFailure::HeaderTooLarge);
+                return Err(error!("Too many HTTP header fields"));
+            }
+
+            // Parse field and validate the field size
+            let len = header_parsing.len();
+            let field = Self::parse_field(&mut header_parsing);
+            let (key, value) = field.inspect_err(|_| ParseMetrics::record_failure(ParseFailure::BadField))?;
+            if len - header_parsing.len() > limits.field_size_max {
+                ParseMetrics::record_failure(ParseFailure::HeaderTooLarge);
+                return Err(error!("HTTP header field is too large: {key}"));
+            }
             fields.push((key, value));
         }
 
-        Ok(Some(Self { header, method, target, version, fields, stream }))
+        // Reject ambiguous framings, which could be used for request smuggling (RFC 9112, section 6.1)
+        let has_field = |name: &[u8]| fields.iter().any(|(key, _)| key.eq_ignore_ascii_case(name));
+        if has_field(b"Transfer-Encoding") && has_field(b"Content-Length") {
+            ParseMetrics::record_failure(ParseFailure::BadField);
+            return Err(error!("HTTP request has both Transfer-Encoding and Content-Length"));
+        }
+        ParseMetrics::record_parsed();
+
+        // Get the peer address and the queue wait from the current connection
+        let (peer, queue) = ConnectionInfo::with_current(|info| (info.peer, info.queued)).unwrap_or_default();
+
+        // Record the timings
+        let first_byte = first_byte.unwrap_or(start);
+        let timings = PhaseTimings {
+            queue,
+            first_byte: Some(first_byte - start),
+            parse: Some(first_byte.elapsed()),
+            ..Default::default()
+        };
+        Ok(Some(Self {
+            header,
+            method,
+            target,
+            version,
+            fields,
+            peer,
+            timings,
+            stream,
+            trailers: None,
+            header_map: OnceCell::new(),
+        }))
+    }
+
+    /// A reader for the request body that decodes `Transfer-Encoding: chunked` or is limited to the `Content-Length`
+    ///
+    /// # Note
+    /// Requests without `Transfer-Encoding` and `Content-Length` have an empty body. Only `chunked` is supported as
+    /// transfer coding.
+    pub fn body(&mut self) -> Result<BodyReader<'_>, Error> {
+        // Get the framing
+        let chunked = match self.field("Transfer-Encoding") {
+            Some(encoding) if encoding.eq_ignore_ascii_case(b"chunked") => true,
+            Some(encoding) => return Err(error!("Unsupported transfer encoding: {encoding}")),
+            None => false,
+        };
+        let len = match chunked {
+            true => 0,
+            false => self.content_length()?.unwrap_or_default(),
+        };
+        Ok(BodyReader::new(self.stream, chunked, len, &mut self.trailers))
+    }
+    /// Replaces the connection stream, e.g. with a buffered body
+    pub(crate) fn with_stream<'b>(self, stream: &'b mut Source) -> Request<'b, HEADER_SIZE_MAX> {
+        let Self { header, method, target, version, fields, peer, timings, .. } = self;
+        Request {
+            header,
+            method,
+            target,
+            version,
+            fields,
+            peer,
+            timings,
+            stream,
+            trailers: None,
+            header_map: OnceCell::new(),
+        }
+    }
+    /// The trailer fields of a chunked body, or `None` if the body has not been read completely via [`Self::body`] (or is
+    /// not chunked)
+    pub fn trailers(&self) -> Option<&[(Data, Data)]> {
+        self.trailers.as_deref()
     }
 
-    /// Reads the entire HTTP header from the stream
-    fn read_header(stream: &mut Source) -> Result<Data, Error> {
-        // Read the header
-        let mut header = Vec::with_capacity(HEADER_SIZE_MAX);
-        'read_loop: for byte in stream.bytes() {
-            // Read the next byte
-            let byte = byte?;
-            header.push(byte);
+    /// A header map of the fields for fast case-insensitive lookups
+    ///
+    /// # Note
+    /// The map is built once on the first call and cached; changes to [`Self::fields`] after the first call are not
+    /// reflected.
+    pub fn header_map(&self) -> &HeaderMap {
+        self.header_map.get_or_init(|| HeaderMap::from(self.fields.clone()))
+    }
+    /// The raw header bytes as received from the peer, including the start line and the terminating empty line
+    pub fn raw_header(&self) -> &[u8] {
+        &self.header
+    }
+    /// Formats the header for diagnostics, with the values of sensitive fields (e.g. `Authorization`) redacted
+    pub fn to_redacted_string(&self) -> String {
+        // Format the start line
+        let (method, target, version) = (
+            String::from_utf8_lossy(&self.method),
+            String::from_utf8_lossy(&self.target),
+            String::from_utf8_lossy(&self.version),
+        );
+        let mut formatted = format!("{method} {target} {version}\n");
 
-            // Check if we have the header
-            if header.ends_with(b"\r\n\r\n") {
-                break 'read_loop;
+        // Format the fields
+        for (key, value) in &self.fields {
+            let value = Self::redacted(key, value);
+            formatted.push_str(&format!("{}: {value}\n", String::from_utf8_lossy(key)));
+        }
+        formatted
+    }
+    /// Formats the request as `curl` command line to reproduce it, with the values of sensitive fields redacted
+    ///
+    /// # Note
+    /// The URL is assembled from the `Host` header field (or `localhost` if the field is missing) and the target. The
+    /// body is not included since it has not been read yet.
+    pub fn to_curl(&self) -> String {
+        // Assemble the URL
+        let target = String::from_utf8_lossy(&self.target);
+        let host = self.fields.iter().find(|(key, _)| key.eq_ignore_ascii_case(b"Host"));
+        let url = match host {
+            // The target is already in absolute form
+            _ if target.starts_with("http://") || target.starts_with("https://") => target.to_string(),
+            Some((_, host)) => format!("http://{}{target}", String::from_utf8_lossy(host)),
+            None => format!("http://localhost{target}"),
+        };
+
+        // Format the method and URL
+        let mut formatted = match self.method.as_ref() {
+            b"HEAD" => format!("curl --head {}", Self::shell_quote(&url)),
+            method => {
+                let method = String::from_utf8_lossy(method);
+                format!("curl -X {} {}", Self::shell_quote(&method), Self::shell_quote(&url))
+            }
+        };
+
+        // Format the fields; `Host` and `Content-Length` are set by `curl` itself
+        for (key, value) in &self.fields {
+            if key.eq_ignore_ascii_case(b"Host") || key.eq_ignore_ascii_case(b"Content-Length") {
+                continue;
+            }
+            let field = format!("{}: {}", String::from_utf8_lossy(key), Self::redacted(key, value));
+            formatted.push_str(&format!(" -H {}", Self::shell_quote(&field)));
+        }
+        formatted
+    }
+
+    /// Gets the field value for diagnostics, or a placeholder if the field is sensitive
+    fn redacted<'b>(key: &Data, value: &'b Data) -> Cow<'b, str> {
+        match Self::REDACTED_FIELDS.iter().any(|redacted| key.eq_ignore_ascii_case(redacted.as_bytes())) {
+            true => Cow::Borrowed("<redacted>"),
+            false => String::from_utf8_lossy(value),
+        }
+    }
+    /// Quotes the string for POSIX shells
+    fn shell_quote(string: &str) -> String {
+        format!("'{}'", string.replace('\'', r"'\''"))
+    }
+
+    /// Reads the entire HTTP header from the stream and records the time when the first byte has been received
+    ///
+    /// # Note
+    /// The header is read in blocks via [`BufRead`]; bytes after the header remain in the stream's buffer.
+    fn read_header(
+        stream: &mut Source,
+        limits: &HeaderLimits,
+        first_byte: &mut Option<Instant>,
+    ) -> Result<Data, Error> {
+        // Backup the original socket read timeout and limit it to the header timeout once if a deadline is enforced
+        let deadline = limits.timeout.and_then(|timeout| Some((timeout, Instant::now().checked_add(timeout)?)));
+        let original_timeout = match (deadline, stream.tcp_stream()) {
+            (Some((timeout, _)), Some(tcp_stream)) => {
+                let original_timeout = tcp_stream.read_timeout()?;
+                let limited = original_timeout.map_or(timeout, |original| original.min(timeout));
+                tcp_stream.set_read_timeout(Some(limited))?;
+                Some(original_timeout)
+            }
+            _ => None,
+        };
+
+        // Read the header and restore the original socket read timeout
+        let deadline = deadline.map(|(_, deadline)| deadline);
+        let header = Self::read_header_until(stream, limits.size_max, deadline, first_byte);
+        if let (Some(original_timeout), Some(tcp_stream)) = (original_timeout, stream.tcp_stream()) {
+            let _ = tcp_stream.set_read_timeout(original_timeout);
+        }
+        header
+    }
+    /// Reads the raw header until the given deadline if any
+    ///
+    /// # Note
+    /// If the deadline is exceeded before the first header byte has been received, the connection is treated as closed.
+    /// The deadline is checked between two reads, and every read is bounded by the socket read timeout; so a header that
+    /// trickles in is aborted at most one read timeout after the deadline.
+    fn read_header_until(
+        stream: &mut Source,
+        size_max: usize,
+        deadline: Option<Instant>,
+        first_byte: &mut Option<Instant>,
+    ) -> Result<Data, Error> {
+        // Read the header into a pooled buffer
+        let mut header = Self::lend_header_buf(size_max);
+        loop {
+            // Check the deadline
+            let expired = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if expired() {
+                break Self::header_expired(header.is_empty());
             }
-            if header.len() == HEADER_SIZE_MAX {
+
+            // Fill the buffer
+            let block = match stream.fill_buf() {
+                Ok([]) if header.is_empty() => break Ok(()),
+                Ok([]) => {
+                    // Note: A header that is truncated by the end of the stream is an I/O failure, not a parse failure
+                    ParseMetrics::record_failure(ParseFailure::Io);
+                    return Err(io::Error::new(ErrorKind::UnexpectedEof, "request header is truncated").into());
+                }
+                Ok(block) => block,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) && expired() => {
+                    break Self::header_expired(header.is_empty());
+                }
+                Err(e) => {
+                    ParseMetrics::record_failure(ParseFailure::Io);
+                    return Err(e.into());
+                }
+            };
+            first_byte.get_or_insert_with(Instant::now);
+
+            // Append the block up to the size limit and search for the end of the header, including a partial terminator
+            // at the end of the previous block
+            let search_start = header.len().saturating_sub(3);
+            let len = block.len().min(size_max.saturating_sub(header.len()));
+            header.extend_from_slice(&block[..len]);
+            let end = header[search_start..].windows(4).position(|window| window == b"\r\n\r\n");
+            if let Some(end) = end.map(|end| search_start + end + 4) {
+                // Consume only the header bytes and keep the remaining bytes buffered
+                let over_read = header.len() - end;
+                stream.consume(len - over_read);
+                header.truncate(end);
+                break Ok(());
+            }
+
+            // Consume the block and check the size limit
+            stream.consume(len);
+            if header.len() >= size_max {
+                ParseMetrics::record_failure(ParseFailure::HeaderTooLarge);
                 return Err(error!("HTTP header is too large"));
             }
+        }?;
+
+        // Copy the header into an exactly sized backing and reclaim the buffer
+        let data = Data::new_arcvec(header.as_slice());
+        Self::reclaim_header_buf(header);
+        Ok(data)
+    }
+    /// Handles an exceeded header deadline by treating an idle connection as closed or failing with `TimedOut` otherwise
+    fn header_expired(idle: bool) -> Result<(), Error> {
+        if idle {
+            return Ok(());
         }
+        ParseMetrics::record_failure(ParseFailure::Io);
+        Err(io::Error::new(ErrorKind::TimedOut, "request header deadline exceeded").into())
+    }
+    /// Lends a buffer from the per-thread pool, or allocates a new buffer if the pool is empty
+    fn lend_header_buf(capacity: usize) -> Vec<u8> {
+        let buf = HEADER_POOL.with(|pool| pool.borrow_mut().pop());
+        let Some(mut buf) = buf else {
+            return Vec::with_capacity(capacity);
+        };
 
-        // Create the RcVec
-        header.shrink_to_fit();
-        let header = Data::new_arcvec(header);
-        Ok(header)
+        // Reset the buffer
+        buf.clear();
+        buf.reserve(capacity);
+        buf
+    }
+    /// Returns a lent buffer to the per-thread pool unless the pool is full
+    fn reclaim_header_buf(buf: Vec<u8>) {
+        HEADER_POOL.with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < Self::HEADER_POOL_SIZE_MAX {
+                pool.push(buf);
+            }
+        });
     }
     /// Parses the start line
     #[allow(clippy::type_complexity)]
@@ -83,13 +415,16 @@ impl<'a, const HEADER_SIZE_MAX: usize> Request<'a, HEADER_SIZE_MAX> {
         Ok((method, target, line))
     }
     /// Parses a header field
-    fn parse_field(header: &mut Data) -> Result<(Data, Data), Error> {
+    pub(crate) fn parse_field(header: &mut Data) -> Result<(Data, Data), Error> {
         // Parse the field
         let mut line = header.split_off(b"\r\n").ok_or_else(|| error!("Truncated HTTP header field: {header}"))?;
         let key = line.split_off(b":").ok_or_else(|| error!("Invalid HTTP header field: {line}"))?;
 
-        // Trim the field values
-        let key = key.trimmed();
+        // Validate the untrimmed name and trim the value
+        // Note: Whitespace between the name and the colon is invalid (RFC 9112, section 5.1)
+        if !key.is_token() {
+            return Err(error!("Invalid HTTP header field name: {key}"));
+        }
         let value = line.trimmed();
         Ok((key, value))
     }
@@ -1,7 +1,12 @@
 //! Extension traits for `http::Request`
 
-use crate::{bytes::Data, error::Error, http::Request};
-use std::{path::Path, str};
+use crate::{
+    bytes::Data,
+    error,
+    error::Error,
+    http::{base64, parse_header_params, HeaderParams, Host, Request},
+};
+use std::{ops::RangeInclusive, path::Path, str};
 
 /// Some HTTP request extensions
 pub trait RequestExt {
@@ -18,6 +23,40 @@ pub trait RequestExt {
         T: AsRef<[u8]>;
     /// The request content length field if any
     fn content_length(&self) -> Result<Option<u64>, Error>;
+    /// The requested byte range within a resource with the given length if any
+    ///
+    /// # Note
+    /// Only single `bytes`-ranges are supported; multi-range requests are ignored and return `None` so that the entire
+    /// resource can be served instead. Unsatisfiable or malformed ranges are an error and should be answered with
+    /// `416 Range Not Satisfiable`.
+    fn range(&self, len: u64) -> Result<Option<RangeInclusive<u64>>, Error>;
+    /// The requested byte range like [`Self::range`], but only if the `If-Range` field is absent or matches the given
+    /// current ETag of the resource
+    ///
+    /// # Note
+    /// If-Range uses the strong comparison, so weak ETags (`W/"..."`) and dates never match and the entire resource
+    /// should be served; this prevents a resumed download from mixing two versions of the resource.
+    fn range_if(&self, len: u64, etag: &[u8]) -> Result<Option<RangeInclusive<u64>>, Error>;
+    /// The normalized request host field if any
+    fn host(&self) -> Result<Option<Host>, Error>;
+    /// The `Content-Type` field as parsed media type with its parameters (e.g. `charset` or `boundary`) if any
+    fn content_type(&self) -> Result<Option<HeaderParams>, Error>;
+    /// The username and password of an `Authorization: Basic` field (RFC 7617) if any
+    ///
+    /// # Note
+    /// Returns `None` if there is no `Authorization` field or if it uses another scheme; a malformed `Basic` credential is
+    /// an error and should be answered with `401 Unauthorized` (see [`crate::http::ResponseExt::new_401_basic`]).
+    fn basic_auth(&self) -> Result<Option<(Data, Data)>, Error>;
+
+    /// Reads the request body and deserializes it as JSON
+    ///
+    /// # Note
+    /// Bodies larger than `size_max` are rejected before they are read if the `Content-Length` is known, or as soon as the
+    /// limit is exceeded otherwise; such requests should be answered with `413 Payload Too Large`.
+    #[cfg(feature = "json")]
+    fn json<T>(&mut self, size_max: u64) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned;
 }
 impl<'a, const HEADER_SIZE_MAX: usize> RequestExt for Request<'a, HEADER_SIZE_MAX> {
     #[cfg(target_family = "unix")]
@@ -58,4 +97,106 @@ impl<'a, const HEADER_SIZE_MAX: usize> RequestExt for Request<'a, HEADER_SIZE_MA
         let content_length: u64 = content_length_utf8.parse()?;
         Ok(Some(content_length))
     }
+    fn range(&self, len: u64) -> Result<Option<RangeInclusive<u64>>, Error> {
+        // Get the range field if set
+        let Some(range_raw) = self.field("Range") else {
+            return Ok(None);
+        };
+
+        // Get the byte range specifier
+        let range_utf8 = str::from_utf8(range_raw)?;
+        let Some(range) = range_utf8.trim().strip_prefix("bytes=") else {
+            return Err(error!("Unsupported range unit: {range_utf8}"));
+        };
+        if range.contains(',') {
+            return Ok(None);
+        }
+
+        // Parse the range
+        let (start, end) = range.split_once('-').ok_or_else(|| error!("Invalid byte range: {range}"))?;
+        let (start, end) = match (start.trim(), end.trim()) {
+            ("", "") => return Err(error!("Invalid byte range: {range}")),
+            ("", suffix) => {
+                // Serve the last `suffix` bytes
+                let suffix: u64 = suffix.parse()?;
+                (len.saturating_sub(suffix), len.saturating_sub(1))
+            }
+            (start, "") => (start.parse()?, len.saturating_sub(1)),
+            (start, end) => (start.parse()?, end.parse::<u64>()?.min(len.saturating_sub(1))),
+        };
+
+        // Validate the range
+        if start > end || start >= len {
+            return Err(error!("Unsatisfiable byte range: {range}"));
+        }
+        Ok(Some(start..=end))
+    }
+    fn range_if(&self, len: u64, etag: &[u8]) -> Result<Option<RangeInclusive<u64>>, Error> {
+        // Validate the precondition if set
+        if let Some(if_range) = self.field("If-Range") {
+            let if_range = if_range.trim_ascii();
+            let strong = if_range.starts_with(b"\"") && !etag.starts_with(b"W/");
+            if !strong || if_range != etag.trim_ascii() {
+                return Ok(None);
+            }
+        }
+        self.range(len)
+    }
+    fn host(&self) -> Result<Option<Host>, Error> {
+        // Get the host field if set
+        let Some(host_raw) = self.field("Host") else {
+            return Ok(None);
+        };
+        Ok(Some(Host::parse(host_raw)?))
+    }
+    fn content_type(&self) -> Result<Option<HeaderParams>, Error> {
+        let content_type = self.field("Content-Type").map(parse_header_params);
+        content_type.transpose()
+    }
+    fn basic_auth(&self) -> Result<Option<(Data, Data)>, Error> {
+        // Get the authorization field if set
+        let Some(authorization) = self.field("Authorization") else {
+            return Ok(None);
+        };
+
+        // Check the scheme
+        let authorization = authorization.trim_ascii();
+        let Some((scheme, credentials)) = authorization.split_at_checked(6) else {
+            return Ok(None);
+        };
+        if !scheme.eq_ignore_ascii_case(b"Basic ") {
+            return Ok(None);
+        }
+
+        // Decode and split the credentials
+        let credentials =
+            base64::decode(credentials.trim_ascii()).ok_or_else(|| error!("Invalid Basic credentials"))?;
+        let split = credentials.iter().position(|byte| *byte == b':');
+        let split = split.ok_or_else(|| error!("Invalid Basic credentials: missing separator"))?;
+        let (username, password) = (credentials[..split].to_vec(), credentials[split + 1..].to_vec());
+        Ok(Some((Data::from(username), Data::from(password))))
+    }
+
+    #[cfg(feature = "json")]
+    fn json<T>(&mut self, size_max: u64) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        use std::io::Read;
+
+        // Validate the announced length
+        if self.content_length()?.is_some_and(|len| len > size_max) {
+            return Err(error!("JSON body is too large"));
+        }
+
+        // Read the body up to the limit
+        let mut body = Vec::new();
+        self.body()?.take(size_max.saturating_add(1)).read_to_end(&mut body)?;
+        if body.len() as u64 > size_max {
+            return Err(error!("JSON body is too large"));
+        }
+
+        // Deserialize the body
+        serde_json::from_slice(&body).map_err(|e| error!(with: e, "Invalid JSON body"))
+    }
 }
@@ -1,10 +1,24 @@
 //! A HTTP request
 
 use crate::{
-    bytes::{Data, Source},
+    bytes::{Data, DataParseExt, Sink, Source},
+    error,
     error::Error,
+    http::{
+        body::{Body, Framing},
+        HeaderMap, Request, ResponseExt, StatusCode,
+    },
 };
-use std::io::{self, Write};
+use std::{
+    cell::Cell,
+    io::{BufRead, Read, Write},
+    mem,
+};
+
+thread_local! {
+    /// A reusable per-thread buffer to serialize response headers without allocating for every response
+    static HEADER_BUF: Cell<Vec<u8>> = const { Cell::new(Vec::new()) };
+}
 
 /// A HTTP response
 #[derive(Debug)]
@@ -18,43 +32,230 @@ pub struct Response<const HEADER_SIZE_MAX: usize = 4096> {
     /// The response header fields
     pub fields: Vec<(Data, Data)>,
     /// The response body
-    pub body: Source,
+    ///
+    /// # Note
+    /// The framing fields are updated to the body framing when the response is written, so the body can also be assigned
+    /// directly.
+    pub body: Body,
 }
 impl<const HEADER_SIZE_MAX: usize> Response<HEADER_SIZE_MAX> {
-    /// Creates a new HTTP response
-    pub fn new(version: Data, status: Data, reason: Data) -> Self {
-        Self { version, status, reason, fields: Vec::new(), body: Source::default() }
+    /// The maximum size of a response that is serialized into a single buffer and written at once
+    const SMALL_RESPONSE_SIZE_MAX: usize = 4096;
+
+    /// Creates a new `HTTP/1.1` response with the given status code and its canonical reason phrase
+    ///
+    /// # Note
+    /// Non-standard status codes get an empty reason phrase; use [`Self::from_parts`] to set a custom reason. Like
+    /// [`crate::http::ResponseExt::new_status_reason`], the response has an empty body with `Content-Length: 0`, except
+    /// for statuses that must not carry a `Content-Length` of zero (`1xx`, `204` and `304`).
+    pub fn new(status: StatusCode) -> Self {
+        // Create the response
+        let reason = status.canonical_reason().unwrap_or_default();
+        let mut this = Self::from_parts(Data::from(b"HTTP/1.1"), Data::from(status.to_string()), Data::from(reason));
+
+        // Set content-length to 0 if appropriate
+        let bodyless =
+            status.is_informational() || status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED;
+        if !bodyless {
+            this.fields.push((Data::from(b"Content-Length"), Data::from(b"0")));
+        }
+        this
+    }
+    /// Creates a new HTTP response from the raw status line parts
+    pub fn from_parts(version: Data, status: Data, reason: Data) -> Self {
+        Self { version, status, reason, fields: Vec::new(), body: Body::default() }
+    }
+
+    /// Reads a HTTP response header from a readable `stream` (e.g. from an upstream server)
+    ///
+    /// # Note
+    /// The body is left in the stream and the returned response has an empty body; it is up to the caller to set the body
+    /// according to the response framing.
+    pub(crate) fn read_head(stream: &mut Source) -> Result<Self, Error> {
+        // Read the header line by line up to the size limit
+        let mut header = Vec::new();
+        loop {
+            let limit = HEADER_SIZE_MAX.saturating_sub(header.len()) as u64;
+            match (&mut *stream).take(limit).read_until(b'\n', &mut header)? {
+                0 if header.len() >= HEADER_SIZE_MAX => return Err(error!("HTTP header is too large")),
+                0 => return Err(error!("Truncated HTTP response header")),
+                _ if header.ends_with(b"\r\n\r\n") => break,
+                _ => continue,
+            }
+        }
+
+        // Parse the status line
+        let mut header = Data::from(header);
+        let mut line = header.split_off(b"\r\n").ok_or_else(|| error!("Truncated HTTP status line: {header}"))?;
+        let version = line.split_off(b" ").ok_or_else(|| error!("Invalid HTTP status line: {line}"))?;
+        let (status, reason) = match line.split_off(b" ") {
+            Some(status) => (status, line.trimmed()),
+            None => (line, Data::Empty),
+        };
+        if !version.eq(b"HTTP/1.1") && !version.eq(b"HTTP/1.0") {
+            return Err(error!("Unsupported HTTP version: {version}"));
+        }
+        StatusCode::parse(&status)?;
+
+        // Parse the fields
+        let mut this = Self::from_parts(version, status, reason);
+        while !header.eq(b"\r\n") {
+            let field = <Request>::parse_field(&mut header)?;
+            this.fields.push(field);
+        }
+        Ok(this)
+    }
+
+    /// Waits until a deferred response is completed and replaces `self` with it (see [`crate::http::Deferred`])
+    ///
+    /// # Note
+    /// This is a no-op for other responses. Wrappers that inspect or modify the response of an inner handler call this
+    /// first, and [`crate::reqresp`] calls this before writing the response.
+    pub fn resolve(&mut self) {
+        while let Some(deferred) = self.body.deferred.take() {
+            let Response { version, status, reason, fields, body } = deferred.wait();
+            *self = Self { version, status, reason, fields, body };
+        }
+    }
+
+    /// The typed status code
+    pub fn status_code(&self) -> Result<StatusCode, Error> {
+        StatusCode::parse(&self.status)
     }
 
     /// Writes the response to the given stream
+    ///
+    /// # Note
+    /// Small responses with an in-memory body (up to 4 KiB in total) are written with a single write to avoid an extra
+    /// syscall and packet for the body.
     pub fn to_stream<T>(&mut self, stream: &mut T) -> Result<(), Error>
     where
         T: Write,
     {
-        // Create a temporary buffer
-        let mut buf = Vec::with_capacity(HEADER_SIZE_MAX);
+        self.to_stream_counted(stream, &mut 0)
+    }
+    /// Writes the response to the given stream and adds the amount of body bytes that have been written to `body_sent`
+    ///
+    /// # Note
+    /// If the write fails midway, `body_sent` contains the amount of body bytes that have been passed to the stream before
+    /// the failure; this is a lower bound for the bytes that have been sent, but not necessarily received by the client
+    /// (see [`crate::http::PartialWrite`]).
+    pub fn to_stream_counted<T>(&mut self, stream: &mut T, body_sent: &mut u64) -> Result<(), Error>
+    where
+        T: Write,
+    {
+        // Write the header and copy the body
+        if !self.write_head(stream, body_sent)? {
+            self.body.write_counted(stream, body_sent)?;
+        }
+        Ok(())
+    }
+    /// Writes the response to the given sink like [`Self::to_stream_counted`], but moves bodies that are read from a TCP
+    /// stream (e.g. relayed upstream bodies) via `splice` on Linux if the sink is a TCP stream too
+    pub(crate) fn write_to_sink_counted(&mut self, sink: &mut Sink, body_sent: &mut u64) -> Result<(), Error> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if let (Sink::TcpStream(stream), true) = (&*sink, self.body.source.tcp_stream().is_some()) {
+            // Write the header and splice the body, or copy it if it cannot be spliced
+            let mut stream = stream;
+            if !self.write_head(&mut stream, body_sent)? && !self.body.splice_counted(stream, body_sent)? {
+                self.body.write_counted(&mut stream, body_sent)?;
+            }
+            return Ok(());
+        }
+        self.to_stream_counted(sink, body_sent)
+    }
+    /// Writes the response header and small in-memory bodies to the given stream, and returns whether the body has been
+    /// written completely
+    fn write_head<T>(&mut self, stream: &mut T, body_sent: &mut u64) -> Result<bool, Error>
+    where
+        T: Write,
+    {
+        // Ensure that the framing fields match the body
+        self.normalize_framing();
+
+        // Borrow the per-thread buffer
+        let mut buf = HEADER_BUF.take();
+        buf.clear();
+        buf.reserve(HEADER_SIZE_MAX);
 
         // Write start line
-        buf.write_all(&self.version)?;
-        buf.write_all(b" ")?;
-        buf.write_all(&self.status)?;
-        buf.write_all(b" ")?;
-        buf.write_all(&self.reason)?;
-        buf.write_all(b"\r\n")?;
+        buf.extend_from_slice(&self.version);
+        buf.extend_from_slice(b" ");
+        buf.extend_from_slice(&self.status);
+        buf.extend_from_slice(b" ");
+        buf.extend_from_slice(&self.reason);
+        buf.extend_from_slice(b"\r\n");
 
         // Write header fields and finalize header
         for (key, value) in &self.fields {
-            buf.write_all(key)?;
-            buf.write_all(b": ")?;
-            buf.write_all(value)?;
-            buf.write_all(b"\r\n")?;
+            buf.extend_from_slice(key);
+            buf.extend_from_slice(b": ");
+            buf.extend_from_slice(value);
+            buf.extend_from_slice(b"\r\n");
         }
-        buf.write_all(b"\r\n")?;
+        buf.extend_from_slice(b"\r\n");
 
-        // Write the header, and copy the body
-        stream.write_all(&buf)?;
-        io::copy(&mut self.body, stream)?;
-        Ok(())
+        // Append small bodies so that the response is written at once
+        let header_len = buf.len();
+        let body_size_max = Self::SMALL_RESPONSE_SIZE_MAX.saturating_sub(header_len);
+        let complete = self.body.append_small(&mut buf, body_size_max);
+
+        // Write the header and return the buffer unless it has grown excessively
+        let written = stream.write_all(&buf);
+        if written.is_ok() {
+            *body_sent += (buf.len() - header_len) as u64;
+        }
+        if buf.capacity() <= HEADER_SIZE_MAX.saturating_mul(2) {
+            HEADER_BUF.set(buf);
+        }
+
+        written?;
+        Ok(complete)
+    }
+
+    /// Updates the framing fields (`Content-Length`, `Transfer-Encoding` and `Connection: close`) if they do not match
+    /// the body framing, e.g. because the body has been assigned directly instead of via [`ResponseExt::set_body`]
+    ///
+    /// # Note
+    /// Fixed-length bodies without a source keep their fields, so that e.g. `HEAD` or `304 Not Modified` responses can
+    /// announce the framing of the omitted body. A non-chunked transfer coding is kept for close-delimited bodies.
+    fn normalize_framing(&mut self) {
+        // Collect the framing fields
+        let (mut content_length, mut transfer_encoding) = (false, None);
+        for (key, value) in &self.fields {
+            if key.eq_ignore_ascii_case(b"Content-Length") {
+                content_length = true;
+            } else if key.eq_ignore_ascii_case(b"Transfer-Encoding") {
+                transfer_encoding = Some(value);
+            }
+        }
+        let chunked = transfer_encoding.is_some_and(|coding| {
+            let last = coding.rsplit(|byte| *byte == b',').next().unwrap_or_default();
+            last.trim_ascii().eq_ignore_ascii_case(b"chunked")
+        });
+
+        // Validate the fields against the body framing
+        let matches = match (self.body.framing, self.body.len) {
+            (Framing::Fixed, _) if matches!(self.body.source, Source::Empty) => true,
+            (Framing::Fixed, Some(len)) => {
+                transfer_encoding.is_none() && matches!(self.content_length(), Ok(Some(announced)) if announced == len)
+            }
+            (Framing::Chunked, _) => chunked && !content_length,
+            (Framing::Fixed | Framing::Close, _) => !chunked && !content_length && self.has_connection_close(),
+        };
+        if !matches {
+            // Reapply the body to update the framing fields
+            let body = mem::take(&mut self.body);
+            self.set_body(body);
+        }
+    }
+
+    /// Moves the fields into a header map for fast case-insensitive lookups, without cloning them
+    ///
+    /// # Note
+    /// The fields are empty until the map is assigned back (`response.fields = map.into()`).
+    pub fn take_header_map(&mut self) -> HeaderMap {
+        HeaderMap::from(mem::take(&mut self.fields))
     }
 
     /// Checks if the header has `Connection: Close` set
@@ -1,14 +1,20 @@
 //! Extension traits for `http::Response`
 
 use crate::{
-    bytes::{Data, Source},
+    bytes::{Data, DataSliceExt, Source},
+    error,
     error::Error,
-    http::response::Response,
+    http::{
+        body::{Body, Framing},
+        response::Response,
+        SecurityHeaders,
+    },
 };
 use std::{
     borrow::BorrowMut,
     fs::File,
     io::{Seek, SeekFrom},
+    ops::RangeInclusive,
     str,
 };
 
@@ -23,6 +29,8 @@ where
         T: Into<Data>;
     /// Creates a new `200 OK` HTTP response with an empty body
     fn new_200_ok() -> Self;
+    /// Creates a new `206 Partial Content` HTTP response with an empty body
+    fn new_206_partialcontent() -> Self;
 
     /// Creates a new `307 Temporary Redirect` HTTP response with an empty body and the `Location`-header field set to the
     /// given location
@@ -37,6 +45,11 @@ where
     fn new_401_unauthorized<T>(requirement: T) -> Self
     where
         T: Into<Data>;
+    /// Creates a new `401 Unauthorized` HTTP response with an empty body and a `Basic` challenge (RFC 7617) for the given
+    /// realm
+    fn new_401_basic<T>(realm: T) -> Self
+    where
+        T: AsRef<str>;
     /// Creates a new `403 Forbidden` HTTP response with an empty body
     fn new_403_forbidden() -> Self;
     /// Creates a new `404 Not Found` HTTP response with an empty body
@@ -47,15 +60,23 @@ where
     fn new_413_payloadtoolarge() -> Self;
     /// Creates a new `416 Range Not Satisfiable` HTTP response with an empty body
     fn new_416_rangenotsatisfiable() -> Self;
+    /// Creates a new `429 Too Many Requests` HTTP response with an empty body
+    fn new_429_toomanyrequests() -> Self;
 
     /// Creates a new `500 Internal Server Error` HTTP response with an empty body
     fn new_500_internalservererror() -> Self;
+    /// Creates a new `503 Service Unavailable` HTTP response with an empty body
+    fn new_503_serviceunavailable() -> Self;
 
     /// Sets the field with the given name (performs an ASCII-case-insensitve comparison for replacement)
     fn set_field<K, V>(&mut self, key: K, value: V)
     where
         K: Into<Data>,
         V: Into<Data>;
+    /// Removes all fields with the given name (performs an ASCII-case-insensitve comparison)
+    fn remove_field<T>(&mut self, key: T)
+    where
+        T: AsRef<[u8]>;
     /// Sets the body content type
     fn set_content_type<T>(&mut self, type_: T)
     where
@@ -68,8 +89,40 @@ where
     /// Returns the content length if it is set
     fn content_length(&self) -> Result<Option<u64>, Error>;
 
+    /// Sets the given body and updates the `Content-Length`, `Transfer-Encoding` and `Connection` header according to the
+    /// body framing
+    fn set_body<T>(&mut self, body: T)
+    where
+        T: Into<Body>;
     /// Sets the given data as body content and updates the `Content-Length` header accordingly
     fn set_body_data<T>(&mut self, data: T)
+    where
+        T: Into<Data>;
+    /// Sets the given UTF-8 text as body content and updates the `Content-Type` and `Content-Length` header accordingly
+    fn set_body_text<T>(&mut self, text: T)
+    where
+        T: Into<Data>;
+    /// Sets the given HTML document as body content and updates the `Content-Type` and `Content-Length` header accordingly
+    fn set_body_html<T>(&mut self, html: T)
+    where
+        T: Into<Data>;
+    /// Sets the given XML document as body content and updates the `Content-Type` and `Content-Length` header accordingly
+    fn set_body_xml<T>(&mut self, xml: T)
+    where
+        T: Into<Data>;
+    /// Serializes the given value as JSON body content and updates the `Content-Type` and `Content-Length` header
+    /// accordingly
+    #[cfg(feature = "json")]
+    fn set_body_json<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: serde::Serialize + ?Sized;
+    /// Sets the given byte range of the data as body content and updates the `Content-Length` and `Content-Range` header
+    /// accordingly
+    ///
+    /// # Note
+    /// This function uses a cheap subcopy of the data, so serving ranges from e.g. `Data::ArcVec` does not copy the
+    /// underlying bytes.
+    fn set_body_data_range<T>(&mut self, data: T, range: RangeInclusive<u64>) -> Result<(), Error>
     where
         T: Into<Data>;
     /// Sets the given file as body content and updates the `Content-Length` header accordingly
@@ -84,6 +137,20 @@ where
     /// Turns the current `GET`-response into a `HEAD`-response by discarding the body without modifying content length
     /// etc.
     fn make_head(&mut self);
+    /// Synthesizes a minimal `text/plain` body containing the status code and reason if `self` is a `4xx` or `5xx`
+    /// response without a body, so that browsers don't show a blank page
+    ///
+    /// # Note
+    /// Responses with a non-error status code or an existing body are left untouched.
+    fn make_error_body(&mut self);
+
+    /// Sets the header fields of the given security preset (HSTS, `X-Content-Type-Options`, `X-Frame-Options`,
+    /// `Referrer-Policy` and CSP)
+    ///
+    /// # Note
+    /// Fields that are already set are left untouched, so that individual handlers can override the preset. A deferred
+    /// response is resolved first (see [`Response::resolve`]).
+    fn apply_security_headers(&mut self, policy: &SecurityHeaders);
 }
 impl<const HEADER_SIZE_MAX: usize> ResponseExt for Response<HEADER_SIZE_MAX> {
     fn new_status_reason<T>(status: u16, reason: T) -> Self
@@ -94,7 +161,7 @@ impl<const HEADER_SIZE_MAX: usize> ResponseExt for Response<HEADER_SIZE_MAX> {
         let version = Data::from(b"HTTP/1.1");
         let status = Data::from(status.to_string());
         let reason = reason.into();
-        let mut this = Self::new(version, status, reason);
+        let mut this = Self::from_parts(version, status, reason);
 
         // Set content-length to 0
         this.set_content_length(0);
@@ -103,6 +170,9 @@ impl<const HEADER_SIZE_MAX: usize> ResponseExt for Response<HEADER_SIZE_MAX> {
     fn new_200_ok() -> Self {
         Self::new_status_reason(200, "OK")
     }
+    fn new_206_partialcontent() -> Self {
+        Self::new_status_reason(206, "Partial Content")
+    }
 
     fn new_307_temporaryredirect<T>(location: T) -> Self
     where
@@ -124,6 +194,14 @@ impl<const HEADER_SIZE_MAX: usize> ResponseExt for Response<HEADER_SIZE_MAX> {
         this.set_field("WWW-Authenticate", requirement);
         this
     }
+    fn new_401_basic<T>(realm: T) -> Self
+    where
+        T: AsRef<str>,
+    {
+        // Escape the realm as quoted-string
+        let realm = realm.as_ref().replace('\\', "\\\\").replace('"', "\\\"");
+        Self::new_401_unauthorized(format!("Basic realm=\"{realm}\", charset=\"UTF-8\""))
+    }
     fn new_403_forbidden() -> Self {
         Self::new_status_reason(403, "Forbidden")
     }
@@ -139,10 +217,16 @@ impl<const HEADER_SIZE_MAX: usize> ResponseExt for Response<HEADER_SIZE_MAX> {
     fn new_416_rangenotsatisfiable() -> Self {
         Self::new_status_reason(416, "Range Not Satisfiable")
     }
+    fn new_429_toomanyrequests() -> Self {
+        Self::new_status_reason(429, "Too Many Requests")
+    }
 
     fn new_500_internalservererror() -> Self {
         Self::new_status_reason(500, "Internal Server Error")
     }
+    fn new_503_serviceunavailable() -> Self {
+        Self::new_status_reason(503, "Service Unavailable")
+    }
 
     fn set_field<K, V>(&mut self, key: K, value: V)
     where
@@ -157,6 +241,12 @@ impl<const HEADER_SIZE_MAX: usize> ResponseExt for Response<HEADER_SIZE_MAX> {
         self.fields.retain(|(existing, _)| !key.eq_ignore_ascii_case(existing));
         self.fields.push((key, value));
     }
+    fn remove_field<T>(&mut self, key: T)
+    where
+        T: AsRef<[u8]>,
+    {
+        self.fields.retain(|(existing, _)| !existing.eq_ignore_ascii_case(key.as_ref()));
+    }
     fn set_content_type<T>(&mut self, type_: T)
     where
         T: Into<Data>,
@@ -183,13 +273,81 @@ impl<const HEADER_SIZE_MAX: usize> ResponseExt for Response<HEADER_SIZE_MAX> {
         Ok(None)
     }
 
+    fn set_body<T>(&mut self, body: T)
+    where
+        T: Into<Body>,
+    {
+        // Set the framing header fields
+        let body = body.into();
+        match (body.framing, body.len) {
+            (Framing::Fixed, Some(len)) => {
+                self.remove_field("Transfer-Encoding");
+                self.set_content_length(len);
+            }
+            (Framing::Chunked, _) => {
+                self.remove_field("Content-Length");
+                self.set_field("Transfer-Encoding", "chunked");
+            }
+            (Framing::Fixed | Framing::Close, _) => {
+                self.remove_field("Content-Length");
+                self.remove_field("Transfer-Encoding");
+                self.set_connection_close();
+            }
+        }
+        self.body = body;
+    }
     fn set_body_data<T>(&mut self, data: T)
     where
         T: Into<Data>,
     {
         let data = data.into();
-        self.set_content_length(data.len() as u64);
-        self.body = Source::from(data);
+        self.set_body(data);
+    }
+    fn set_body_text<T>(&mut self, text: T)
+    where
+        T: Into<Data>,
+    {
+        self.set_content_type("text/plain; charset=utf-8");
+        self.set_body_data(text);
+    }
+    fn set_body_html<T>(&mut self, html: T)
+    where
+        T: Into<Data>,
+    {
+        self.set_content_type("text/html; charset=utf-8");
+        self.set_body_data(html);
+    }
+    fn set_body_xml<T>(&mut self, xml: T)
+    where
+        T: Into<Data>,
+    {
+        self.set_content_type("application/xml; charset=utf-8");
+        self.set_body_data(xml);
+    }
+    #[cfg(feature = "json")]
+    fn set_body_json<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        let json = serde_json::to_vec(value).map_err(|e| error!(with: e, "Failed to serialize JSON body"))?;
+        self.set_content_type("application/json");
+        self.set_body_data(json);
+        Ok(())
+    }
+    fn set_body_data_range<T>(&mut self, data: T, range: RangeInclusive<u64>) -> Result<(), Error>
+    where
+        T: Into<Data>,
+    {
+        // Create the subcopy
+        let data = data.into();
+        let (start, end) = (usize::try_from(*range.start())?, usize::try_from(*range.end())?);
+        let subcopy = data.subcopy(start..=end).ok_or_else(|| error!("Byte range is out of bounds"))?;
+
+        // Set the range and the body
+        let content_range = format!("bytes {start}-{end}/{}", data.len());
+        self.set_field("Content-Range", content_range);
+        self.set_body_data(subcopy);
+        Ok(())
     }
     fn set_body_file<T>(&mut self, mut file: T) -> Result<(), Error>
     where
@@ -205,14 +363,44 @@ impl<const HEADER_SIZE_MAX: usize> ResponseExt for Response<HEADER_SIZE_MAX> {
         if pos != len {
             file_real.seek(SeekFrom::Start(pos))?;
         }
-        self.set_content_length(len - pos);
 
         // Set the body
-        self.body = file.into();
+        self.set_body(Body::new(file.into(), Some(len - pos)));
         Ok(())
     }
 
     fn make_head(&mut self) {
-        self.body = Source::Empty;
+        self.body = Body::empty();
+    }
+    fn make_error_body(&mut self) {
+        // Check if we have an error status
+        let is_error = self.status_code().is_ok_and(|status| status.is_error());
+        if !is_error {
+            return;
+        }
+
+        // Check if the body is empty
+        let has_body = !self.body.is_empty() || !matches!(self.content_length(), Ok(None | Some(0)));
+        if has_body {
+            return;
+        }
+
+        // Synthesize the body
+        let mut body = Vec::with_capacity(self.status.len() + self.reason.len() + 3);
+        body.extend_from_slice(&self.status);
+        body.extend_from_slice(b" ");
+        body.extend_from_slice(&self.reason);
+        body.extend_from_slice(b"\r\n");
+        self.set_body_text(body);
+    }
+
+    fn apply_security_headers(&mut self, policy: &SecurityHeaders) {
+        self.resolve();
+        for (key, value) in policy.fields() {
+            let exists = self.fields.iter().any(|(existing, _)| existing.eq_ignore_ascii_case(key.as_bytes()));
+            if !exists {
+                self.fields.push((Data::from(key), Data::from(value)));
+            }
+        }
     }
 }
@@ -0,0 +1,102 @@
+//! Support for client-side resumption of responses that could not be written completely
+
+use crate::{bytes::Data, error::Error, http::Response};
+use std::{
+    fs::Metadata,
+    str,
+    sync::{Arc, PoisonError, RwLock},
+    time::UNIX_EPOCH,
+};
+
+/// A hook that is invoked if a response could not be written completely
+type PartialWriteHook = Arc<dyn Fn(&PartialWrite) + Send + Sync + 'static>;
+
+/// The global partial write hook
+static HOOK: RwLock<Option<PartialWriteHook>> = RwLock::new(None);
+
+/// A response that could not be written completely
+///
+/// # Note
+/// `sent` counts the body bytes that have been passed to the connection before the failure, which is a lower bound for
+/// what the client has received. A client can resume the download via a `Range` request for the remaining bytes, and
+/// should send `If-Range` with the ETag so that a changed resource is served completely (see
+/// [`crate::http::RequestExt::range_if`]).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct PartialWrite {
+    /// The request target
+    pub target: Data,
+    /// The `ETag` field of the response if any
+    pub etag: Option<Data>,
+    /// The offset of the first body byte within the resource (i.e. the start of the `Content-Range` if any)
+    pub offset: u64,
+    /// The amount of body bytes that have been written before the failure
+    pub sent: u64,
+    /// The complete length of the resource if known
+    pub len: Option<u64>,
+}
+impl PartialWrite {
+    /// Creates a new partial write record for the given response
+    pub fn new<const HEADER_SIZE_MAX: usize>(target: Data, response: &Response<HEADER_SIZE_MAX>, sent: u64) -> Self {
+        // Get the relevant fields
+        let field = |name: &[u8]| response.fields.iter().find(|(key, _)| key.eq_ignore_ascii_case(name));
+        let etag = field(b"ETag").map(|(_, value)| value.clone());
+        let content_range = field(b"Content-Range").and_then(|(_, value)| str::from_utf8(value).ok());
+
+        // Get the offset and the complete length
+        let (offset, len) = match content_range.and_then(Self::parse_content_range) {
+            Some((offset, len)) => (offset, len),
+            None => (0, response.body.len),
+        };
+        Self { target, etag, offset, sent, len }
+    }
+
+    /// The `Range` field value to request the remaining bytes (e.g. `bytes=4096-`)
+    pub fn resume_range(&self) -> String {
+        format!("bytes={}-", self.offset.saturating_add(self.sent))
+    }
+
+    /// Reports `self` to the global hook if any
+    pub(crate) fn report(&self) {
+        // Clone the hook so that the lock is not held while the hook is running
+        let hook = HOOK.read().unwrap_or_else(PoisonError::into_inner).clone();
+        if let Some(hook) = hook {
+            hook(self);
+        }
+    }
+
+    /// Parses a `Content-Range` value into the start offset and the complete length if known
+    fn parse_content_range(content_range: &str) -> Option<(u64, Option<u64>)> {
+        let range = content_range.trim().strip_prefix("bytes ")?;
+        let (range, len) = range.split_once('/')?;
+        let (start, _) = range.split_once('-')?;
+        Some((start.trim().parse().ok()?, len.trim().parse().ok()))
+    }
+}
+
+/// Creates a strong ETag from the length and modification time of a file (e.g. `"1f40-65a8c1d2.1dcd6500"`)
+///
+/// # Note
+/// The ETag is deterministic, so that full and partial responses for the same file version carry the same ETag, which
+/// is required for resumption via `If-Range`.
+pub fn file_etag(metadata: &Metadata) -> Result<String, Error> {
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok(format!("\"{:x}-{:x}.{:x}\"", metadata.len(), modified.as_secs(), modified.subsec_nanos()))
+}
+
+/// Sets a global hook that is invoked if a response could not be written completely by [`crate::reqresp`]
+///
+/// # Note
+/// The hook is called on the worker thread after the write has failed, so it should be cheap.
+pub fn set_partial_write_hook<F>(hook: F)
+where
+    F: Fn(&PartialWrite) + Send + Sync + 'static,
+{
+    let mut current = HOOK.write().unwrap_or_else(PoisonError::into_inner);
+    *current = Some(Arc::new(hook));
+}
+/// Removes the global partial write hook
+pub fn clear_partial_write_hook() {
+    let mut current = HOOK.write().unwrap_or_else(PoisonError::into_inner);
+    *current = None;
+}
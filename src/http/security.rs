@@ -0,0 +1,141 @@
+//! A preset for common security header fields
+
+use std::time::Duration;
+
+/// The `X-Frame-Options` value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameOptions {
+    /// The page must not be framed at all
+    Deny,
+    /// The page may only be framed by pages of the same origin
+    SameOrigin,
+}
+
+/// A preset of security header fields (see [`crate::http::ResponseExt::apply_security_headers`])
+///
+/// # Defaults
+/// - `Strict-Transport-Security: max-age=31536000; includeSubDomains`
+/// - `X-Content-Type-Options: nosniff`
+/// - `X-Frame-Options: DENY`
+/// - `Referrer-Policy: strict-origin-when-cross-origin`
+/// - no `Content-Security-Policy`, since a useful policy depends on the application
+///
+/// # Example
+/// ```
+/// # use ehttpd::http::{Response, ResponseExt, SecurityHeaders};
+/// let mut security_headers = SecurityHeaders::new();
+/// security_headers.set_csp_directive("default-src", "'self'");
+/// security_headers.set_csp_directive("img-src", "'self' data:");
+///
+/// let mut response: Response = Response::new_200_ok();
+/// response.apply_security_headers(&security_headers);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    /// The HSTS max-age, or `None` to omit the HSTS field
+    hsts_max_age: Option<Duration>,
+    /// Whether the HSTS policy includes subdomains
+    hsts_include_subdomains: bool,
+    /// Whether the HSTS policy requests preloading
+    hsts_preload: bool,
+    /// Whether to set `X-Content-Type-Options: nosniff`
+    nosniff: bool,
+    /// The `X-Frame-Options` value
+    frame_options: Option<FrameOptions>,
+    /// The `Referrer-Policy` value
+    referrer_policy: Option<String>,
+    /// The CSP directives in insertion order
+    csp: Vec<(String, String)>,
+}
+impl SecurityHeaders {
+    /// Creates a new preset with the default values
+    pub fn new() -> Self {
+        Self {
+            hsts_max_age: Some(Duration::from_secs(365 * 24 * 60 * 60)),
+            hsts_include_subdomains: true,
+            hsts_preload: false,
+            nosniff: true,
+            frame_options: Some(FrameOptions::Deny),
+            referrer_policy: Some("strict-origin-when-cross-origin".to_string()),
+            csp: Vec::new(),
+        }
+    }
+
+    /// Sets the HSTS max-age, or `None` to omit the HSTS field (e.g. for plain HTTP deployments)
+    pub fn set_hsts(&mut self, max_age: Option<Duration>) {
+        self.hsts_max_age = max_age;
+    }
+    /// Sets whether the HSTS policy includes subdomains (defaults to `true`)
+    pub fn set_hsts_include_subdomains(&mut self, include: bool) {
+        self.hsts_include_subdomains = include;
+    }
+    /// Sets whether the HSTS policy requests preloading (defaults to `false`)
+    pub fn set_hsts_preload(&mut self, preload: bool) {
+        self.hsts_preload = preload;
+    }
+    /// Sets whether to send `X-Content-Type-Options: nosniff` (defaults to `true`)
+    pub fn set_nosniff(&mut self, nosniff: bool) {
+        self.nosniff = nosniff;
+    }
+    /// Sets the `X-Frame-Options` value, or `None` to omit the field
+    pub fn set_frame_options(&mut self, frame_options: Option<FrameOptions>) {
+        self.frame_options = frame_options;
+    }
+    /// Sets the `Referrer-Policy` value (e.g. `no-referrer`), or `None` to omit the field
+    pub fn set_referrer_policy<T>(&mut self, policy: Option<T>)
+    where
+        T: ToString,
+    {
+        self.referrer_policy = policy.map(|policy| policy.to_string());
+    }
+    /// Sets a CSP directive (e.g. `script-src` with `'self' https://cdn.example.org`), replacing any existing directive
+    /// with the same name
+    pub fn set_csp_directive<N, V>(&mut self, name: N, value: V)
+    where
+        N: ToString,
+        V: ToString,
+    {
+        let (name, value) = (name.to_string(), value.to_string());
+        match self.csp.iter_mut().find(|(existing, _)| existing.eq_ignore_ascii_case(&name)) {
+            Some((_, existing)) => *existing = value,
+            None => self.csp.push((name, value)),
+        }
+    }
+
+    /// The header fields of the preset
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(max_age) = self.hsts_max_age {
+            // Build the HSTS value
+            let mut hsts = format!("max-age={}", max_age.as_secs());
+            if self.hsts_include_subdomains {
+                hsts.push_str("; includeSubDomains");
+            }
+            if self.hsts_preload {
+                hsts.push_str("; preload");
+            }
+            fields.push(("Strict-Transport-Security", hsts));
+        }
+        if self.nosniff {
+            fields.push(("X-Content-Type-Options", "nosniff".to_string()));
+        }
+        match self.frame_options {
+            Some(FrameOptions::Deny) => fields.push(("X-Frame-Options", "DENY".to_string())),
+            Some(FrameOptions::SameOrigin) => fields.push(("X-Frame-Options", "SAMEORIGIN".to_string())),
+            None => (),
+        }
+        if let Some(referrer_policy) = &self.referrer_policy {
+            fields.push(("Referrer-Policy", referrer_policy.clone()));
+        }
+        if !self.csp.is_empty() {
+            let directives: Vec<_> = self.csp.iter().map(|(name, value)| format!("{name} {value}")).collect();
+            fields.push(("Content-Security-Policy", directives.join("; ")));
+        }
+        fields
+    }
+}
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
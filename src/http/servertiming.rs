@@ -0,0 +1,42 @@
+//! A `Server-Timing` middleware
+
+use crate::{
+    bytes::Data,
+    http::{Request, Response, ResponseExt},
+    timing,
+};
+use std::time::Instant;
+
+/// Wraps a `request->response`-handler and emits a `Server-Timing` header with the recorded phase timings, the handler
+/// duration and all metrics recorded via [`timing::record_metric`]
+///
+/// # Note
+/// Existing `Server-Timing` fields set by the handler are preserved. Since timings may reveal internals, this middleware
+/// should only be enabled during development or for trusted clients.
+pub fn server_timing<F>(handler: F) -> impl Fn(Request) -> Response + Send + Sync + 'static
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    move |request: Request| {
+        // Handle the request
+        let mut timings = request.timings;
+        let _ = timing::take_metrics();
+        let start = Instant::now();
+        let mut response = handler(request);
+        response.resolve();
+        timings.handler = Some(start.elapsed());
+
+        // Collect the metrics
+        let mut metrics = vec![timings.to_server_timing()];
+        metrics.extend(timing::take_metrics());
+        let existing = response.fields.iter().find(|(key, _)| key.eq_ignore_ascii_case(b"Server-Timing"));
+        if let Some((_, existing)) = existing {
+            metrics.push(String::from_utf8_lossy(existing).into_owned());
+        }
+
+        // Set the header
+        metrics.retain(|metric| !metric.is_empty());
+        response.set_field("Server-Timing", Data::from(metrics.join(", ")));
+        response
+    }
+}
@@ -0,0 +1,211 @@
+//! A typed HTTP status code
+
+use crate::{error, error::Error};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::{self, FromStr},
+};
+
+/// Declares the status code constants together with their canonical reason phrases
+macro_rules! status_codes {
+    ($($(#[$meta:meta])* $name:ident = $code:literal, $reason:literal;)*) => {
+        impl StatusCode {
+            $(
+                $(#[$meta])*
+                pub const $name: Self = Self($code);
+            )*
+
+            /// The canonical reason phrase for standard status codes, e.g. `Not Found` for `404`
+            pub const fn canonical_reason(&self) -> Option<&'static str> {
+                match self.0 {
+                    $($code => Some($reason),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+/// A HTTP status code
+///
+/// # Note
+/// Any three-digit code (`100` to `999`) is valid; [`StatusCode::canonical_reason`] is only available for the standard
+/// codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StatusCode(u16);
+status_codes! {
+    /// `100 Continue`
+    CONTINUE = 100, "Continue";
+    /// `101 Switching Protocols`
+    SWITCHING_PROTOCOLS = 101, "Switching Protocols";
+    /// `103 Early Hints`
+    EARLY_HINTS = 103, "Early Hints";
+    /// `200 OK`
+    OK = 200, "OK";
+    /// `201 Created`
+    CREATED = 201, "Created";
+    /// `202 Accepted`
+    ACCEPTED = 202, "Accepted";
+    /// `203 Non-Authoritative Information`
+    NON_AUTHORITATIVE_INFORMATION = 203, "Non-Authoritative Information";
+    /// `204 No Content`
+    NO_CONTENT = 204, "No Content";
+    /// `205 Reset Content`
+    RESET_CONTENT = 205, "Reset Content";
+    /// `206 Partial Content`
+    PARTIAL_CONTENT = 206, "Partial Content";
+    /// `300 Multiple Choices`
+    MULTIPLE_CHOICES = 300, "Multiple Choices";
+    /// `301 Moved Permanently`
+    MOVED_PERMANENTLY = 301, "Moved Permanently";
+    /// `302 Found`
+    FOUND = 302, "Found";
+    /// `303 See Other`
+    SEE_OTHER = 303, "See Other";
+    /// `304 Not Modified`
+    NOT_MODIFIED = 304, "Not Modified";
+    /// `307 Temporary Redirect`
+    TEMPORARY_REDIRECT = 307, "Temporary Redirect";
+    /// `308 Permanent Redirect`
+    PERMANENT_REDIRECT = 308, "Permanent Redirect";
+    /// `400 Bad Request`
+    BAD_REQUEST = 400, "Bad Request";
+    /// `401 Unauthorized`
+    UNAUTHORIZED = 401, "Unauthorized";
+    /// `402 Payment Required`
+    PAYMENT_REQUIRED = 402, "Payment Required";
+    /// `403 Forbidden`
+    FORBIDDEN = 403, "Forbidden";
+    /// `404 Not Found`
+    NOT_FOUND = 404, "Not Found";
+    /// `405 Method Not Allowed`
+    METHOD_NOT_ALLOWED = 405, "Method Not Allowed";
+    /// `406 Not Acceptable`
+    NOT_ACCEPTABLE = 406, "Not Acceptable";
+    /// `407 Proxy Authentication Required`
+    PROXY_AUTHENTICATION_REQUIRED = 407, "Proxy Authentication Required";
+    /// `408 Request Timeout`
+    REQUEST_TIMEOUT = 408, "Request Timeout";
+    /// `409 Conflict`
+    CONFLICT = 409, "Conflict";
+    /// `410 Gone`
+    GONE = 410, "Gone";
+    /// `411 Length Required`
+    LENGTH_REQUIRED = 411, "Length Required";
+    /// `412 Precondition Failed`
+    PRECONDITION_FAILED = 412, "Precondition Failed";
+    /// `413 Payload Too Large`
+    PAYLOAD_TOO_LARGE = 413, "Payload Too Large";
+    /// `414 URI Too Long`
+    URI_TOO_LONG = 414, "URI Too Long";
+    /// `415 Unsupported Media Type`
+    UNSUPPORTED_MEDIA_TYPE = 415, "Unsupported Media Type";
+    /// `416 Range Not Satisfiable`
+    RANGE_NOT_SATISFIABLE = 416, "Range Not Satisfiable";
+    /// `417 Expectation Failed`
+    EXPECTATION_FAILED = 417, "Expectation Failed";
+    /// `421 Misdirected Request`
+    MISDIRECTED_REQUEST = 421, "Misdirected Request";
+    /// `422 Unprocessable Content`
+    UNPROCESSABLE_CONTENT = 422, "Unprocessable Content";
+    /// `425 Too Early`
+    TOO_EARLY = 425, "Too Early";
+    /// `426 Upgrade Required`
+    UPGRADE_REQUIRED = 426, "Upgrade Required";
+    /// `428 Precondition Required`
+    PRECONDITION_REQUIRED = 428, "Precondition Required";
+    /// `429 Too Many Requests`
+    TOO_MANY_REQUESTS = 429, "Too Many Requests";
+    /// `431 Request Header Fields Too Large`
+    REQUEST_HEADER_FIELDS_TOO_LARGE = 431, "Request Header Fields Too Large";
+    /// `451 Unavailable For Legal Reasons`
+    UNAVAILABLE_FOR_LEGAL_REASONS = 451, "Unavailable For Legal Reasons";
+    /// `500 Internal Server Error`
+    INTERNAL_SERVER_ERROR = 500, "Internal Server Error";
+    /// `501 Not Implemented`
+    NOT_IMPLEMENTED = 501, "Not Implemented";
+    /// `502 Bad Gateway`
+    BAD_GATEWAY = 502, "Bad Gateway";
+    /// `503 Service Unavailable`
+    SERVICE_UNAVAILABLE = 503, "Service Unavailable";
+    /// `504 Gateway Timeout`
+    GATEWAY_TIMEOUT = 504, "Gateway Timeout";
+    /// `505 HTTP Version Not Supported`
+    HTTP_VERSION_NOT_SUPPORTED = 505, "HTTP Version Not Supported";
+    /// `511 Network Authentication Required`
+    NETWORK_AUTHENTICATION_REQUIRED = 511, "Network Authentication Required";
+}
+impl StatusCode {
+    /// Creates a status code from a three-digit number
+    pub fn from_u16(code: u16) -> Result<Self, Error> {
+        match code {
+            100..=999 => Ok(Self(code)),
+            _ => Err(error!("Invalid status code: {code}")),
+        }
+    }
+    /// Parses a status code from its three-digit representation, e.g. the `status` field of a response
+    pub fn parse<T>(raw: T) -> Result<Self, Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        let raw = raw.as_ref();
+        if raw.len() != 3 || !raw.iter().all(u8::is_ascii_digit) {
+            return Err(error!("Invalid status code: {}", String::from_utf8_lossy(raw)));
+        }
+        Self::from_u16(str::from_utf8(raw)?.parse()?)
+    }
+
+    /// The numeric status code
+    pub const fn as_u16(&self) -> u16 {
+        self.0
+    }
+
+    /// Whether the status code is informational (`1xx`)
+    pub const fn is_informational(&self) -> bool {
+        self.0 / 100 == 1
+    }
+    /// Whether the status code indicates success (`2xx`)
+    pub const fn is_success(&self) -> bool {
+        self.0 / 100 == 2
+    }
+    /// Whether the status code is a redirection (`3xx`)
+    pub const fn is_redirection(&self) -> bool {
+        self.0 / 100 == 3
+    }
+    /// Whether the status code is a client error (`4xx`)
+    pub const fn is_client_error(&self) -> bool {
+        self.0 / 100 == 4
+    }
+    /// Whether the status code is a server error (`5xx`)
+    pub const fn is_server_error(&self) -> bool {
+        self.0 / 100 == 5
+    }
+    /// Whether the status code is a client or server error (`4xx` or `5xx`)
+    pub const fn is_error(&self) -> bool {
+        self.is_client_error() || self.is_server_error()
+    }
+}
+impl TryFrom<u16> for StatusCode {
+    type Error = Error;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        Self::from_u16(code)
+    }
+}
+impl From<StatusCode> for u16 {
+    fn from(status: StatusCode) -> Self {
+        status.0
+    }
+}
+impl FromStr for StatusCode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+impl Display for StatusCode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,62 @@
+//! A minimal placeholder-substitution template helper
+
+use crate::bytes::Data;
+
+/// Renders a template by replacing all `{{key}}`-placeholders with the associated value
+///
+/// # Note
+/// Whitespace around the placeholder key is ignored (i.e. `{{ key }}` is equivalent to `{{key}}`). Unknown placeholders
+/// are kept as-is. The values are inserted verbatim, so untrusted values should be escaped (e.g. with [`escape_html`]).
+pub fn render<T, K, V>(template: T, values: &[(K, V)]) -> Data
+where
+    T: AsRef<[u8]>,
+    K: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+{
+    let mut template = template.as_ref();
+    let mut rendered = Vec::with_capacity(template.len());
+    while let Some(start) = find(template, b"{{") {
+        // Find the end of the placeholder and copy the prefix
+        let Some(len) = find(&template[start + 2..], b"}}") else {
+            break;
+        };
+        rendered.extend_from_slice(&template[..start]);
+
+        // Substitute the placeholder
+        let placeholder = &template[start..start + 2 + len + 2];
+        let key = placeholder[2..placeholder.len() - 2].trim_ascii();
+        match values.iter().find(|(candidate, _)| candidate.as_ref() == key) {
+            Some((_, value)) => rendered.extend_from_slice(value.as_ref()),
+            None => rendered.extend_from_slice(placeholder),
+        }
+        template = &template[start + placeholder.len()..];
+    }
+
+    // Copy the remaining suffix
+    rendered.extend_from_slice(template);
+    Data::from(rendered)
+}
+
+/// Escapes the HTML special characters `&`, `<`, `>`, `"` and `'`
+pub fn escape_html<T>(value: T) -> Data
+where
+    T: AsRef<[u8]>,
+{
+    let mut escaped = Vec::with_capacity(value.as_ref().len());
+    for byte in value.as_ref() {
+        match byte {
+            b'&' => escaped.extend_from_slice(b"&amp;"),
+            b'<' => escaped.extend_from_slice(b"&lt;"),
+            b'>' => escaped.extend_from_slice(b"&gt;"),
+            b'"' => escaped.extend_from_slice(b"&quot;"),
+            b'\'' => escaped.extend_from_slice(b"&#39;"),
+            byte => escaped.push(*byte),
+        }
+    }
+    Data::from(escaped)
+}
+
+/// Finds the first occurrence of `pat` within `haystack`
+fn find(haystack: &[u8], pat: &[u8]) -> Option<usize> {
+    haystack.windows(pat.len()).position(|window| window == pat)
+}
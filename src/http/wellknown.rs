@@ -0,0 +1,140 @@
+//! A registry for well-known URIs (RFC 8615)
+
+use crate::{
+    bytes::Data,
+    http::{Request, Response, ResponseExt},
+};
+use std::{fmt::Debug, sync::Arc};
+
+/// A handler for a well-known resource; the second argument is the path remainder for prefix entries
+type WellKnownHandler = Arc<dyn Fn(&Request, &[u8]) -> Response + Send + Sync + 'static>;
+
+/// A registry for `/.well-known/*` resources that can be composed with any other handler
+///
+/// # Example
+/// ```
+/// # use ehttpd::http::{Response, ResponseExt, WellKnown};
+/// let mut well_known = WellKnown::new();
+/// well_known.register_security_txt("Contact: mailto:security@example.org\n");
+/// let handler = well_known.wrap(|_| Response::new_404_notfound());
+/// ```
+#[derive(Clone, Default)]
+pub struct WellKnown {
+    /// The registered entries by name; names ending with `/` are prefix entries
+    entries: Vec<(Vec<u8>, WellKnownHandler)>,
+}
+impl WellKnown {
+    /// The well-known path prefix
+    const PREFIX: &'static [u8] = b"/.well-known/";
+
+    /// Creates a new empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a static resource with the given name, content type and body
+    pub fn register_static<N, C, B>(&mut self, name: N, content_type: C, body: B)
+    where
+        N: Into<Data>,
+        C: Into<Data>,
+        B: Into<Data>,
+    {
+        // Move the data into shareable backings
+        let (content_type, body) = (Arc::new(content_type.into().to_vec()), Arc::new(body.into().to_vec()));
+        self.register_handler(name, move |_, _| {
+            let mut response = Response::new_200_ok();
+            response.set_content_type(Data::ArcVec { backing: content_type.clone(), range: 0..content_type.len() });
+            response.set_body_data(Data::ArcVec { backing: body.clone(), range: 0..body.len() });
+            response
+        });
+    }
+    /// Registers a handler for the given name
+    ///
+    /// # Note
+    /// If the name ends with `/` (e.g. `acme-challenge/`), the handler is invoked for all resources below this path, and
+    /// the remainder (e.g. the challenge token) is passed as second argument.
+    pub fn register_handler<N, F>(&mut self, name: N, handler: F)
+    where
+        N: Into<Data>,
+        F: Fn(&Request, &[u8]) -> Response + Send + Sync + 'static,
+    {
+        let name = name.into().to_vec();
+        self.entries.retain(|(existing, _)| *existing != name);
+        self.entries.push((name, Arc::new(handler)));
+    }
+
+    /// Registers a `security.txt` (RFC 9116) with the given contents
+    pub fn register_security_txt<T>(&mut self, contents: T)
+    where
+        T: Into<Data>,
+    {
+        self.register_static("security.txt", "text/plain; charset=utf-8", contents);
+    }
+    /// Delegates ACME HTTP-01 challenges (RFC 8555) to the given resolver, which maps a challenge token to the key
+    /// authorization if the token is known
+    pub fn register_acme_challenge<F>(&mut self, resolver: F)
+    where
+        F: Fn(&[u8]) -> Option<Data> + Send + Sync + 'static,
+    {
+        self.register_handler("acme-challenge/", move |_, token| match resolver(token) {
+            Some(key_authorization) => {
+                let mut response = Response::new_200_ok();
+                response.set_content_type("application/octet-stream");
+                response.set_body_data(key_authorization);
+                response
+            }
+            None => Response::new_404_notfound(),
+        });
+    }
+
+    /// Handles the request if it targets a well-known resource, or returns `None` otherwise
+    ///
+    /// # Note
+    /// Unknown well-known resources are answered with `404 Not Found`, and methods other than `GET` and `HEAD` with
+    /// `405 Method Not Allowed`.
+    pub fn handle(&self, request: &Request) -> Option<Response> {
+        // Get the well-known resource name without the query
+        let target = request.target.as_ref();
+        let path = match target.iter().position(|byte| *byte == b'?') {
+            Some(query) => &target[..query],
+            None => target,
+        };
+        let name = path.strip_prefix(Self::PREFIX)?;
+
+        // Find the entry
+        let entry = self.entries.iter().find_map(|(entry, handler)| match entry.ends_with(b"/") {
+            true => name.strip_prefix(entry.as_slice()).map(|remainder| (handler, remainder)),
+            false => (entry == name).then_some((handler, b"".as_slice())),
+        });
+        let Some((handler, remainder)) = entry else {
+            return Some(Response::new_404_notfound());
+        };
+
+        // Handle the request
+        let mut response = match request.method.as_ref() {
+            b"GET" | b"HEAD" => handler(request, remainder),
+            _ => return Some(Response::new_405_methodnotallowed()),
+        };
+        if request.method.eq(b"HEAD") {
+            response.make_head();
+        }
+        Some(response)
+    }
+    /// Wraps a `request->response`-handler so that well-known resources are served before the request is passed to the
+    /// handler
+    pub fn wrap<F>(self, handler: F) -> impl Fn(Request) -> Response + Send + Sync + 'static
+    where
+        F: Fn(Request) -> Response + Send + Sync + 'static,
+    {
+        move |request: Request| match self.handle(&request) {
+            Some(response) => response,
+            None => handler(request),
+        }
+    }
+}
+impl Debug for WellKnown {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let names: Vec<_> = self.entries.iter().map(|(name, _)| String::from_utf8_lossy(name)).collect();
+        f.debug_struct("WellKnown").field("entries", &names).finish()
+    }
+}
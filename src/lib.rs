@@ -1,33 +1,195 @@
 #![doc = include_str!("../README.md")]
 
 pub mod bytes;
+pub mod cancel;
+pub mod config;
+pub mod control;
+pub mod drain;
 pub mod error;
 pub mod http;
+pub mod limits;
+pub mod log;
+pub mod socket;
+pub mod tags;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod threadpool;
+pub mod timing;
 
 use crate::{
     bytes::{Sink, Source},
+    cancel::CancellationToken,
+    control::Control,
+    drain::{ActiveGuard, ActiveHandle, Connections},
     error::Error,
-    http::{Request, Response},
-    threadpool::{Executable, Threadpool},
+    http::{Framing, HeaderLimits, PartialWrite, Request, Response, ResponseExt, StatusCode},
+    limits::{PeerGuard, PeerLimit},
+    socket::{ListenerOptions, SocketOptions},
+    tags::{TagPolicy, Tags},
+    threadpool::{Backpressure, Executable, PanicPolicy, Priority, Shedding, Threadpool, ThreadpoolStats},
 };
 use std::{
+    any::Any,
+    cell::RefCell,
     convert::Infallible,
-    io::BufReader,
-    net::{TcpListener, ToSocketAddrs},
-    sync::Arc,
+    fmt::{self, Debug, Formatter},
+    io, mem,
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering::SeqCst},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
+/// A callback that is invoked if a connection fails
+pub type ErrorCallback = Arc<dyn Fn(&Error, &ConnectionInfo) + Send + Sync + 'static>;
+/// A callback that is invoked with the panic payload if a connection handler panics
+pub type PanicCallback = Arc<dyn Fn(&(dyn Any + Send), &ConnectionInfo) + Send + Sync + 'static>;
+
+thread_local! {
+    /// The info about the connection that is currently handled by this thread
+    static CURRENT_CONNECTION: RefCell<Option<ConnectionInfo>> = const { RefCell::new(None) };
+}
+
+/// Some information about a connection
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct ConnectionInfo {
+    /// The peer address if known
+    pub peer: Option<SocketAddr>,
+    /// The time the connection has waited in the threadpool queue before the current handler invocation
+    pub queued: Option<Duration>,
+    /// The token that is cancelled if the server is shutting down or the peer has disconnected
+    ///
+    /// # Note
+    /// A disconnect is only detected between two handler invocations; long-running handlers can probe the peer directly
+    /// via [`Sink::connection_alive`].
+    pub cancellation: CancellationToken,
+    /// The tag that has been assigned to the connection when it was accepted if any (e.g. to route admin connections)
+    pub tag: Option<Arc<str>>,
+    /// The request header limits if configured by the server
+    pub header_limits: Option<HeaderLimits>,
+    /// The handle to the registry entry of the connection if it is tracked by a server
+    active: Option<ActiveHandle>,
+    /// The callback to invoke if the connection fails
+    on_error: Option<ErrorCallback>,
+}
+impl ConnectionInfo {
+    /// The info about the connection that is currently handled by the calling thread if any
+    ///
+    /// # Note
+    /// This is only set within connection handlers that are called by the server; it's e.g. `None` if a handler is
+    /// invoked manually.
+    pub fn current() -> Option<Self> {
+        CURRENT_CONNECTION.with(|current| current.borrow().clone())
+    }
+    /// Calls `f` with the info about the connection that is currently handled by the calling thread if any
+    ///
+    /// # Note
+    /// Unlike [`Self::current`], this does not clone the connection info; `f` must not modify the current connection
+    /// info.
+    pub fn with_current<F, R>(f: F) -> Option<R>
+    where
+        F: FnOnce(&Self) -> R,
+    {
+        CURRENT_CONNECTION.with(|current| current.borrow().as_ref().map(f))
+    }
+
+    /// Sets the request that is currently being handled for the connection of the calling thread if any
+    ///
+    /// # Note
+    /// The request is only created and recorded if the registry records the current requests.
+    fn set_request<F>(request: F)
+    where
+        F: FnOnce() -> Option<Arc<str>>,
+    {
+        CURRENT_CONNECTION.with(|current| {
+            let current = current.borrow();
+            let active = current.as_ref().and_then(|info| info.active.as_ref());
+            if let Some(active) = active.filter(|active| active.is_tracking()) {
+                active.set_request(request());
+            }
+        });
+    }
+
+    /// Reports a failure of the connection to the error callback if any
+    fn report_error(&self, error: &Error) {
+        if let Some(on_error) = &self.on_error {
+            on_error(error, self);
+        }
+    }
+    /// Reports a failure of the connection of the calling thread to the error callback if any
+    fn report_current_error(error: &Error) {
+        // Note: The info is cloned so that the callback may access the current connection info itself
+        if let Some(info) = Self::current() {
+            info.report_error(error);
+        }
+    }
+
+    /// Marks `self` as the connection that is currently handled by the calling thread until the guard is dropped
+    fn enter(&self) -> CurrentConnectionGuard {
+        CURRENT_CONNECTION.with(|current| current.replace(Some(self.clone())));
+        CurrentConnectionGuard
+    }
+}
+
+impl Debug for ConnectionInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("ConnectionInfo")
+            .field("peer", &self.peer)
+            .field("queued", &self.queued)
+            .field("cancellation", &self.cancellation)
+            .field("tag", &self.tag)
+            .field("header_limits", &self.header_limits)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A guard that resets the current connection info on drop
+struct CurrentConnectionGuard;
+impl Drop for CurrentConnectionGuard {
+    fn drop(&mut self) {
+        CURRENT_CONNECTION.with(|current| current.take());
+    }
+}
+
 /// A connection to pass to the thread pool
 struct Connection<T, const STACK_SIZE: usize> {
+    /// The registration as active connection (unregistered when the connection is dropped)
+    #[allow(dead_code)]
+    pub active_guard: ActiveGuard,
     /// The connection handler
     pub handler: T,
     /// The receiving half of the stream
     pub rx: Source,
     /// The writing half of the stream
     pub tx: Sink,
+    /// Some information about the connection
+    pub info: ConnectionInfo,
+    /// The callback to invoke if the connection handler panics
+    pub on_panic: Option<PanicCallback>,
+    /// Whether to answer with a `500 Internal Server Error` if the connection handler panics
+    pub panic_response: bool,
+    /// The maximum queue wait and the action to apply to connections that have waited longer
+    pub shedding: Option<(Duration, Shedding)>,
+    /// The backpressure strategy to apply if the connection is rescheduled into a congested threadpool
+    pub backpressure: Backpressure,
+    /// The `Retry-After` delay in seconds for a canned `503 Service Unavailable` response
+    pub retry_after: Option<u64>,
+    /// Whether the connection has been rescheduled after a previous handler invocation
+    pub rescheduled: bool,
+    /// The peer connection slot if the server has a per-peer limit
+    ///
+    /// # Note
+    /// This field is never read; it is only held so that the slot is released once the connection is dropped.
+    _peer_guard: Option<PeerGuard>,
     /// The connection queue for keep-alice TCP connections
     pub threadpool: Arc<Threadpool<Self, STACK_SIZE>>,
+    /// The time when the connection has been queued
+    pub queued_at: Instant,
 }
 impl<T, const STACK_SIZE: usize> Connection<T, STACK_SIZE>
 where
@@ -36,13 +198,85 @@ where
     /// Handles the connection
     fn handle(mut self) -> Result<(), Error> {
         // Call the connection handler
-        if (self.handler)(&mut self.rx, &mut self.tx) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("handler_invocation", peer = ?self.info.peer).entered();
+        let _log_context = self.info.peer.map(|peer| log::enter_context(format_args!("peer={peer}")));
+        let queued = self.queued_at.elapsed();
+        self.info.queued = Some(queued);
+        if let Some((queue_age_max, shedding)) = self.shedding.filter(|(queue_age_max, _)| queued > *queue_age_max) {
+            return Err(self.shed(shedding, queue_age_max));
+        }
+        let current_connection = self.info.enter();
+        let result = match self.threadpool.panic_policy() {
+            PanicPolicy::Catch => panic::catch_unwind(AssertUnwindSafe(|| (self.handler)(&mut self.rx, &mut self.tx))),
+            PanicPolicy::Propagate => Ok((self.handler)(&mut self.rx, &mut self.tx)),
+        };
+        drop(current_connection);
+        let reschedule = match result {
+            Ok(reschedule) => reschedule,
+            Err(payload) => return Err(self.panicked(payload.as_ref())),
+        };
+
+        // Probe the peer once before rescheduling, and cancel the connection if it has disconnected
+        // Note: Pipelined requests that are already buffered are handled even if the peer has half-closed the connection
+        if reschedule && !self.rx.has_buffered() && !self.tx.connection_alive() {
+            self.info.cancellation.cancel();
+        }
+
+        // Note: Connections are not rescheduled if the server is shutting down or the peer has disconnected
+        if reschedule && !self.info.cancellation.is_cancelled() {
             // Reschedule the connection
             let threadpool = self.threadpool.clone();
-            threadpool.dispatch(self)?;
+            (self.queued_at, self.rescheduled) = (Instant::now(), true);
+            let backpressure = self.backpressure;
+            if let Err(connection) = threadpool.dispatch_with(self, backpressure) {
+                let error = error!("Threadpool is congested");
+                log::dropped(&connection.tx, "reschedule", &error);
+                connection.info.report_error(&error);
+                return Err(error);
+            }
         }
         Ok(())
     }
+
+    /// Sheds a connection that has waited too long in the threadpool queue
+    ///
+    /// # Note
+    /// Rescheduled keep-alive connections are always closed without a response, since the client may not have sent a
+    /// request yet.
+    fn shed(&mut self, shedding: Shedding, queue_age_max: Duration) -> Error {
+        // Report the shedding
+        let error = error!("Connection has waited longer than {queue_age_max:?} in the queue");
+        log::dropped(&self.tx, "shed", &error);
+        self.info.report_error(&error);
+
+        // Answer with an error response
+        let respond = shedding == Shedding::Unavailable && !self.rescheduled;
+        if let (true, Sink::TcpStream(stream)) = (respond, &self.tx) {
+            let _ = reject(stream, unavailable(self.retry_after));
+        }
+        error
+    }
+    /// Reports a panicked connection handler and writes a `500 Internal Server Error` if configured
+    fn panicked(&mut self, payload: &(dyn Any + Send)) -> Error {
+        // Report the panic
+        let message = threadpool::panic_message(payload);
+        match &self.on_panic {
+            Some(on_panic) => on_panic(payload, &self.info),
+            None => log_error!("connection handler panicked: message={message}"),
+        }
+
+        // Answer with an error response
+        // Note: If the handler has already started to write the response, the response will be garbled; however the
+        // connection is closed anyway
+        if self.panic_response {
+            let mut response: Response = Response::new_500_internalservererror();
+            response.make_error_body();
+            response.set_connection_close();
+            let _ = response.to_stream(&mut self.tx);
+        }
+        error!("Connection handler panicked: {message}")
+    }
 }
 impl<T, const STACK_SIZE: usize> Executable for Connection<T, STACK_SIZE>
 where
@@ -51,6 +285,20 @@ where
     fn exec(self) {
         let _ = self.handle();
     }
+
+    fn evicted(self) {
+        // Report the eviction
+        let error = error!("Connection has been dropped from the congested queue");
+        log::dropped(&self.tx, "evict", &error);
+        self.info.report_error(&error);
+
+        // Answer with an error response
+        // Note: Rescheduled keep-alive connections are closed without a response, since the client may not have sent a
+        // request yet
+        if let (false, Sink::TcpStream(stream)) = (self.rescheduled, &self.tx) {
+            let _ = reject(stream, unavailable(self.retry_after));
+        }
+    }
 }
 
 /// A HTTP server
@@ -59,23 +307,411 @@ pub struct Server<T, const STACK_SIZE: usize = 65_536> {
     threadpool: Arc<Threadpool<Connection<T, STACK_SIZE>, STACK_SIZE>>,
     /// The connection handler
     handler: T,
+    /// The optional per-peer connection limit
+    peer_limit: Option<PeerLimit>,
+    /// The `Retry-After` delay in seconds for a canned `503 Service Unavailable` if the threadpool is congested
+    overload_retry_after: Option<u64>,
+    /// The callback to invoke if a connection fails
+    on_error: Option<ErrorCallback>,
+    /// The callback to invoke if a connection handler panics
+    on_panic: Option<PanicCallback>,
+    /// Whether to answer with a `500 Internal Server Error` if a connection handler panics
+    panic_response: bool,
+    /// The socket options for accepted connections
+    socket_options: SocketOptions,
+    /// The socket options for listeners
+    listener_options: ListenerOptions,
+    /// The default socket read timeout for connections whose tag policy does not set one
+    read_timeout: Option<Duration>,
+    /// The backpressure strategy if the threadpool is congested
+    backpressure: Backpressure,
+    /// The maximum queue wait and the action to apply to connections that have waited longer
+    shedding: Option<(Duration, Shedding)>,
+    /// The request header limits if any
+    header_limits: Option<HeaderLimits>,
+    /// The server-wide cancellation token
+    cancellation: CancellationToken,
+    /// The connection tagger and the per-tag policies
+    tags: Tags,
+    /// Whether the server is in maintenance mode
+    maintenance: Arc<AtomicBool>,
+    /// The active connections
+    connections: Connections,
 }
 impl<T, const STACK_SIZE: usize> Server<T, STACK_SIZE>
 where
     T: Fn(&mut Source, &mut Sink) -> bool + Clone + Send + Sync + 'static,
 {
+    /// The default socket read timeout for connections that are served inline by [`Self::serve_single_threaded`]
+    const SINGLE_THREADED_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
     /// Creates a new server bound on the given address
     pub fn new(worker_max: usize, handler: T) -> Self {
         // Create threadpool and init self
         let threadpool: Threadpool<_, STACK_SIZE> = Threadpool::new(worker_max);
-        Self { threadpool: Arc::new(threadpool), handler }
+        let cancellation = CancellationToken::new();
+        Self {
+            threadpool: Arc::new(threadpool),
+            handler,
+            peer_limit: None,
+            overload_retry_after: None,
+            on_error: None,
+            on_panic: None,
+            panic_response: false,
+            socket_options: SocketOptions::default(),
+            listener_options: ListenerOptions::default(),
+            read_timeout: None,
+            backpressure: Backpressure::default(),
+            shedding: None,
+            header_limits: None,
+            connections: Connections::new(cancellation.clone()),
+            cancellation,
+            tags: Tags::default(),
+            maintenance: Arc::default(),
+        }
+    }
+
+    /// Keeps at least the given amount of warm workers alive, and pre-spawns them immediately
+    pub fn set_worker_min(&mut self, worker_min: usize) -> Result<(), Error> {
+        self.threadpool.set_worker_min(worker_min)
+    }
+    /// Limits the amount of concurrent connections per peer IP address
+    ///
+    /// # Note
+    /// Connections above the limit are answered with a canned `429 Too Many Requests` response and closed immediately.
+    pub fn set_peer_limit(&mut self, limit: usize) {
+        self.peer_limit = Some(PeerLimit::new(limit));
+    }
+    /// Answers connections with a canned `503 Service Unavailable` response and the given `Retry-After` delay in seconds
+    /// if the threadpool is congested
+    ///
+    /// # Note
+    /// By default, a congested threadpool is a fatal error for [`Self::accept`]; with an overload fallback, the server
+    /// stays alive and rejects the connection instead.
+    pub fn set_overload_fallback(&mut self, retry_after: u64) {
+        self.overload_retry_after = Some(retry_after);
+    }
+    /// Sets the backpressure strategy if the threadpool is congested (defaults to [`Backpressure::Reject`])
+    ///
+    /// # Note
+    /// The strategy is applied before the overload fallback (see [`Self::set_overload_fallback`]), so e.g.
+    /// [`Backpressure::Block`] can absorb short bursts that would otherwise be rejected. Connections that are dropped by
+    /// [`Backpressure::DropOldest`] are answered with a canned `503 Service Unavailable` response (or closed without a
+    /// response if they are rescheduled keep-alive connections). The strategy also applies to rescheduled keep-alive
+    /// connections.
+    pub fn set_backpressure(&mut self, backpressure: Backpressure) {
+        self.backpressure = backpressure;
+    }
+    /// Sheds connections that have waited longer than `queue_age_max` in the threadpool queue instead of running the
+    /// handler, since their clients have most likely timed out already
+    ///
+    /// # Note
+    /// With [`Shedding::Unavailable`], the canned response includes the `Retry-After` delay of the overload fallback if
+    /// set (see [`Self::set_overload_fallback`]).
+    pub fn set_backlog_shedding(&mut self, queue_age_max: Duration, shedding: Shedding) {
+        self.shedding = Some((queue_age_max, shedding));
+    }
+    /// Sets the request header limits for all connections, which take precedence over the `HEADER_SIZE_MAX` of the
+    /// request type (see [`http::Request::from_stream`])
+    pub fn set_header_limits(&mut self, limits: HeaderLimits) {
+        self.header_limits = Some(limits);
+    }
+    /// Sets the socket options for accepted connections (e.g. to disable Nagle's algorithm for latency-sensitive APIs)
+    pub fn set_socket_options(&mut self, options: SocketOptions) {
+        self.socket_options = options;
+    }
+    /// Sets the socket options for listeners (e.g. TCP Fast Open or deferred accept to reduce the handshake latency)
+    pub fn set_listener_options(&mut self, options: ListenerOptions) {
+        self.listener_options = options;
+    }
+    /// Sets a callback that is invoked with the error and the connection info whenever the server drops a connection
+    ///
+    /// # Note
+    /// The callback is invoked if a connection is shed after waiting too long in the queue (see
+    /// [`Self::set_backlog_shedding`]), is dropped from the queue by [`Backpressure::DropOldest`], or cannot be
+    /// rescheduled because the threadpool is congested, and if [`reqresp`] drops a connection because the request
+    /// cannot be read or parsed, has timed out, or the response cannot be written. Panics are reported via
+    /// [`Self::on_panic`] instead.
+    pub fn on_connection_error<F>(&mut self, callback: F)
+    where
+        F: Fn(&Error, &ConnectionInfo) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(callback));
+    }
+    /// Sets a callback that tags every accepted connection based on the local listener address and the peer address
+    ///
+    /// # Note
+    /// The tag is available to the handler via [`ConnectionInfo::tag`], and selects the policy that is registered via
+    /// [`Self::set_tag_policy`] (e.g. to expose an admin port with different limits on the same server).
+    pub fn set_tagger<F>(&mut self, tagger: F)
+    where
+        F: Fn(SocketAddr, SocketAddr) -> Option<Arc<str>> + Send + Sync + 'static,
+    {
+        self.tags.set_tagger(tagger);
+    }
+    /// Sets the policy for all connections with the given tag
+    pub fn set_tag_policy<N>(&mut self, tag: N, policy: TagPolicy)
+    where
+        N: Into<Arc<str>>,
+    {
+        self.tags.set_policy(tag, policy);
+    }
+    /// Sets a callback that is invoked with the panic payload and the connection info whenever a connection handler
+    /// panics
+    ///
+    /// # Note
+    /// Panicking handlers are caught and the connection is closed; if no callback is set, the panic is logged as error.
+    pub fn on_panic<F>(&mut self, callback: F)
+    where
+        F: Fn(&(dyn Any + Send), &ConnectionInfo) + Send + Sync + 'static,
+    {
+        self.on_panic = Some(Arc::new(callback));
+    }
+    /// Sets the policy to apply if a connection handler panics (defaults to `PanicPolicy::Catch`)
+    ///
+    /// # Note
+    /// With `PanicPolicy::Propagate`, the panic callback and the panic response are not used; the panic unwinds and
+    /// terminates the worker thread, which closes the connection.
+    pub fn set_panic_policy(&mut self, policy: PanicPolicy) {
+        self.threadpool.set_panic_policy(policy);
+    }
+    /// Whether to answer with a canned `500 Internal Server Error` response if a connection handler panics (defaults to
+    /// `false`)
+    pub fn set_panic_response(&mut self, enabled: bool) {
+        self.panic_response = enabled;
+    }
+
+    /// Overrides the server settings with all `EHTTPD_*` environment variables that are set, so that they are layered on
+    /// top of the settings in code or from configuration files
+    ///
+    /// # Variables
+    /// - `EHTTPD_WORKER_MIN`: The amount of warm workers (see [`Self::set_worker_min`])
+    /// - `EHTTPD_PEER_LIMIT`: The per-peer connection limit (see [`Self::set_peer_limit`])
+    /// - `EHTTPD_OVERLOAD_RETRY_AFTER`: The overload fallback delay, e.g. `30s` (see [`Self::set_overload_fallback`])
+    /// - `EHTTPD_BACKPRESSURE`: `reject`, `drop-oldest` or `block:<timeout>`, e.g. `block:2s` (see
+    ///   [`Self::set_backpressure`])
+    /// - `EHTTPD_PANIC_RESPONSE`: Whether to answer panics with `500 Internal Server Error` (see
+    ///   [`Self::set_panic_response`])
+    /// - `EHTTPD_MAINTENANCE`: Whether to start in maintenance mode (see [`Self::set_maintenance`])
+    /// - `EHTTPD_HEADER_SIZE_MAX`, `EHTTPD_HEADER_FIELD_SIZE_MAX`, `EHTTPD_HEADER_FIELD_COUNT_MAX`,
+    ///   `EHTTPD_HEADER_TIMEOUT`: The request header limits, e.g. `EHTTPD_HEADER_SIZE_MAX=16KiB` or
+    ///   `EHTTPD_HEADER_TIMEOUT=10s` (see [`Self::set_header_limits`])
+    /// - `EHTTPD_LOG_LEVEL`: The log level, e.g. `info` (see [`log::set_level`])
+    /// - `EHTTPD_TCP_NODELAY`, `EHTTPD_TCP_KEEPALIVE`, `EHTTPD_SEND_BUFFER_SIZE`, `EHTTPD_RECV_BUFFER_SIZE`: The socket
+    ///   options for accepted connections, e.g. `EHTTPD_TCP_KEEPALIVE=1m` or `EHTTPD_SEND_BUFFER_SIZE=256KiB`
+    /// - `EHTTPD_TCP_FASTOPEN`, `EHTTPD_DEFER_ACCEPT`: The listener options
+    ///
+    /// Values are parsed with the parsers in [`config`]. All values are parsed before any setting is applied, so an invalid
+    /// value leaves the server unchanged. Unknown `EHTTPD_*` variables are logged as warning to catch typos.
+    pub fn apply_env(&mut self) -> Result<(), Error> {
+        /// The known variables
+        const KNOWN: &[&str] = &[
+            "EHTTPD_WORKER_MIN",
+            "EHTTPD_PEER_LIMIT",
+            "EHTTPD_OVERLOAD_RETRY_AFTER",
+            "EHTTPD_BACKPRESSURE",
+            "EHTTPD_PANIC_RESPONSE",
+            "EHTTPD_MAINTENANCE",
+            "EHTTPD_HEADER_SIZE_MAX",
+            "EHTTPD_HEADER_FIELD_SIZE_MAX",
+            "EHTTPD_HEADER_FIELD_COUNT_MAX",
+            "EHTTPD_HEADER_TIMEOUT",
+            "EHTTPD_LOG_LEVEL",
+            "EHTTPD_TCP_NODELAY",
+            "EHTTPD_TCP_KEEPALIVE",
+            "EHTTPD_SEND_BUFFER_SIZE",
+            "EHTTPD_RECV_BUFFER_SIZE",
+            "EHTTPD_TCP_FASTOPEN",
+            "EHTTPD_DEFER_ACCEPT",
+        ];
+        for (name, _) in std::env::vars_os() {
+            let name = name.to_string_lossy();
+            if name.starts_with("EHTTPD_") && !KNOWN.contains(&name.as_ref()) {
+                log_warn!("unknown environment variable: name={name}");
+            }
+        }
+
+        // Parse the integer values
+        let parse_usize = |value: &str| value.trim().parse::<usize>().map_err(Error::from);
+        let parse_u32 = |value: &str| value.trim().parse::<u32>().map_err(Error::from);
+        let parse_buffer_size = |value: &str| {
+            usize::try_from(config::parse_size(value)?).map_err(|_| error!("Buffer size is too large: {value}"))
+        };
+        let parse_backpressure = |value: &str| match value.trim() {
+            "reject" => Ok(Backpressure::Reject),
+            "drop-oldest" => Ok(Backpressure::DropOldest),
+            value => match value.strip_prefix("block:") {
+                Some(timeout) => Ok(Backpressure::Block(config::parse_duration(timeout)?)),
+                None => Err(error!("Invalid backpressure strategy: {value}")),
+            },
+        };
+
+        // Parse all values first, so that an invalid value does not leave the server partially configured
+        let worker_min = config::env("EHTTPD_WORKER_MIN", parse_usize)?;
+        let peer_limit = config::env("EHTTPD_PEER_LIMIT", parse_usize)?;
+        let retry_after = config::env("EHTTPD_OVERLOAD_RETRY_AFTER", config::parse_duration)?;
+        let backpressure = config::env("EHTTPD_BACKPRESSURE", parse_backpressure)?;
+        let panic_response = config::env("EHTTPD_PANIC_RESPONSE", config::parse_bool)?;
+        let maintenance = config::env("EHTTPD_MAINTENANCE", config::parse_bool)?;
+        let header_size_max = config::env("EHTTPD_HEADER_SIZE_MAX", parse_buffer_size)?;
+        let field_size_max = config::env("EHTTPD_HEADER_FIELD_SIZE_MAX", parse_buffer_size)?;
+        let field_count_max = config::env("EHTTPD_HEADER_FIELD_COUNT_MAX", parse_usize)?;
+        let header_timeout = config::env("EHTTPD_HEADER_TIMEOUT", config::parse_duration)?;
+        let log_level = config::env("EHTTPD_LOG_LEVEL", str::parse)?;
+        let nodelay = config::env("EHTTPD_TCP_NODELAY", config::parse_bool)?;
+        let keepalive = config::env("EHTTPD_TCP_KEEPALIVE", config::parse_duration)?;
+        let send_buffer_size = config::env("EHTTPD_SEND_BUFFER_SIZE", parse_buffer_size)?;
+        let recv_buffer_size = config::env("EHTTPD_RECV_BUFFER_SIZE", parse_buffer_size)?;
+        let fastopen = config::env("EHTTPD_TCP_FASTOPEN", parse_u32)?;
+        let defer_accept = config::env("EHTTPD_DEFER_ACCEPT", config::parse_duration)?;
+
+        // Apply the server settings
+        // Note: The worker minimum is applied first since it is the only setting that can fail
+        if let Some(worker_min) = worker_min {
+            self.set_worker_min(worker_min)?;
+        }
+        if let Some(limit) = peer_limit {
+            self.set_peer_limit(limit);
+        }
+        if let Some(retry_after) = retry_after {
+            self.set_overload_fallback(retry_after.as_secs());
+        }
+        if let Some(backpressure) = backpressure {
+            self.set_backpressure(backpressure);
+        }
+        if let Some(enabled) = panic_response {
+            self.set_panic_response(enabled);
+        }
+        if let Some(enabled) = maintenance {
+            self.set_maintenance(enabled);
+        }
+        if header_size_max.is_some()
+            || field_size_max.is_some()
+            || field_count_max.is_some()
+            || header_timeout.is_some()
+        {
+            // Limit the field size to the header size unless it is set explicitly
+            let mut header_limits = self.header_limits.unwrap_or_default();
+            if let Some(size_max) = header_size_max {
+                (header_limits.size_max, header_limits.field_size_max) = (size_max, size_max);
+            }
+            header_limits.field_size_max = field_size_max.unwrap_or(header_limits.field_size_max);
+            header_limits.field_count_max = field_count_max.unwrap_or(header_limits.field_count_max);
+            header_limits.timeout = header_timeout.or(header_limits.timeout);
+            self.set_header_limits(header_limits);
+        }
+        if let Some(level) = log_level {
+            log::set_level(level);
+        }
+
+        // Apply the socket options
+        let socket_options = &mut self.socket_options;
+        socket_options.nodelay = nodelay.or(socket_options.nodelay);
+        socket_options.keepalive = keepalive.or(socket_options.keepalive);
+        socket_options.send_buffer_size = send_buffer_size.or(socket_options.send_buffer_size);
+        socket_options.recv_buffer_size = recv_buffer_size.or(socket_options.recv_buffer_size);
+
+        // Apply the listener options
+        self.listener_options.fastopen = fastopen.or(self.listener_options.fastopen);
+        self.listener_options.defer_accept = defer_accept.or(self.listener_options.defer_accept);
+        Ok(())
+    }
+
+    /// The server-wide cancellation token, which is the parent of all per-connection tokens
+    ///
+    /// # Note
+    /// Cancelling the token (e.g. during shutdown) signals all connection handlers that poll their token (see
+    /// [`ConnectionInfo::cancellation`]) to abort, and prevents keep-alive connections from being rescheduled.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Enables or disables the maintenance mode, where new connections are answered with a canned
+    /// `503 Service Unavailable` response (with `Retry-After` if an overload fallback is set)
+    pub fn set_maintenance(&self, enabled: bool) {
+        self.maintenance.store(enabled, SeqCst);
+    }
+    /// Creates a handle to manage the server at runtime, e.g. via a local control socket
+    pub fn control(&self) -> Control {
+        self.connections.track_requests();
+        let threadpool = self.threadpool.clone();
+        let stats = Arc::new(move || threadpool.stats());
+        Control::new(stats, self.maintenance.clone(), self.cancellation.clone(), self.connections.clone())
+    }
+    /// Gets a handle to the active connections (e.g. to drain the server during shutdown)
+    pub fn connections(&self) -> Connections {
+        self.connections.track_requests();
+        self.connections.clone()
+    }
+
+    /// Gets a snapshot of the threadpool state (e.g. for capacity planning or health checks)
+    pub fn stats(&self) -> ThreadpoolStats {
+        self.threadpool.stats()
     }
 
     /// Dispatches a connection
+    ///
+    /// # Note
+    /// If the threadpool is congested, the backpressure strategy and the overload fallback apply like for accepted
+    /// connections (see [`Self::set_backpressure`] and [`Self::set_overload_fallback`]).
     pub fn dispatch(&self, rx: Source, tx: Sink) -> Result<(), Error> {
-        // Create and dispatch the job
-        let job = Connection { handler: self.handler.clone(), rx, tx, threadpool: self.threadpool.clone() };
-        self.threadpool.dispatch(job)
+        self.dispatch_with_priority(rx, tx, Priority::Normal)
+    }
+    /// Dispatches a connection with the given priority
+    ///
+    /// # Note
+    /// The priority only applies to the first handler invocation; rescheduled keep-alive connections use the normal
+    /// priority. If the threadpool is congested, the backpressure strategy and the overload fallback apply like for
+    /// accepted connections.
+    pub fn dispatch_with_priority(&self, rx: Source, tx: Sink, priority: Priority) -> Result<(), Error> {
+        let peer = tx.peer_addr();
+        let job = self.connection(self.handler.clone(), rx, tx, peer, None, None);
+        self.dispatch_job(job, priority)
+    }
+    /// Creates a new connection job
+    fn connection(
+        &self,
+        handler: T,
+        rx: Source,
+        tx: Sink,
+        peer: Option<SocketAddr>,
+        tag: Option<Arc<str>>,
+        peer_guard: Option<PeerGuard>,
+    ) -> Connection<T, STACK_SIZE> {
+        let (on_error, threadpool) = (self.on_error.clone(), self.threadpool.clone());
+        let (on_panic, panic_response) = (self.on_panic.clone(), self.panic_response);
+        let (shedding, retry_after, backpressure) = (self.shedding, self.overload_retry_after, self.backpressure);
+        let cancellation = self.cancellation.child();
+
+        // Register the connection
+        let stream = match &tx {
+            Sink::TcpStream(stream) => Some(stream),
+            _ => None,
+        };
+        let active_guard = self.connections.register(peer, tag.clone(), stream);
+        let active = Some(active_guard.handle());
+
+        // Buffer the source once, so that the request parser can read the header in blocks
+        let rx = Source::buffered(rx);
+        let header_limits = self.header_limits;
+        let info = ConnectionInfo { peer, queued: None, cancellation, tag, header_limits, active, on_error };
+        let queued_at = Instant::now();
+        Connection {
+            active_guard,
+            handler,
+            rx,
+            tx,
+            info,
+            on_panic,
+            panic_response,
+            shedding,
+            backpressure,
+            retry_after,
+            rescheduled: false,
+            _peer_guard: peer_guard,
+            threadpool,
+            queued_at,
+        }
     }
 
     /// Listens on the given address and accepts forever
@@ -85,35 +721,264 @@ where
     {
         // Bind and listen
         let socket = TcpListener::bind(address)?;
+        self.accept_listener(socket)
+    }
+    /// Accepts forever on the given listener
+    ///
+    /// # Note
+    /// This is useful for pre-bound or inherited listeners, e.g. for socket activation (see
+    /// [`socket::listeners_from_env`]) or zero-downtime restarts.
+    pub fn accept_listener(self, socket: TcpListener) -> Result<Infallible, Error> {
+        self.accept_loop(&socket, &self.handler)
+    }
+    /// Accepts forever on the given address and handles every connection inline on the calling thread, without a
+    /// threadpool (e.g. for containers with a single CPU or embedded targets)
+    ///
+    /// # Note
+    /// Connections are handled strictly one after another; a keep-alive connection is served until it is closed before the
+    /// next connection is accepted. To avoid that a single idle or slow client blocks the server, connections get a
+    /// default socket read timeout of 30 seconds unless their tag policy sets one (see [`TagPolicy`]). The worker limit
+    /// and the backpressure strategy have no effect in this mode.
+    pub fn serve_single_threaded<A>(mut self, address: A) -> Result<Infallible, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        // Bound idle and slow clients
+        self.read_timeout = Some(Self::SINGLE_THREADED_READ_TIMEOUT);
+
+        // Replace the threadpool with an inline pool
+        let threadpool = Threadpool::new_inline(1);
+        threadpool.set_panic_policy(self.threadpool.panic_policy());
+        self.threadpool = Arc::new(threadpool);
+
+        // Bind and accept
+        let socket = TcpListener::bind(address)?;
+        self.accept_loop(&socket, &self.handler)
+    }
+    /// Accepts forever on all given listeners simultaneously, sharing the same threadpool and handler
+    ///
+    /// # Note
+    /// Each listener is served by a dedicated accept thread; this function returns the first error that terminates any of
+    /// the accept threads.
+    pub fn accept_listeners<I>(self, listeners: I) -> Result<Infallible, Error>
+    where
+        I: IntoIterator<Item = TcpListener>,
+    {
+        let handler = self.handler.clone();
+        let listeners: Vec<_> = listeners.into_iter().map(|socket| (socket, handler.clone())).collect();
+        self.accept_listeners_with(listeners)
+    }
+    /// Accepts forever on all given listeners simultaneously, where each listener has its own handler (e.g. public traffic
+    /// and metrics on different ports), while sharing the same threadpool, settings and shutdown lifecycle
+    ///
+    /// # Note
+    /// All handlers must have the same type; so to use different handlers, `T` should be a function pointer type (e.g.
+    /// `fn(&mut Source, &mut Sink) -> bool`) or a shared router. The server-wide handler is only used for manual
+    /// dispatches.
+    pub fn accept_listeners_with<I>(self, listeners: I) -> Result<Infallible, Error>
+    where
+        I: IntoIterator<Item = (TcpListener, T)>,
+    {
+        // Spawn an accept thread per listener
+        let this = Arc::new(self);
+        let (error_tx, error_rx) = flume::bounded(1);
+        for (socket, handler) in listeners {
+            let (this, error_tx) = (this.clone(), error_tx.clone());
+            let builder = thread::Builder::new().name("listener thread".to_string());
+            builder.spawn(move || {
+                let Err(e) = this.accept_loop(&socket, &handler);
+                let _ = error_tx.try_send(e);
+            })?;
+        }
+
+        // Wait for the first accept thread to fail
+        drop(error_tx);
+        match error_rx.recv() {
+            Ok(error) => Err(error),
+            Err(_) => Err(error!("No listeners to accept on")),
+        }
+    }
+    /// Accepts forever on the given listener and dispatches all connections to the given handler
+    fn accept_loop(&self, socket: &TcpListener, handler: &T) -> Result<Infallible, Error> {
+        // Apply the listener options
+        if let Err(e) = self.listener_options.apply(socket) {
+            log_warn!("failed to apply listener options: error={:?}", e.error);
+        }
+
+        let listener = socket.local_addr()?;
         loop {
-            // Accept and prepare connection
-            let (stream, _) = socket.accept()?;
+            // Accept and tag the connection
+            let (stream, peer) = socket.accept()?;
+            let (tag, policy) = self.tags.tag(listener, peer);
+            if self.maintenance.load(SeqCst) {
+                // Reject the connection
+                let _ = reject(&stream, unavailable(self.overload_retry_after));
+                continue;
+            }
+
+            // Acquire a peer slot if necessary
+            let peer_limit = policy.and_then(|policy| policy.peer_limit.as_ref()).or(self.peer_limit.as_ref());
+            let peer_guard = match peer_limit {
+                Some(peer_limit) => match peer_limit.acquire(peer.ip()) {
+                    Some(peer_guard) => Some(peer_guard),
+                    None => {
+                        // Reject the connection
+                        let _ = reject(&stream, Response::new_429_toomanyrequests());
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
+            // Apply the socket options
+            let socket_options = policy.and_then(|policy| policy.socket_options.as_ref());
+            if let Err(e) = socket_options.unwrap_or(&self.socket_options).apply(&stream) {
+                log_warn!("failed to apply socket options: peer={peer} error={:?}", e.error);
+            }
+            if let Some(Err(e)) = policy.map(|policy| policy.apply_timeouts(&stream)) {
+                log_warn!("failed to apply socket timeouts: peer={peer} error={:?}", e.error);
+            }
+            if let Some(read_timeout) = self.read_timeout.filter(|_| policy.and_then(|p| p.read_timeout).is_none()) {
+                if let Err(e) = stream.set_read_timeout(Some(read_timeout)) {
+                    log_warn!("failed to apply socket timeouts: peer={peer} error={e}");
+                }
+            }
+
+            // Prepare connection
             let tx = stream.try_clone()?;
-            let rx = BufReader::new(stream);
+            let rx = Source::from(stream);
 
             // Dispatch connection
-            let rx = Source::from_other(rx);
-            self.dispatch(rx, tx.into())?;
+            let job = self.connection(handler.clone(), rx, tx.into(), Some(peer), tag, peer_guard);
+            self.dispatch_job(job, Priority::Normal)?;
         }
     }
+    /// Dispatches a connection job using the backpressure strategy, and answers it with a canned `503 Service
+    /// Unavailable` if the threadpool is congested and there is an overload fallback
+    fn dispatch_job(&self, job: Connection<T, STACK_SIZE>, priority: Priority) -> Result<(), Error> {
+        let Err(job) = self.threadpool.dispatch_with_backpressure(job, priority, self.backpressure) else {
+            return Ok(());
+        };
+
+        // Fail if there is no overload fallback
+        if self.overload_retry_after.is_none() {
+            return Err(error!("Threadpool is congested"));
+        }
+
+        // Reject the connection
+        if let Sink::TcpStream(stream) = &job.tx {
+            let _ = reject(stream, unavailable(self.overload_retry_after));
+        }
+        Ok(())
+    }
+}
+
+/// Creates a canned `503 Service Unavailable` response with the given `Retry-After` delay in seconds if any
+fn unavailable(retry_after: Option<u64>) -> Response {
+    let mut response = Response::new_503_serviceunavailable();
+    if let Some(retry_after) = retry_after {
+        response.set_field("Retry-After", retry_after.to_string());
+    }
+    response
+}
+/// Rejects a connection by writing the given response and shutting down the writing half of the stream
+///
+/// # Note
+/// The stream is closed once it is dropped by the caller. The stream is switched to non-blocking mode, so that a slow or
+/// malicious peer cannot stall the caller (e.g. the accept thread); since the canned response fits into the socket send
+/// buffer of a fresh connection, it is usually written completely anyway.
+fn reject(mut stream: &TcpStream, mut response: Response) -> Result<(), Error> {
+    // Write the response
+    stream.set_nonblocking(true)?;
+    response.make_error_body();
+    response.set_connection_close();
+    response.to_stream(&mut stream)?;
+    stream.shutdown(Shutdown::Write)?;
+
+    // Discard any pending request data, since closing a socket with unread data resets the connection which may
+    // destroy the response before the client has read it
+    let _ = io::copy(&mut stream, &mut io::sink());
+    Ok(())
 }
 
 /// An adapter to bridge a `source,sink`-handler to a `request->response`-handler
+///
+/// # Note
+/// Unbuffered sources are wrapped in place (see [`bytes::Source::buffered`]); the server buffers its connections
+/// upfront, so this only applies to manually dispatched connections.
 #[must_use]
 pub fn reqresp<F>(source: &mut Source, sink: &mut Sink, handler: F) -> bool
 where
     F: Fn(Request) -> Response + Send + Sync + 'static,
 {
     // Read request
-    let Ok(Some(request)) = Request::from_stream(source) else {
-        return false;
+    let request = match Request::from_stream(source) {
+        Ok(Some(request)) => request,
+        Ok(None) => return false,
+        Err(e) if e.io_kind() == Some(io::ErrorKind::TimedOut) => {
+            // Answer requests that have exceeded the header deadline before closing the connection
+            let mut response: Response = Response::new(StatusCode::REQUEST_TIMEOUT);
+            response.make_error_body();
+            response.set_connection_close();
+            let _ = response.to_stream(sink);
+            log::dropped(sink, "read-request", &e);
+            ConnectionInfo::report_current_error(&e);
+            return false;
+        }
+        Err(e) => {
+            log::dropped(sink, "read-request", &e);
+            ConnectionInfo::report_current_error(&e);
+            return false;
+        }
     };
 
     // Handle request and write response
+    #[cfg(feature = "tracing")]
+    let span = {
+        // Only format the method and target if the span is enabled
+        let (method, target, status) = (tracing::field::Empty, tracing::field::Empty, tracing::field::Empty);
+        let span = tracing::info_span!("request", method, target, peer = ?request.peer, status);
+        if !span.is_disabled() {
+            span.record("method", tracing::field::display(String::from_utf8_lossy(&request.method)));
+            span.record("target", tracing::field::display(String::from_utf8_lossy(&request.target)));
+        }
+        span.entered()
+    };
+    let (mut timings, start) = (request.timings, Instant::now());
+    ConnectionInfo::set_request(|| {
+        let (method, target) = (String::from_utf8_lossy(&request.method), String::from_utf8_lossy(&request.target));
+        Some(Arc::from(format!("{method} {target}")))
+    });
+    let (target, version) = (request.target.clone(), request.version.clone());
     let mut response = handler(request);
-    let Ok(_) = response.to_stream(sink) else {
+    // Hold the connection until a deferred response is completed
+    response.resolve();
+
+    // Clients before HTTP/1.1 do not support chunked transfer encoding, so delimit the body by closing the connection
+    if response.body.framing == Framing::Chunked && !version.eq_ignore_ascii_case(b"HTTP/1.1") {
+        let mut body = mem::take(&mut response.body);
+        body.framing = Framing::Close;
+        response.set_body(body);
+    }
+    #[cfg(feature = "tracing")]
+    if !span.is_disabled() {
+        span.record("status", tracing::field::display(String::from_utf8_lossy(&response.status)));
+    }
+    let handled = Instant::now();
+    let mut body_sent = 0;
+    let written = response.write_to_sink_counted(sink, &mut body_sent);
+    ConnectionInfo::set_request(|| None);
+    if let Err(e) = written {
+        // Report the partial write so that the application can support resumption
+        PartialWrite::new(target, &response, body_sent).report();
+        log::dropped(sink, "write-response", &e);
+        ConnectionInfo::report_current_error(&e);
         return false;
-    };
+    }
+
+    // Report the phase timings
+    (timings.handler, timings.write) = (Some(handled - start), Some(handled.elapsed()));
+    timing::report(&timings);
 
     // Mark connection as to-be-rescheduled
     !response.has_connection_close()
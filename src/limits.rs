@@ -0,0 +1,68 @@
+//! Implements connection limits
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// A limit for the amount of concurrent connections per peer IP address
+#[derive(Debug, Clone)]
+pub struct PeerLimit {
+    /// The maximum amount of live connections per peer
+    limit: usize,
+    /// The amount of live connections per peer
+    peers: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+impl PeerLimit {
+    /// Creates a new per-peer limit
+    pub fn new(limit: usize) -> Self {
+        Self { limit, peers: Arc::default() }
+    }
+
+    /// Tries to acquire a connection slot for the given peer or returns `None` if the peer has reached the limit
+    pub fn acquire(&self, peer: IpAddr) -> Option<PeerGuard> {
+        // Get the connection counter for the peer
+        let mut peers = self.peers.lock().unwrap_or_else(PoisonError::into_inner);
+        let count = peers.entry(peer).or_default();
+        if *count >= self.limit {
+            return None;
+        }
+
+        // Acquire the slot
+        *count += 1;
+        Some(PeerGuard { peer, peers: self.peers.clone() })
+    }
+    /// The amount of live connections for the given peer
+    pub fn count(&self, peer: IpAddr) -> usize {
+        let peers = self.peers.lock().unwrap_or_else(PoisonError::into_inner);
+        peers.get(&peer).copied().unwrap_or_default()
+    }
+}
+
+/// An acquired connection slot which is released if the guard is dropped
+#[derive(Debug)]
+pub struct PeerGuard {
+    /// The peer address
+    peer: IpAddr,
+    /// The amount of live connections per peer
+    peers: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+impl PeerGuard {
+    /// The peer address
+    pub fn peer(&self) -> IpAddr {
+        self.peer
+    }
+}
+impl Drop for PeerGuard {
+    fn drop(&mut self) {
+        // Decrement the counter and remove the peer if it has no more live connections
+        let mut peers = self.peers.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = peers.get_mut(&self.peer) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                peers.remove(&self.peer);
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Implements a minimal logging facility
+//!
+//! # `log` crate integration
+//! If the `log` feature is enabled, all records are forwarded to the [`log`](https://docs.rs/log) crate facade with the
+//! target `ehttpd` instead of being written to `stderr`. In this case, the default level is `Level::Debug` so that the
+//! filtering is up to the application's logger.
+//!
+//! # Context
+//! Each thread has a logging context (e.g. `peer=127.0.0.1:4242 request=7`) that is prepended to all records of that
+//! thread, so that interleaved records of concurrent workers remain attributable. The server sets the peer address for
+//! every connection; handlers can append further fields via [`enter_context`].
+
+use crate::{bytes::Sink, error, error::Error};
+use std::{
+    cell::RefCell,
+    fmt::{self, Arguments, Display, Formatter, Write},
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering::Relaxed},
+};
+
+/// Logs a message at the given level
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => {{
+        let level = $level;
+        if $crate::log::enabled(level) {
+            $crate::log::write(level, format_args!($($arg)*));
+        }
+    }};
+}
+/// Logs an error message
+#[macro_export]
+macro_rules! log_error {
+    ($($arg:tt)*) => { $crate::log!($crate::log::Level::Error, $($arg)*) };
+}
+/// Logs a warning message
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)*) => { $crate::log!($crate::log::Level::Warn, $($arg)*) };
+}
+/// Logs an info message
+#[macro_export]
+macro_rules! log_info {
+    ($($arg:tt)*) => { $crate::log!($crate::log::Level::Info, $($arg)*) };
+}
+/// Logs a debug message
+#[macro_export]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { $crate::log!($crate::log::Level::Debug, $($arg)*) };
+}
+
+/// The default log level
+#[cfg(not(feature = "log"))]
+const LEVEL_DEFAULT: Level = Level::Warn;
+/// The default log level
+#[cfg(feature = "log")]
+const LEVEL_DEFAULT: Level = Level::Debug;
+
+/// The current log level
+static LEVEL: AtomicU8 = AtomicU8::new(LEVEL_DEFAULT as u8);
+
+thread_local! {
+    /// The logging context of the current thread
+    static CONTEXT: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// A log level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Level {
+    /// Logging is disabled
+    Off = 0,
+    /// Errors only
+    Error = 1,
+    /// Warnings and errors
+    Warn = 2,
+    /// Informational messages, warnings and errors
+    Info = 3,
+    /// All messages including debug messages
+    Debug = 4,
+}
+impl Level {
+    /// Converts `self` into the corresponding `log` crate level
+    #[cfg(feature = "log")]
+    fn to_facade(self) -> Option<::log::Level> {
+        match self {
+            Self::Off => None,
+            Self::Error => Some(::log::Level::Error),
+            Self::Warn => Some(::log::Level::Warn),
+            Self::Info => Some(::log::Level::Info),
+            Self::Debug => Some(::log::Level::Debug),
+        }
+    }
+}
+impl FromStr for Level {
+    type Err = Error;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level.to_ascii_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            _ => Err(error!("Invalid log level: {level}")),
+        }
+    }
+}
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "OFF"),
+            Self::Error => write!(f, "ERROR"),
+            Self::Warn => write!(f, "WARN"),
+            Self::Info => write!(f, "INFO"),
+            Self::Debug => write!(f, "DEBUG"),
+        }
+    }
+}
+
+/// Sets the log level (defaults to `Level::Warn`, or `Level::Debug` if the `log` feature is enabled)
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Relaxed);
+}
+/// The current log level
+pub fn level() -> Level {
+    match LEVEL.load(Relaxed) {
+        0 => Level::Off,
+        1 => Level::Error,
+        2 => Level::Warn,
+        3 => Level::Info,
+        _ => Level::Debug,
+    }
+}
+/// Whether messages at the given level are logged or not
+#[cfg(not(feature = "log"))]
+pub fn enabled(level: Level) -> bool {
+    level != Level::Off && level <= self::level()
+}
+/// Whether messages at the given level are logged or not
+#[cfg(feature = "log")]
+pub fn enabled(level: Level) -> bool {
+    let Some(facade_level) = level.to_facade() else {
+        return false;
+    };
+    level <= self::level() && ::log::log_enabled!(target: "ehttpd", facade_level)
+}
+
+/// Appends the given field(s) to the logging context of the current thread until the returned guard is dropped
+///
+/// # Note
+/// Contexts nest: Dropping the guard restores the context that was active when the guard was created, so guards should be
+/// dropped in reverse order.
+pub fn enter_context<T>(context: T) -> ContextGuard
+where
+    T: Display,
+{
+    CONTEXT.with(|current| {
+        // Append the context
+        let mut current = current.borrow_mut();
+        let previous_len = current.len();
+        match current.is_empty() {
+            true => write!(current, "{context}"),
+            false => write!(current, " {context}"),
+        }
+        .expect("failed to write to string");
+        ContextGuard { previous_len }
+    })
+}
+/// The logging context of the current thread
+pub fn context() -> String {
+    CONTEXT.with(|current| current.borrow().clone())
+}
+
+/// A guard that restores the previous logging context on drop
+#[derive(Debug)]
+#[must_use = "the context is removed immediately if the guard is dropped"]
+pub struct ContextGuard {
+    /// The length of the previous context
+    previous_len: usize,
+}
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        CONTEXT.with(|current| current.borrow_mut().truncate(self.previous_len));
+    }
+}
+
+/// Writes a log record to `stderr`
+///
+/// # Note
+/// Unlike `eprintln!`, this function never panics, even if `stderr` is closed or otherwise unavailable.
+#[doc(hidden)]
+#[cfg(not(feature = "log"))]
+pub fn write(level: Level, message: Arguments) {
+    use std::io::{self, Write};
+    CONTEXT
+        .with(|context| match context.borrow().as_str() {
+            "" => writeln!(io::stderr().lock(), "[ehttpd {level}] {message}"),
+            context => writeln!(io::stderr().lock(), "[ehttpd {level}] [{context}] {message}"),
+        })
+        .unwrap_or_default();
+}
+/// Forwards a log record to the `log` crate facade
+#[doc(hidden)]
+#[cfg(feature = "log")]
+pub fn write(level: Level, message: Arguments) {
+    let Some(facade_level) = level.to_facade() else {
+        return;
+    };
+    CONTEXT.with(|context| match context.borrow().as_str() {
+        "" => ::log::log!(target: "ehttpd", facade_level, "{message}"),
+        context => ::log::log!(target: "ehttpd", facade_level, "[{context}] {message}"),
+    });
+}
+
+/// Logs a structured debug record for a connection that has been dropped due to an error
+pub(crate) fn dropped(sink: &Sink, phase: &str, error: &Error) {
+    // Avoid the peer lookup if the record is discarded anyways
+    if !enabled(Level::Debug) {
+        return;
+    }
+
+    // Format the peer address and the error kind
+    let peer = match sink.peer_addr() {
+        Some(peer) => peer.to_string(),
+        None => "-".to_string(),
+    };
+    let kind = match error.io_kind() {
+        _ if error.is_client_abort() => "ClientAborted".to_string(),
+        Some(kind) => format!("{kind:?}"),
+        None => "Other".to_string(),
+    };
+    log_debug!("connection dropped: peer={peer} phase={phase} kind={kind} error={:?}", error.error);
+}
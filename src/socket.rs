@@ -0,0 +1,221 @@
+//! Socket helpers
+
+use crate::error::Error;
+use socket2::{SockRef, TcpKeepalive};
+use std::{
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+/// Socket options for accepted connections
+///
+/// # Note
+/// Options that are `None` are left at the platform defaults.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SocketOptions {
+    /// Whether to disable Nagle's algorithm (`TCP_NODELAY`)
+    pub nodelay: Option<bool>,
+    /// The idle time after which TCP keepalive probes are sent (enables `SO_KEEPALIVE`)
+    pub keepalive: Option<Duration>,
+    /// The interval between TCP keepalive probes (ignored on platforms that do not support it)
+    pub keepalive_interval: Option<Duration>,
+    /// The size of the send buffer (`SO_SNDBUF`)
+    pub send_buffer_size: Option<usize>,
+    /// The size of the receive buffer (`SO_RCVBUF`)
+    pub recv_buffer_size: Option<usize>,
+    /// The linger timeout (`SO_LINGER`); `Some(None)` explicitly disables lingering
+    pub linger: Option<Option<Duration>>,
+}
+impl SocketOptions {
+    /// Applies the options to the given stream
+    pub fn apply(&self, stream: &TcpStream) -> Result<(), Error> {
+        let socket = SockRef::from(stream);
+        if let Some(nodelay) = self.nodelay {
+            socket.set_tcp_nodelay(nodelay)?;
+        }
+        if let Some(keepalive) = self.keepalive {
+            let keepalive = Self::keepalive_interval(TcpKeepalive::new().with_time(keepalive), self.keepalive_interval);
+            socket.set_tcp_keepalive(&keepalive)?;
+        }
+        if let Some(send_buffer_size) = self.send_buffer_size {
+            socket.set_send_buffer_size(send_buffer_size)?;
+        }
+        if let Some(recv_buffer_size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(recv_buffer_size)?;
+        }
+        if let Some(linger) = self.linger {
+            socket.set_linger(linger)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the keepalive interval if supported by the platform
+    #[cfg(any(
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "netbsd",
+        target_os = "windows"
+    ))]
+    fn keepalive_interval(keepalive: TcpKeepalive, interval: Option<Duration>) -> TcpKeepalive {
+        match interval {
+            Some(interval) => keepalive.with_interval(interval),
+            None => keepalive,
+        }
+    }
+    /// Sets the keepalive interval if supported by the platform
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "netbsd",
+        target_os = "windows"
+    )))]
+    fn keepalive_interval(keepalive: TcpKeepalive, _interval: Option<Duration>) -> TcpKeepalive {
+        keepalive
+    }
+}
+
+/// Socket options for listening sockets
+///
+/// # Note
+/// Options that are `None` are left at the platform defaults. The options are currently only supported on Linux and
+/// Android; on other platforms, applying a set option fails.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ListenerOptions {
+    /// The TCP Fast Open queue length (`TCP_FASTOPEN`), which allows clients to send the request within the handshake
+    pub fastopen: Option<u32>,
+    /// The time to wait for the first data before a connection is accepted (`TCP_DEFER_ACCEPT`), so that the accept loop
+    /// is not woken up for connections that do not send a request
+    pub defer_accept: Option<Duration>,
+}
+impl ListenerOptions {
+    /// Applies the options to the given listener
+    pub fn apply(&self, listener: &TcpListener) -> Result<(), Error> {
+        if let Some(fastopen) = self.fastopen {
+            Self::set_fastopen(listener, fastopen)?;
+        }
+        if let Some(defer_accept) = self.defer_accept {
+            Self::set_defer_accept(listener, defer_accept)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the TCP Fast Open queue length
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn set_fastopen(listener: &TcpListener, queue_len: u32) -> Result<(), Error> {
+        let queue_len = libc::c_int::try_from(queue_len)?;
+        Self::setsockopt_tcp(listener, libc::TCP_FASTOPEN, queue_len)
+    }
+    /// Sets the TCP Fast Open queue length
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn set_fastopen(_listener: &TcpListener, _queue_len: u32) -> Result<(), Error> {
+        Err(crate::error!("TCP Fast Open is not supported on this platform"))
+    }
+
+    /// Sets the deferred accept timeout
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn set_defer_accept(listener: &TcpListener, timeout: Duration) -> Result<(), Error> {
+        // The timeout is specified in seconds; round up so that a non-zero timeout does not disable the option
+        let timeout = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+        let timeout = libc::c_int::try_from(timeout)?;
+        Self::setsockopt_tcp(listener, libc::TCP_DEFER_ACCEPT, timeout)
+    }
+    /// Sets the deferred accept timeout
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn set_defer_accept(_listener: &TcpListener, _timeout: Duration) -> Result<(), Error> {
+        Err(crate::error!("Deferred accept is not supported on this platform"))
+    }
+
+    /// Sets an integer `IPPROTO_TCP`-level socket option
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn setsockopt_tcp(listener: &TcpListener, option: libc::c_int, value: libc::c_int) -> Result<(), Error> {
+        use std::{io, mem, os::unix::io::AsRawFd};
+
+        // Set the option
+        let (value_ptr, value_len) = (&value as *const libc::c_int, mem::size_of::<libc::c_int>());
+        // Safety: The file descriptor is valid for the lifetime of the listener, and the value pointer and length refer
+        // to a valid `c_int`
+        let result = unsafe {
+            libc::setsockopt(
+                listener.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                option,
+                value_ptr.cast(),
+                value_len as libc::socklen_t,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+/// Takes over the listening sockets passed by the service manager via `LISTEN_PID`/`LISTEN_FDS` (e.g. systemd socket
+/// activation)
+///
+/// # Note
+/// Returns an empty vector if the environment does not contain any sockets for this process. The sockets are taken over
+/// only once: the variables are removed from the environment, and subsequent calls return an empty vector. Every file
+/// descriptor is validated to be a stream socket before it is taken over. Since the environment is modified, this
+/// function should be called early, before other threads read the environment.
+#[cfg(target_family = "unix")]
+pub fn listeners_from_env() -> Result<Vec<TcpListener>, Error> {
+    use socket2::Type;
+    use std::{
+        env,
+        os::unix::io::{BorrowedFd, FromRawFd},
+        process,
+        sync::atomic::{AtomicBool, Ordering::SeqCst},
+    };
+
+    /// The first passed file descriptor as defined by `sd_listen_fds(3)`
+    const LISTEN_FDS_START: i32 = 3;
+    /// Whether the sockets have already been taken over
+    static TAKEN: AtomicBool = AtomicBool::new(false);
+
+    // Take the variables once
+    if TAKEN.swap(true, SeqCst) {
+        return Ok(Vec::new());
+    }
+    let (listen_pid, listen_fds) = (env::var("LISTEN_PID"), env::var("LISTEN_FDS"));
+    for name in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        env::remove_var(name);
+    }
+
+    // Ensure the sockets are meant for us and get the socket count
+    let (Ok(listen_pid), Ok(listen_fds)) = (listen_pid, listen_fds) else {
+        return Ok(Vec::new());
+    };
+    if listen_pid.parse::<u32>()? != process::id() {
+        return Ok(Vec::new());
+    }
+    let listen_fds: i32 = listen_fds.parse()?;
+    let fds = LISTEN_FDS_START..LISTEN_FDS_START.saturating_add(listen_fds);
+
+    // Validate that all file descriptors are stream sockets before taking over any of them
+    for fd in fds.clone() {
+        // Safety: The descriptor is only borrowed for the duration of the check; an invalid descriptor is reported as error
+        let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+        match SockRef::from(&borrowed).r#type() {
+            Ok(Type::STREAM) => (),
+            Ok(_) => return Err(crate::error!("Inherited file descriptor {fd} is not a stream socket")),
+            Err(e) => return Err(crate::error!("Inherited file descriptor {fd} is not a socket: {e}")),
+        }
+    }
+
+    // Take over the sockets
+    let listeners = fds
+        // Safety: The file descriptors are valid stream sockets, and they are taken over only once since the environment
+        // has been consumed
+        .map(|fd| unsafe { TcpListener::from_raw_fd(fd) })
+        .collect();
+    Ok(listeners)
+}
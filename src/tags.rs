@@ -0,0 +1,82 @@
+//! Connection tags and per-tag policies
+
+use crate::{error::Error, limits::PeerLimit, socket::SocketOptions};
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    net::{SocketAddr, TcpStream},
+    sync::Arc,
+    time::Duration,
+};
+
+/// A callback that tags an accepted connection based on the local listener address and the peer address
+type TagCallback = Arc<dyn Fn(SocketAddr, SocketAddr) -> Option<Arc<str>> + Send + Sync + 'static>;
+
+/// A policy that applies to all connections with a given tag
+///
+/// # Note
+/// Options that are `None` fall back to the server-wide settings.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct TagPolicy {
+    /// The per-peer connection limit for connections with this tag
+    ///
+    /// # Note
+    /// The limit is shared by all connections with this tag, and is independent of the server-wide limit.
+    pub peer_limit: Option<PeerLimit>,
+    /// The socket options for connections with this tag
+    pub socket_options: Option<SocketOptions>,
+    /// The socket read timeout
+    pub read_timeout: Option<Duration>,
+    /// The socket write timeout
+    pub write_timeout: Option<Duration>,
+}
+impl TagPolicy {
+    /// Applies the socket timeouts to the given stream
+    pub(crate) fn apply_timeouts(&self, stream: &TcpStream) -> Result<(), Error> {
+        if let Some(read_timeout) = self.read_timeout {
+            stream.set_read_timeout(Some(read_timeout))?;
+        }
+        if let Some(write_timeout) = self.write_timeout {
+            stream.set_write_timeout(Some(write_timeout))?;
+        }
+        Ok(())
+    }
+}
+
+/// The connection tagger and the per-tag policies of a server
+#[derive(Default)]
+pub(crate) struct Tags {
+    /// The callback to tag accepted connections
+    tagger: Option<TagCallback>,
+    /// The policies by tag
+    policies: HashMap<Arc<str>, TagPolicy>,
+}
+impl Tags {
+    /// Sets the callback to tag accepted connections
+    pub fn set_tagger<F>(&mut self, tagger: F)
+    where
+        F: Fn(SocketAddr, SocketAddr) -> Option<Arc<str>> + Send + Sync + 'static,
+    {
+        self.tagger = Some(Arc::new(tagger));
+    }
+    /// Sets the policy for the given tag
+    pub fn set_policy<T>(&mut self, tag: T, policy: TagPolicy)
+    where
+        T: Into<Arc<str>>,
+    {
+        self.policies.insert(tag.into(), policy);
+    }
+
+    /// Tags a connection and returns the tag together with its policy if any
+    pub fn tag(&self, listener: SocketAddr, peer: SocketAddr) -> (Option<Arc<str>>, Option<&TagPolicy>) {
+        let tag = self.tagger.as_ref().and_then(|tagger| tagger(listener, peer));
+        let policy = tag.as_ref().and_then(|tag| self.policies.get(tag));
+        (tag, policy)
+    }
+}
+impl Debug for Tags {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Tags").field("policies", &self.policies).finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,219 @@
+//! Structured-random valid and invalid HTTP requests for property and fuzz testing
+//!
+//! # `arbitrary` crate integration
+//! If both the `testing` and the `arbitrary` features are enabled, [`ArbitraryRequest`] implements
+//! `arbitrary::Arbitrary`, so it can be used directly as input type for `cargo fuzz` targets.
+//!
+//! # Note
+//! Enabling `arbitrary` alone is not sufficient, since this module only exists with the `testing` feature.
+
+/// The methods that are used for valid requests
+const METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "DELETE", "OPTIONS", "PATCH"];
+/// The field names that are used for valid requests besides random tokens
+const FIELD_NAMES: &[&str] = &["Host", "Accept", "User-Agent", "Content-Type", "Cookie", "X-Request-Id"];
+/// The characters that are valid in a token (RFC 9110)
+const TOKEN_CHARS: &[u8] = b"!#$%&'*+-.^_`|~0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+/// The characters that are used for path segments and query values
+const PATH_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789-._~%";
+
+/// A generated request
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ArbitraryRequest {
+    /// The raw request header
+    pub raw: Vec<u8>,
+    /// Whether the request is valid or has been corrupted so that it must be rejected
+    pub valid: bool,
+    /// The method of the (uncorrupted) request
+    pub method: Vec<u8>,
+    /// The target of the (uncorrupted) request
+    pub target: Vec<u8>,
+    /// The header fields of the (uncorrupted) request
+    pub fields: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// A deterministic generator for structured-random requests
+///
+/// # Example
+/// ```
+/// # use ehttpd::testing::arbitrary::Generator;
+/// let mut generator = Generator::new(7);
+/// for _ in 0..16 {
+///     let request = generator.request();
+///     // Feed `request.raw` into the handler under test and check `request.valid`
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Generator {
+    /// The PRNG state
+    state: u64,
+}
+impl Generator {
+    /// The header size limit that invalid requests may exceed
+    const HEADER_SIZE_MAX: usize = 4096;
+
+    /// Creates a new generator with the given seed
+    pub fn new(seed: u64) -> Self {
+        // Avoid the zero state of xorshift
+        Self { state: (seed ^ 0x9E37_79B9_7F4A_7C15) | 1 }
+    }
+
+    /// Generates a valid or an invalid request with equal probability
+    pub fn request(&mut self) -> ArbitraryRequest {
+        match self.below(2) {
+            0 => self.valid_request(),
+            _ => self.invalid_request(),
+        }
+    }
+    /// Generates a valid request
+    pub fn valid_request(&mut self) -> ArbitraryRequest {
+        // Generate the start line
+        let method = match self.below(4) {
+            0 => self.token(1, 8),
+            _ => self.pick(METHODS).as_bytes().to_vec(),
+        };
+        let target = self.target();
+        let version: &[u8] = match self.below(4) {
+            0 => b"HTTP/1.0",
+            _ => b"HTTP/1.1",
+        };
+
+        // Generate the fields
+        let fields: Vec<_> = (0..self.below(9)).map(|_| self.field()).collect();
+
+        // Serialize the request with optional whitespace around the field values
+        let mut raw = [method.as_slice(), b" ", &target, b" ", version, b"\r\n"].concat();
+        for (name, value) in &fields {
+            let (leading, trailing) = (self.whitespace(), self.whitespace());
+            raw.extend_from_slice(&[name.as_slice(), b":", &leading, value, &trailing, b"\r\n"].concat());
+        }
+        raw.extend_from_slice(b"\r\n");
+        ArbitraryRequest { raw, valid: true, method, target, fields }
+    }
+    /// Generates an invalid request that must be rejected by a conforming parser
+    pub fn invalid_request(&mut self) -> ArbitraryRequest {
+        let mut request = self.valid_request();
+        request.valid = false;
+        request.raw = match self.below(5) {
+            0 => {
+                // Use an unsupported version
+                let version = self.pick(&["HTTP/2.0", "HTTP/0.9", "HTTX/1.1", "http/1.1"]);
+                let start_line = [request.method.as_slice(), b" ", &request.target, b" ", version.as_bytes()].concat();
+                Self::replace_start_line(&request.raw, &start_line)
+            }
+            1 => {
+                // Remove the separators from the start line
+                let start_line = [request.method.as_slice(), &request.target, b"HTTP/1.1"].concat();
+                Self::replace_start_line(&request.raw, &start_line)
+            }
+            2 => {
+                // Insert a field without colon after the start line
+                let start = Self::start_line_len(&request.raw);
+                let field = [self.token(1, 16).as_slice(), b" ", &self.token(1, 16), b"\r\n"].concat();
+                [&request.raw[..start], &field, &request.raw[start..]].concat()
+            }
+            3 => {
+                // Exceed the header size limit
+                let start = Self::start_line_len(&request.raw);
+                let field = [b"X-Padding: ".as_slice(), &vec![b'x'; Self::HEADER_SIZE_MAX], b"\r\n"].concat();
+                [&request.raw[..start], &field, &request.raw[start..]].concat()
+            }
+            _ => {
+                // Truncate the request within the header
+                let len = 1 + self.below(request.raw.len() as u64 - 4) as usize;
+                request.raw[..len].to_vec()
+            }
+        };
+        request
+    }
+
+    /// Generates a request target
+    fn target(&mut self) -> Vec<u8> {
+        // Generate the path segments
+        let mut target = Vec::new();
+        for _ in 0..=self.below(4) {
+            target.push(b'/');
+            target.extend(self.string(PATH_CHARS, 0, 12));
+        }
+
+        // Generate an optional query
+        if self.below(3) == 0 {
+            target.push(b'?');
+            target.extend(self.string(PATH_CHARS, 1, 8));
+            target.push(b'=');
+            target.extend(self.string(PATH_CHARS, 0, 8));
+        }
+        target
+    }
+    /// Generates a header field
+    fn field(&mut self) -> (Vec<u8>, Vec<u8>) {
+        let name = match self.below(2) {
+            0 => self.token(1, 16),
+            _ => self.pick(FIELD_NAMES).as_bytes().to_vec(),
+        };
+
+        // Generate a value of visible ASCII characters with inner spaces but without surrounding whitespace
+        let len = self.below(32) as usize;
+        let mut value: Vec<u8> = (0..len).map(|_| b' ' + self.below(95) as u8).collect();
+        let trimmed = value.iter().rposition(|byte| *byte != b' ').map(|end| end + 1).unwrap_or(0);
+        value.truncate(trimmed);
+        let leading = value.iter().position(|byte| *byte != b' ').unwrap_or(value.len());
+        (name, value.split_off(leading))
+    }
+    /// Generates optional whitespace
+    fn whitespace(&mut self) -> Vec<u8> {
+        match self.below(3) {
+            0 => Vec::new(),
+            1 => b" ".to_vec(),
+            _ => b" \t".to_vec(),
+        }
+    }
+    /// Generates a token
+    fn token(&mut self, len_min: u64, len_max: u64) -> Vec<u8> {
+        self.string(TOKEN_CHARS, len_min, len_max)
+    }
+    /// Generates a string from the given characters
+    fn string(&mut self, chars: &[u8], len_min: u64, len_max: u64) -> Vec<u8> {
+        let len = len_min + self.below(len_max - len_min + 1);
+        (0..len).map(|_| *self.pick(chars)).collect()
+    }
+
+    /// Replaces the start line of the given request
+    fn replace_start_line(raw: &[u8], start_line: &[u8]) -> Vec<u8> {
+        let start = Self::start_line_len(raw);
+        [start_line, b"\r\n", &raw[start..]].concat()
+    }
+    /// The length of the start line including the line break
+    fn start_line_len(raw: &[u8]) -> usize {
+        let end = raw.windows(2).position(|window| window == b"\r\n").expect("missing start line");
+        end + 2
+    }
+
+    /// Picks a random element
+    fn pick<'a, T>(&mut self, elements: &'a [T]) -> &'a T {
+        &elements[self.below(elements.len() as u64) as usize]
+    }
+    /// Generates a random number below the given limit
+    fn below(&mut self, limit: u64) -> u64 {
+        self.next() % limit.max(1)
+    }
+    /// Generates the next random number (xorshift64*)
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+#[cfg(all(feature = "testing", feature = "arbitrary"))]
+impl<'a> ::arbitrary::Arbitrary<'a> for ArbitraryRequest {
+    fn arbitrary(unstructured: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+        let (seed, valid) = (u64::arbitrary(unstructured)?, bool::arbitrary(unstructured)?);
+        let mut generator = Generator::new(seed);
+        match valid {
+            true => Ok(generator.valid_request()),
+            false => Ok(generator.invalid_request()),
+        }
+    }
+}
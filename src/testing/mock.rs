@@ -0,0 +1,201 @@
+//! A scripted in-process mock upstream for integration tests
+
+use crate::{
+    bytes::{Sink, Source},
+    error::Error,
+    http::{Request, RequestExt, Response},
+};
+use socket2::SockRef;
+use std::{
+    collections::VecDeque,
+    fmt::{self, Debug, Formatter},
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering::SeqCst},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+
+/// A scripted action for the next request
+#[derive(Debug)]
+enum Action {
+    /// Writes the serialized response after the given delay
+    Respond { delay: Duration, response: Vec<u8> },
+    /// Resets the connection (`SO_LINGER` with a zero timeout)
+    Reset,
+    /// Closes the connection without a response
+    Close,
+}
+
+/// A request that has been received by the mock upstream
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReceivedRequest {
+    /// The raw request header
+    pub header: Vec<u8>,
+    /// The request body (only `Content-Length`-framed bodies are supported)
+    pub body: Vec<u8>,
+}
+
+/// The shared mock state
+#[derive(Debug, Default)]
+struct State {
+    /// The scripted actions
+    script: Mutex<VecDeque<Action>>,
+    /// The received requests
+    received: Mutex<Vec<ReceivedRequest>>,
+    /// The amount of requests that arrived after the script has been exhausted
+    unexpected: Mutex<usize>,
+    /// Whether the mock has been dropped
+    stopped: AtomicBool,
+}
+impl State {
+    /// Locks the given mutex and ignores poisoning
+    fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        mutex.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A scripted mock upstream that listens on a random local port and answers requests in the scripted order
+///
+/// # Note
+/// Every request consumes the next scripted action; requests after the script has been exhausted are answered with
+/// `500 Internal Server Error` and counted as unexpected. Connections are kept alive until an action closes them or the
+/// peer disconnects. The mock stops accepting connections when it is dropped.
+///
+/// # Example
+/// ```
+/// # use ehttpd::{http::{Response, ResponseExt}, testing::mock::MockUpstream};
+/// let upstream = MockUpstream::start().expect("failed to start mock upstream");
+/// upstream.respond(Response::new_200_ok());
+/// upstream.reset();
+/// // Point the client under test to `upstream.address()`
+/// ```
+pub struct MockUpstream {
+    /// The listening address
+    address: SocketAddr,
+    /// The shared state
+    state: Arc<State>,
+}
+impl MockUpstream {
+    /// Starts a new mock upstream on a random local port
+    pub fn start() -> Result<Self, Error> {
+        // Bind the listener
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(State::default());
+
+        // Spawn the accept thread
+        let accept_state = state.clone();
+        let builder = thread::Builder::new().name("mock upstream".to_string());
+        builder.spawn(move || Self::accept_loop(&listener, &accept_state))?;
+        Ok(Self { address, state })
+    }
+
+    /// The listening address
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Scripts a response for the next request
+    pub fn respond(&self, response: Response) {
+        self.respond_after(Duration::ZERO, response);
+    }
+    /// Scripts a response that is written after the given delay (e.g. to test timeouts)
+    pub fn respond_after(&self, delay: Duration, mut response: Response) {
+        let mut serialized = Vec::new();
+        response.to_stream(&mut serialized).expect("failed to serialize response");
+        self.push(Action::Respond { delay, response: serialized });
+    }
+    /// Scripts a connection reset for the next request
+    pub fn reset(&self) {
+        self.push(Action::Reset);
+    }
+    /// Scripts a connection close without response for the next request
+    pub fn close(&self) {
+        self.push(Action::Close);
+    }
+
+    /// The amount of scripted actions that have not been consumed yet
+    pub fn pending(&self) -> usize {
+        State::lock(&self.state.script).len()
+    }
+    /// The amount of requests that arrived after the script has been exhausted
+    pub fn unexpected(&self) -> usize {
+        *State::lock(&self.state.unexpected)
+    }
+    /// The received requests in arrival order
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        State::lock(&self.state.received).clone()
+    }
+
+    /// Appends an action to the script
+    fn push(&self, action: Action) {
+        State::lock(&self.state.script).push_back(action);
+    }
+
+    /// Accepts connections until the mock is dropped
+    fn accept_loop(listener: &TcpListener, state: &Arc<State>) {
+        for stream in listener.incoming() {
+            // Check if the mock has been dropped
+            if state.stopped.load(SeqCst) {
+                return;
+            }
+
+            // Serve the connection
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let state = state.clone();
+            thread::spawn(move || Self::serve(stream, &state));
+        }
+    }
+    /// Serves a connection until an action closes it or the peer disconnects
+    fn serve(stream: TcpStream, state: &State) -> Result<(), Error> {
+        let mut rx = Source::buffered(stream.try_clone()?);
+        let mut tx = Sink::from(stream.try_clone()?);
+        loop {
+            // Read the request and the body
+            let Some(request) = Request::<4096>::from_stream(&mut rx)? else {
+                return Ok(());
+            };
+            let (header, content_length) = (request.header.to_vec(), request.content_length()?.unwrap_or_default());
+            let mut body = Vec::new();
+            request.stream.take(content_length).read_to_end(&mut body)?;
+            State::lock(&state.received).push(ReceivedRequest { header, body });
+
+            // Execute the next action
+            let action = State::lock(&state.script).pop_front();
+            match action {
+                Some(Action::Respond { delay, response }) => {
+                    thread::sleep(delay);
+                    tx.write_all(&response)?;
+                }
+                Some(Action::Reset) => {
+                    SockRef::from(&stream).set_linger(Some(Duration::ZERO))?;
+                    return Ok(());
+                }
+                Some(Action::Close) => return Ok(()),
+                None => {
+                    *State::lock(&state.unexpected) += 1;
+                    tx.write_all(b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n")?;
+                }
+            }
+        }
+    }
+}
+impl Debug for MockUpstream {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("MockUpstream").field("address", &self.address).field("pending", &self.pending()).finish()
+    }
+}
+impl Drop for MockUpstream {
+    fn drop(&mut self) {
+        // Stop and wake up the accept thread
+        self.state.stopped.store(true, SeqCst);
+        let _ = TcpStream::connect(self.address);
+    }
+}
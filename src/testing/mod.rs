@@ -0,0 +1,4 @@
+//! Utilities to test handlers and the crate itself
+
+pub mod arbitrary;
+pub mod mock;
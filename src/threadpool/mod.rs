@@ -1,40 +1,343 @@
 //! Implements a threadpool
 
+mod queue;
 mod worker;
 
-use crate::{error, error::Error, threadpool::worker::Worker};
-use flume::{Receiver, Sender};
-use std::sync::{
-    atomic::{AtomicUsize, Ordering::SeqCst},
-    Arc,
+use crate::{
+    error,
+    error::Error,
+    log_error,
+    threadpool::{queue::PriorityQueue, worker::Worker},
+};
+use std::{
+    any::Any,
+    cell::RefCell,
+    fmt::{self, Debug, Formatter},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering::SeqCst},
+        Arc, PoisonError, RwLock,
+    },
+    time::{Duration, Instant},
 };
 
 /// A trait for functions etc. that can be executed/called, similar to `FnOnce()`
 pub trait Executable {
     /// Executes `self`
     fn exec(self);
+
+    /// Called instead of [`Self::exec`] if the queued job is dropped to make room for a new job (see
+    /// [`Backpressure::DropOldest`]); does nothing by default
+    fn evicted(self)
+    where
+        Self: Sized,
+    {
+        // Do nothing by default
+    }
+}
+
+/// The strategy to apply if a job is dispatched into a congested threadpool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backpressure {
+    /// Rejects the new job immediately
+    #[default]
+    Reject,
+    /// Waits up to the given timeout for a free queue slot before rejecting the new job
+    Block(Duration),
+    /// Drops the oldest queued job to make room for the new job
+    ///
+    /// # Note
+    /// The dropped job is notified via [`Executable::evicted`].
+    DropOldest,
 }
 
-/// A threadpool with dynamic thread allocation and termination based on the current pressure
+/// The action to apply to a connection that has waited too long in the threadpool queue (see
+/// [`crate::Server::set_backlog_shedding`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Shedding {
+    /// Answers with a canned `503 Service Unavailable` response and closes the connection
+    #[default]
+    Unavailable,
+    /// Closes the connection without a response
+    Close,
+}
+
+/// The priority of a job
+///
+/// # Note
+/// Jobs with a higher priority are dequeued before jobs with a lower priority, so that latency-critical jobs (e.g. health
+/// checks) can jump ahead of bulk work (e.g. large file transfers). To avoid starvation, every 8th dequeue serves the lower
+/// priorities first. All priorities share the same queue capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(usize)]
+pub enum Priority {
+    /// Latency-critical jobs
+    High = 0,
+    /// Regular jobs
+    #[default]
+    Normal = 1,
+    /// Bulk jobs
+    Low = 2,
+}
+
+/// The policy to apply if a job panics
+///
+/// # Unwind safety
+/// Jobs are not required to be `UnwindSafe`; with `PanicPolicy::Catch`, the job execution is wrapped into
+/// `AssertUnwindSafe` internally. This is sound since the panicking job itself is consumed; however shared state that is
+/// captured by the job (e.g. an `Arc<RefCell<_>>` or data behind a `Mutex` that is not poisoned) may be left in an
+/// inconsistent state. If this is unacceptable, use `PanicPolicy::Propagate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Catches the panic, reports it to the panic hook and keeps the worker alive
+    #[default]
+    Catch,
+    /// Does not catch the panic, so that it unwinds and terminates the worker thread (the worker is replaced on demand)
+    Propagate,
+}
+
+thread_local! {
+    /// The inline threadpools that are currently draining their queue on this thread (identified by their state address)
+    static INLINE_DRAINING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Gets the message of a panic payload if it is a string
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message,
+        (_, Some(message)) => message,
+        _ => "<non-string panic payload>",
+    }
+}
+
+/// A snapshot of the threadpool state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ThreadpoolStats {
+    /// The amount of live workers
+    pub workers: usize,
+    /// The amount of idle workers that are waiting for a job
+    pub idle: usize,
+    /// The amount of queued jobs
+    pub queued: usize,
+    /// The maximum amount of workers and queued jobs
+    pub capacity: usize,
+    /// The total amount of executed jobs
+    pub executed: u64,
+    /// The total amount of jobs that have panicked
+    pub panicked: u64,
+}
+
+/// A hook that is invoked with the panic payload if a job panics
+type PanicHook = Arc<dyn Fn(&(dyn Any + Send)) + Send + Sync + 'static>;
+
+/// The state that is shared between the threadpool and its workers
+struct State {
+    /// The total worker count
+    workers: AtomicUsize,
+    /// The minimum amount of workers to keep alive even if idle
+    worker_min: AtomicUsize,
+    /// The amount of idle workers
+    idle: AtomicUsize,
+    /// The amount of workers that are currently executing a job
+    busy: AtomicUsize,
+    /// The peak amount of busy workers within the current load window
+    busy_peak: AtomicUsize,
+    /// The peak amount of busy workers within the previous load window
+    busy_peak_previous: AtomicUsize,
+    /// The start of the current load window in milliseconds since `epoch`
+    window_start: AtomicU64,
+    /// The reference point for `window_start`
+    epoch: Instant,
+    /// The total amount of executed jobs
+    executed: AtomicU64,
+    /// The total amount of jobs that have panicked
+    panicked: AtomicU64,
+    /// The hook to invoke if a job panics
+    panic_hook: RwLock<Option<PanicHook>>,
+    /// Whether panics are propagated instead of caught
+    propagate_panics: AtomicBool,
+    /// Whether jobs are executed in the caller's thread instead of worker threads
+    inline: bool,
+}
+impl State {
+    /// The duration of a load window
+    const WINDOW: Duration = Duration::from_secs(16);
+
+    /// Marks a worker as busy and records the peak concurrency
+    fn enter_busy(&self) {
+        let busy = self.busy.fetch_add(1, SeqCst) + 1;
+        self.busy_peak.fetch_max(busy, SeqCst);
+    }
+    /// Marks a worker as no longer busy
+    fn leave_busy(&self) {
+        self.busy.fetch_sub(1, SeqCst);
+    }
+
+    /// The amount of workers to keep alive, which is the peak concurrency within the current and the previous load window
+    /// but at least the worker minimum
+    ///
+    /// # Note
+    /// Using the peak over a sliding window instead of the current load avoids oscillation under sawtooth load.
+    fn worker_target(&self) -> usize {
+        // Rotate the load window if it has expired
+        let now = u64::try_from(self.epoch.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let window_start = self.window_start.load(SeqCst);
+        if now.saturating_sub(window_start) >= Self::WINDOW.as_millis() as u64
+            && self.window_start.compare_exchange(window_start, now, SeqCst, SeqCst).is_ok()
+        {
+            // Start the new window with the current concurrency
+            let busy_peak = self.busy_peak.swap(self.busy.load(SeqCst), SeqCst);
+            self.busy_peak_previous.store(busy_peak, SeqCst);
+        }
+
+        // Compute the target
+        let busy_peak = self.busy_peak.load(SeqCst).max(self.busy_peak_previous.load(SeqCst));
+        busy_peak.max(self.worker_min.load(SeqCst))
+    }
+
+    /// Records a panicked job and invokes the panic hook if any
+    fn panicked(&self, payload: &(dyn Any + Send)) {
+        self.panicked.fetch_add(1, SeqCst);
+        let panic_hook = self.panic_hook.read().unwrap_or_else(PoisonError::into_inner).clone();
+        match panic_hook {
+            Some(panic_hook) => panic_hook(payload),
+            None => log_error!("threadpool job panicked: {}", panic_message(payload)),
+        }
+    }
+}
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            workers: AtomicUsize::default(),
+            worker_min: AtomicUsize::default(),
+            idle: AtomicUsize::default(),
+            busy: AtomicUsize::default(),
+            busy_peak: AtomicUsize::default(),
+            busy_peak_previous: AtomicUsize::default(),
+            window_start: AtomicU64::default(),
+            epoch: Instant::now(),
+            executed: AtomicU64::default(),
+            panicked: AtomicU64::default(),
+            panic_hook: RwLock::default(),
+            propagate_panics: AtomicBool::default(),
+            inline: false,
+        }
+    }
+}
+impl Debug for State {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("State")
+            .field("workers", &self.workers)
+            .field("worker_min", &self.worker_min)
+            .field("idle", &self.idle)
+            .field("busy", &self.busy)
+            .field("executed", &self.executed)
+            .field("panicked", &self.panicked)
+            .field("inline", &self.inline)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A threadpool with dynamic thread allocation and termination based on the recent pressure
 #[derive(Debug)]
 pub struct Threadpool<T, const STACK_SIZE: usize> {
-    /// The job queue to send the data to the waiting workers
-    queue_tx: Sender<T>,
-    /// The receiving half of the `queue_tx` job-queue that can be passed as "seed" to newly created workers
-    queue_rx_seed: Receiver<T>,
-    /// The total worker count
-    workers: Arc<AtomicUsize>,
+    /// The prioritized and sharded job queues that are shared with the workers
+    queue: PriorityQueue<T>,
+    /// The shared state
+    state: Arc<State>,
 }
 impl<T, const STACK_SIZE: usize> Threadpool<T, STACK_SIZE> {
     /// Creates a new thread pool
+    ///
+    /// # Note
+    /// `worker_max` is clamped to at least `1`, so that a pool created from a zero configuration value still executes
+    /// jobs one at a time instead of rejecting every dispatch.
     pub fn new(worker_max: usize) -> Self
     where
         T: Executable + Send + 'static,
     {
-        // Create queues and counter
-        let (queue_tx, queue_rx_seed) = flume::bounded(worker_max);
-        let workers = Arc::new(AtomicUsize::default());
-        Self { queue_tx, queue_rx_seed, workers }
+        // Create queue and counter
+        let queue = PriorityQueue::new(worker_max.max(1));
+        let state = Arc::new(State::default());
+        Self { queue, state }
+    }
+    /// Creates a new inline pool that executes jobs synchronously in the caller's thread instead of worker threads (e.g.
+    /// for tests or single-threaded embedded targets)
+    ///
+    /// # Note
+    /// Jobs that are dispatched while a job is executing (e.g. rescheduled keep-alive connections) are queued and executed
+    /// after the current job returns, so `queue_max` limits the amount of such pending jobs (clamped to at least `1`).
+    pub fn new_inline(queue_max: usize) -> Self
+    where
+        T: Executable + Send + 'static,
+    {
+        let queue = PriorityQueue::new(queue_max.max(1));
+        let state = Arc::new(State { inline: true, ..State::default() });
+        Self { queue, state }
+    }
+
+    /// Gets a snapshot of the current threadpool state
+    pub fn stats(&self) -> ThreadpoolStats {
+        ThreadpoolStats {
+            workers: self.state.workers.load(SeqCst),
+            idle: self.state.idle.load(SeqCst),
+            queued: self.queue.len(),
+            capacity: self.queue.capacity(),
+            executed: self.state.executed.load(SeqCst),
+            panicked: self.state.panicked.load(SeqCst),
+        }
+    }
+
+    /// Sets a hook that is invoked with the panic payload if a job panics
+    ///
+    /// # Note
+    /// With `PanicPolicy::Catch`, panicking jobs are caught so that the worker survives; if no hook is set, the panic is
+    /// logged as error. See [`PanicPolicy`] for the unwind-safety implications.
+    pub fn set_panic_hook<F>(&self, hook: F)
+    where
+        F: Fn(&(dyn Any + Send)) + Send + Sync + 'static,
+    {
+        let mut panic_hook = self.state.panic_hook.write().unwrap_or_else(PoisonError::into_inner);
+        *panic_hook = Some(Arc::new(hook));
+    }
+
+    /// Sets the policy to apply if a job panics (defaults to `PanicPolicy::Catch`)
+    pub fn set_panic_policy(&self, policy: PanicPolicy) {
+        let propagate_panics = policy == PanicPolicy::Propagate;
+        self.state.propagate_panics.store(propagate_panics, SeqCst);
+    }
+    /// The policy to apply if a job panics
+    pub fn panic_policy(&self) -> PanicPolicy {
+        match self.state.propagate_panics.load(SeqCst) {
+            true => PanicPolicy::Propagate,
+            false => PanicPolicy::Catch,
+        }
+    }
+
+    /// Sets the minimum amount of workers to keep alive even if idle, and pre-spawns the missing workers
+    ///
+    /// # Note
+    /// Warm workers avoid the thread-spawn latency for the first burst after an idle period. The minimum is capped to
+    /// `worker_max`.
+    pub fn set_worker_min(&self, worker_min: usize) -> Result<(), Error>
+    where
+        T: Executable + Send + 'static,
+    {
+        // Inline pools have no workers
+        if self.state.inline {
+            return Ok(());
+        }
+
+        // Set the minimum
+        let worker_min = worker_min.min(self.queue.capacity());
+        self.state.worker_min.store(worker_min, SeqCst);
+
+        // Pre-spawn the missing workers
+        while self.state.workers.load(SeqCst) < worker_min {
+            self.spawn()?;
+        }
+        Ok(())
     }
 
     /// Dispatches a job into the threadpool
@@ -42,20 +345,130 @@ impl<T, const STACK_SIZE: usize> Threadpool<T, STACK_SIZE> {
     where
         T: Executable + Send + 'static,
     {
+        self.try_dispatch(job).map_err(|_| error!("Threadpool is congested"))
+    }
+    /// Dispatches a job into the threadpool or returns the job if the threadpool is congested
+    pub fn try_dispatch(&self, job: T) -> Result<(), T>
+    where
+        T: Executable + Send + 'static,
+    {
+        self.try_dispatch_with_priority(job, Priority::Normal)
+    }
+    /// Dispatches a job with the given priority into the threadpool
+    pub fn dispatch_with_priority(&self, job: T, priority: Priority) -> Result<(), Error>
+    where
+        T: Executable + Send + 'static,
+    {
+        self.try_dispatch_with_priority(job, priority).map_err(|_| error!("Threadpool is congested"))
+    }
+    /// Dispatches a job with the given priority into the threadpool or returns the job if the threadpool is congested
+    pub fn try_dispatch_with_priority(&self, job: T, priority: Priority) -> Result<(), T>
+    where
+        T: Executable + Send + 'static,
+    {
+        // Execute the job in the caller's thread if the pool is inline
+        if self.state.inline {
+            self.queue.try_send(job, priority)?;
+            self.drain_inline();
+            return Ok(());
+        }
+
         // Spawn workers as necessary
-        let worker_count = self.workers.load(SeqCst);
+        let worker_count = self.state.workers.load(SeqCst);
         if worker_count == 0 {
             // We need at least one worker, so required spawn
-            self.spawn()?;
+            if self.spawn().is_err() {
+                return Err(job);
+            }
         }
-        if worker_count <= self.queue_tx.len() {
+        if worker_count <= self.queue.len() {
             // More workers would be better, so opportunistic spawn
             let _ = self.spawn();
         }
 
         // Dispatch the job
-        self.queue_tx.try_send(job).map_err(|_| error!("Threadpool is congested"))?;
-        Ok(())
+        self.queue.try_send(job, priority)
+    }
+
+    /// Dispatches a job into the threadpool using the given backpressure strategy if the threadpool is congested, or
+    /// returns the job if it cannot be dispatched
+    ///
+    /// # Note
+    /// The job is dispatched with `Priority::Normal`.
+    pub fn dispatch_with(&self, job: T, backpressure: Backpressure) -> Result<(), T>
+    where
+        T: Executable + Send + 'static,
+    {
+        self.dispatch_with_backpressure(job, Priority::Normal, backpressure)
+    }
+    /// Dispatches a job with the given priority into the threadpool using the given backpressure strategy if the
+    /// threadpool is congested, or returns the job if it cannot be dispatched
+    ///
+    /// # Note
+    /// `Backpressure::DropOldest` only drops jobs whose priority is not higher than the priority of the new job, and
+    /// prefers the lowest priority.
+    pub fn dispatch_with_backpressure(&self, job: T, priority: Priority, backpressure: Backpressure) -> Result<(), T>
+    where
+        T: Executable + Send + 'static,
+    {
+        // Try the fast path first
+        let Err(job) = self.try_dispatch_with_priority(job, priority) else {
+            return Ok(());
+        };
+
+        // Apply the backpressure strategy
+        match backpressure {
+            Backpressure::Reject => Err(job),
+            Backpressure::Block(timeout) => self.queue.send_timeout(job, priority, timeout),
+            Backpressure::DropOldest => {
+                // Drop the oldest job if any and retry once
+                if let Some(evicted) = self.queue.try_recv_oldest(priority) {
+                    evicted.evicted();
+                }
+                self.queue.try_send(job, priority)
+            }
+        }
+    }
+
+    /// Executes the queued jobs of an inline pool in the caller's thread until the queue is empty
+    ///
+    /// # Note
+    /// Only the outermost dispatch on a thread drains the queue; nested dispatches from within a job just enqueue the job
+    /// to avoid unbounded recursion.
+    fn drain_inline(&self)
+    where
+        T: Executable,
+    {
+        /// Unmarks the pool as draining, even if a job panics with `PanicPolicy::Propagate`
+        struct Draining(usize);
+        impl Drop for Draining {
+            fn drop(&mut self) {
+                INLINE_DRAINING.with_borrow_mut(|draining| draining.retain(|key| *key != self.0));
+            }
+        }
+
+        // Mark the pool as draining unless it is already draining on this thread
+        let key = Arc::as_ptr(&self.state) as usize;
+        let nested = INLINE_DRAINING.with_borrow(|draining| draining.contains(&key));
+        if nested {
+            return;
+        }
+        INLINE_DRAINING.with_borrow_mut(|draining| draining.push(key));
+        let _draining = Draining(key);
+
+        // Execute the jobs
+        while let Some(job) = self.queue.try_recv(0) {
+            self.state.enter_busy();
+            let result = panic::catch_unwind(AssertUnwindSafe(|| job.exec()));
+            self.state.leave_busy();
+            match result {
+                Ok(_) => {
+                    self.state.executed.fetch_add(1, SeqCst);
+                }
+                Err(payload) if self.state.propagate_panics.load(SeqCst) => panic::resume_unwind(payload),
+                Err(payload) => self.state.panicked(payload.as_ref()),
+            }
+        }
     }
 
     /// Spawns a new worker
@@ -64,20 +477,18 @@ impl<T, const STACK_SIZE: usize> Threadpool<T, STACK_SIZE> {
         T: Executable + Send + 'static,
     {
         // Check if we've reached the hard limit
-        if Some(self.workers.load(SeqCst)) >= self.queue_tx.capacity() {
+        let workers = self.state.workers.load(SeqCst);
+        if workers >= self.queue.capacity() {
             return Err(error!("Worker limit exceeded"));
         }
 
-        // Spawn the worker
-        Worker::<T, STACK_SIZE>::spawn(self.queue_rx_seed.clone(), self.workers.clone())
+        // Spawn the worker and distribute the home shards evenly
+        let home = workers % self.queue.shards();
+        Worker::<T, STACK_SIZE>::spawn(self.queue.clone(), home, self.state.clone())
     }
 }
 impl<T, const STACK_SIZE: usize> Clone for Threadpool<T, STACK_SIZE> {
     fn clone(&self) -> Self {
-        Self {
-            queue_tx: self.queue_tx.clone(),
-            queue_rx_seed: self.queue_rx_seed.clone(),
-            workers: self.workers.clone(),
-        }
+        Self { queue: self.queue.clone(), state: self.state.clone() }
     }
 }
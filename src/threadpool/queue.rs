@@ -0,0 +1,239 @@
+//! A sharded job queue with work stealing
+
+use crate::threadpool::Priority;
+use flume::{Receiver, Selector, Sender};
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering::{Relaxed, SeqCst},
+        },
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A bounded job queue that is split into multiple shards to reduce the contention under high dispatch rates
+///
+/// # Sharding
+/// Jobs are dispatched round-robin across the shards; each worker has a home shard, but steals jobs from the other shards
+/// if its home shard is empty, and waits on all shards if there is no job at all. The total capacity is distributed over
+/// all shards.
+#[derive(Debug)]
+pub struct Queue<T> {
+    /// The sending halves of the shards
+    shards_tx: Arc<[Sender<T>]>,
+    /// The receiving halves of the shards
+    shards_rx: Arc<[Receiver<T>]>,
+    /// The round-robin counter to select the next shard
+    next: Arc<AtomicUsize>,
+}
+impl<T> Queue<T> {
+    /// Creates a new queue with the given total capacity and one shard per available core (but not more shards than
+    /// capacity)
+    pub fn new(capacity: usize) -> Self {
+        let shards = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        Self::with_shards(capacity, shards)
+    }
+    /// Creates a new queue with the given total capacity and shard count
+    pub fn with_shards(capacity: usize, shards: usize) -> Self {
+        // Create the shards and distribute the capacity
+        let shards = shards.min(capacity).max(1);
+        let (shards_tx, shards_rx): (Vec<_>, Vec<_>) = (0..shards)
+            .map(|index| capacity / shards + usize::from(index < capacity % shards))
+            .map(flume::bounded)
+            .unzip();
+
+        // Init self
+        let (shards_tx, shards_rx) = (Arc::from(shards_tx), Arc::from(shards_rx));
+        Self { shards_tx, shards_rx, next: Arc::default() }
+    }
+
+    /// The amount of shards
+    pub fn shards(&self) -> usize {
+        self.shards_tx.len()
+    }
+
+    /// Enqueues a job into the next shard with a free slot, or returns the job if all shards are full
+    pub fn try_send(&self, mut job: T) -> Result<(), T> {
+        let start = self.next.fetch_add(1, Relaxed);
+        for offset in 0..self.shards() {
+            // Try the next shard
+            let shard = &self.shards_tx[(start + offset) % self.shards()];
+            match shard.try_send(job) {
+                Ok(_) => return Ok(()),
+                Err(e) => job = e.into_inner(),
+            }
+        }
+        Err(job)
+    }
+    /// Dequeues the oldest job of the most-loaded shard if any
+    pub fn try_recv_oldest(&self) -> Option<T> {
+        let shard = self.shards_rx.iter().max_by_key(|shard| shard.len())?;
+        shard.try_recv().ok()
+    }
+    /// Dequeues the next job for a worker with the given home shard without waiting
+    ///
+    /// # Note
+    /// The home shard is tried first; if it is empty, the job is stolen from the other shards.
+    pub fn try_recv(&self, home: usize) -> Option<T> {
+        let shards = self.shards();
+        (0..shards).find_map(|offset| self.shards_rx[(home + offset) % shards].try_recv().ok())
+    }
+}
+impl<T> Clone for Queue<T> {
+    fn clone(&self) -> Self {
+        Self { shards_tx: self.shards_tx.clone(), shards_rx: self.shards_rx.clone(), next: self.next.clone() }
+    }
+}
+
+/// The queue slots that are shared by all priorities
+#[derive(Debug, Default)]
+struct Slots {
+    /// The amount of occupied slots
+    occupied: AtomicUsize,
+    /// The amount of dispatchers that are waiting for a free slot
+    waiting: AtomicUsize,
+    /// The lock to wait for a free slot
+    lock: Mutex<()>,
+    /// The signal that a slot has been freed
+    freed: Condvar,
+    /// The dequeue counter to avoid starvation of lower priorities
+    turn: AtomicUsize,
+}
+
+/// A set of job queues with different priorities that share a single capacity
+///
+/// # Fairness
+/// Jobs with a higher priority are dequeued first; however every `STARVATION_INTERVAL`th dequeue serves the lower
+/// priorities first, so that a steady stream of higher-priority jobs cannot starve the lower priorities.
+#[derive(Debug)]
+pub struct PriorityQueue<T> {
+    /// The queues, indexed by priority
+    levels: [Queue<T>; 3],
+    /// The shared slots
+    slots: Arc<Slots>,
+    /// The total capacity
+    capacity: usize,
+}
+impl<T> PriorityQueue<T> {
+    /// The interval of dequeues that serve the lower priorities first
+    const STARVATION_INTERVAL: usize = 8;
+
+    /// Creates a new set of queues with the given total capacity that is shared by all priorities
+    ///
+    /// # Note
+    /// Only the normal-priority queue is sharded, since the other priorities are expected to carry less traffic.
+    pub fn new(capacity: usize) -> Self {
+        let (high, low) = (Queue::with_shards(capacity, 1), Queue::with_shards(capacity, 1));
+        Self { levels: [high, Queue::new(capacity), low], slots: Arc::default(), capacity }
+    }
+
+    /// The amount of shards of the normal-priority queue
+    pub fn shards(&self) -> usize {
+        self.levels[Priority::Normal as usize].shards()
+    }
+    /// The total capacity
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// The total amount of queued jobs
+    pub fn len(&self) -> usize {
+        self.slots.occupied.load(SeqCst)
+    }
+
+    /// Enqueues a job with the given priority, or returns the job if the queue is full
+    pub fn try_send(&self, job: T, priority: Priority) -> Result<(), T> {
+        // Occupy a slot
+        let occupy = |occupied: usize| (occupied < self.capacity).then_some(occupied + 1);
+        if self.slots.occupied.fetch_update(SeqCst, SeqCst, occupy).is_err() {
+            return Err(job);
+        }
+
+        // Enqueue the job
+        // Note: Every level can hold the total capacity, so this only fails if the shards are unevenly loaded
+        self.levels[priority as usize].try_send(job).inspect_err(|_| {
+            self.slots.occupied.fetch_sub(1, SeqCst);
+        })
+    }
+    /// Enqueues a job with the given priority, waiting up to the given timeout for a free slot, or returns the job if the
+    /// timeout has expired
+    pub fn send_timeout(&self, mut job: T, priority: Priority, timeout: Duration) -> Result<(), T> {
+        // Register as waiting dispatcher
+        // Note: The waiting counter is incremented before the first attempt, so that a concurrent release either frees
+        // the slot before the attempt or signals us after we've started to wait
+        let deadline = Instant::now().checked_add(timeout);
+        let mut lock = self.slots.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        self.slots.waiting.fetch_add(1, SeqCst);
+        let result = loop {
+            // Try to enqueue the job
+            job = match self.try_send(job, priority) {
+                Ok(_) => break Ok(()),
+                Err(job) => job,
+            };
+
+            // Wait for a free slot
+            let remaining = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => timeout,
+            };
+            if remaining.is_zero() {
+                break Err(job);
+            }
+            (lock, _) = self.slots.freed.wait_timeout(lock, remaining).unwrap_or_else(PoisonError::into_inner);
+        };
+        self.slots.waiting.fetch_sub(1, SeqCst);
+        result
+    }
+
+    /// Dequeues the oldest job with the lowest priority that is not higher than the given priority if any
+    pub fn try_recv_oldest(&self, priority: Priority) -> Option<T> {
+        let levels = self.levels[priority as usize..].iter().rev();
+        let job = levels.filter_map(Queue::try_recv_oldest).next()?;
+        self.release();
+        Some(job)
+    }
+    /// Dequeues the next job with the highest priority for a worker with the given home shard without waiting
+    pub fn try_recv(&self, home: usize) -> Option<T> {
+        // Serve the lower priorities first on every `STARVATION_INTERVAL`th dequeue
+        let turn = self.slots.turn.fetch_add(1, Relaxed);
+        let job = match turn % Self::STARVATION_INTERVAL == Self::STARVATION_INTERVAL - 1 {
+            true => self.levels.iter().rev().find_map(|level| level.try_recv(home)),
+            false => self.levels.iter().find_map(|level| level.try_recv(home)),
+        }?;
+        self.release();
+        Some(job)
+    }
+    /// Dequeues the next job with the highest priority for a worker with the given home shard, waiting up to the given
+    /// timeout if there is no job
+    pub fn recv_timeout(&self, home: usize, timeout: Duration) -> Option<T> {
+        // Try all priorities in order
+        if let Some(job) = self.try_recv(home) {
+            return Some(job);
+        }
+
+        // Wait on all shards of all priorities
+        let shards = self.levels.iter().flat_map(|level| level.shards_rx.iter());
+        let selector = shards.fold(Selector::new(), |selector, shard| selector.recv(shard, Result::ok));
+        let job = selector.wait_timeout(timeout).ok().flatten()?;
+        self.release();
+        Some(job)
+    }
+
+    /// Releases a slot and signals a waiting dispatcher if any
+    fn release(&self) {
+        self.slots.occupied.fetch_sub(1, SeqCst);
+        if self.slots.waiting.load(SeqCst) > 0 {
+            // Take the lock so that the signal cannot get lost before the waiter has started to wait
+            let _lock = self.slots.lock.lock().unwrap_or_else(PoisonError::into_inner);
+            self.slots.freed.notify_all();
+        }
+    }
+}
+impl<T> Clone for PriorityQueue<T> {
+    fn clone(&self) -> Self {
+        Self { levels: self.levels.clone(), slots: self.slots.clone(), capacity: self.capacity }
+    }
+}
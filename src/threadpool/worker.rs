@@ -1,37 +1,41 @@
 //! A thread worker
 
-use crate::{error::Error, threadpool::Executable};
-use flume::Receiver;
+use crate::{
+    error::Error,
+    threadpool::{queue::PriorityQueue, Executable, State},
+};
 use std::{
-    sync::{
-        atomic::{AtomicUsize, Ordering::SeqCst},
-        Arc,
-    },
+    panic::{self, AssertUnwindSafe},
+    sync::{atomic::Ordering::SeqCst, Arc},
     thread::Builder,
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 /// A thread
 pub struct Worker<T, const STACK_SIZE: usize> {
-    /// The receiving half of the job-queue
-    queue_rx: Receiver<T>,
-    /// The total worker count
-    worker: Arc<AtomicUsize>,
+    /// The job queue
+    queue: PriorityQueue<T>,
+    /// The home shard within the job queue
+    home: usize,
+    /// The shared threadpool state
+    state: Arc<State>,
+    /// Whether the worker is still accounted for in the total worker count
+    counted: bool,
+    /// Whether the worker is accounted for in the busy count
+    busy: bool,
 }
 impl<T, const STACK_SIZE: usize> Worker<T, STACK_SIZE> {
-    /// Timeout after which workers consider themselves idle or dispatch operations timeout
+    /// Timeout after which an idle worker checks whether it is still required by the recent load (see [`Self::retire`])
     const TIMEOUT: Duration = Duration::from_secs(4);
-    /// The 1/N chance for a worker to terminate if idle
-    const TERMCHANCE: u128 = 8;
 
     /// Spawns a new worker and returns it's job queue
-    pub fn spawn(queue_rx: Receiver<T>, worker: Arc<AtomicUsize>) -> Result<(), Error>
+    pub fn spawn(queue: PriorityQueue<T>, home: usize, state: Arc<State>) -> Result<(), Error>
     where
         T: Executable + Send + 'static,
     {
         // Create the worker and increment counter
-        worker.fetch_add(1, SeqCst);
-        let this = Self { queue_rx, worker };
+        state.workers.fetch_add(1, SeqCst);
+        let this = Self { queue, home, state, counted: true, busy: false };
 
         // Spawn the thread
         let builder = Builder::new().stack_size(STACK_SIZE).name("threadpool worker thread".to_string());
@@ -40,29 +44,72 @@ impl<T, const STACK_SIZE: usize> Worker<T, STACK_SIZE> {
     }
 
     /// The worker runloop
-    fn runloop(self)
+    fn runloop(mut self)
     where
         T: Executable,
     {
         'runloop: loop {
             // Mark use as idle and wait for the next job
-            let Ok(job) = self.queue_rx.recv_timeout(Self::TIMEOUT) else {
-                // Roll whether to continue or terminate
-                match Instant::now().elapsed().as_nanos() % Self::TERMCHANCE {
-                    0 => break 'runloop,
-                    _ => continue 'runloop,
+            self.state.idle.fetch_add(1, SeqCst);
+            let job = self.queue.recv_timeout(self.home, Self::TIMEOUT);
+            self.state.idle.fetch_sub(1, SeqCst);
+            let Some(job) = job else {
+                // Terminate if there are more workers than the recent load requires
+                match self.retire() {
+                    true => break 'runloop,
+                    false => continue 'runloop,
                 }
             };
 
             // Execute job
-            // Note: While jobs should not panic, it's ok if they do: The worker thread panics and gets unwound, but that
-            // should not cause any trouble
-            job.exec();
+            // Note: While jobs should not panic, it's ok if they do: Depending on the policy, the panic is either caught
+            // and reported so that the worker continues with the next job, or it unwinds and terminates the worker
+            self.enter_busy();
+            let result = match self.state.propagate_panics.load(SeqCst) {
+                true => {
+                    job.exec();
+                    Ok(())
+                }
+                false => panic::catch_unwind(AssertUnwindSafe(|| job.exec())),
+            };
+            self.leave_busy();
+            let Err(payload) = result else {
+                self.state.executed.fetch_add(1, SeqCst);
+                continue 'runloop;
+            };
+            self.state.panicked(payload.as_ref());
         }
     }
+
+    /// Marks the worker as busy
+    fn enter_busy(&mut self) {
+        self.state.enter_busy();
+        self.busy = true;
+    }
+    /// Marks the worker as no longer busy
+    fn leave_busy(&mut self) {
+        self.state.leave_busy();
+        self.busy = false;
+    }
+
+    /// Removes the worker from the total worker count unless this would drop the count below the target
+    fn retire(&mut self) -> bool {
+        // Decrement atomically so that concurrently retiring workers cannot undercut the target
+        let worker_target = self.state.worker_target();
+        let retired =
+            self.state.workers.fetch_update(SeqCst, SeqCst, |count| (count > worker_target).then(|| count - 1));
+        self.counted = retired.is_err();
+        retired.is_ok()
+    }
 }
 impl<T, const STACK_SIZE: usize> Drop for Worker<T, STACK_SIZE> {
     fn drop(&mut self) {
-        self.worker.fetch_sub(1, SeqCst);
+        // Note: If the job has panicked with `PanicPolicy::Propagate`, the worker is dropped while it is still busy
+        if self.busy {
+            self.state.leave_busy();
+        }
+        if self.counted {
+            self.state.workers.fetch_sub(1, SeqCst);
+        }
     }
 }
@@ -0,0 +1,117 @@
+//! Per-request phase timings and a global hook to observe them (e.g. for load tests and performance regression tests)
+
+use crate::{bytes::is_token, error, error::Error};
+use std::{
+    cell::RefCell,
+    fmt::Write,
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+/// A hook that is invoked with the phase timings of every request
+type TimingHook = Arc<dyn Fn(&PhaseTimings) + Send + Sync + 'static>;
+
+/// The global timing hook
+static HOOK: RwLock<Option<TimingHook>> = RwLock::new(None);
+
+thread_local! {
+    /// The handler-supplied `Server-Timing` metrics for the request that is currently handled by this thread
+    static METRICS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The timings of the phases of a single request
+///
+/// # Note
+/// Phases that were not measured (e.g. the queue wait if the handler was invoked manually) are `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PhaseTimings {
+    /// The time the connection has waited in the threadpool queue before it was handled
+    pub queue: Option<Duration>,
+    /// The time until the first header byte has been received
+    pub first_byte: Option<Duration>,
+    /// The time to read and parse the request header after the first header byte has been received
+    pub parse: Option<Duration>,
+    /// The time spent in the request handler
+    pub handler: Option<Duration>,
+    /// The time to write the response
+    pub write: Option<Duration>,
+}
+impl PhaseTimings {
+    /// Formats the measured phases as `Server-Timing` header field value (e.g. `queue;dur=0.012, parse;dur=0.034`)
+    ///
+    /// # Note
+    /// As required by the `Server-Timing` specification, the durations are in milliseconds.
+    pub fn to_server_timing(&self) -> String {
+        let phases = [
+            ("queue", self.queue),
+            ("first-byte", self.first_byte),
+            ("parse", self.parse),
+            ("handler", self.handler),
+            ("write", self.write),
+        ];
+        let metrics: Vec<_> = (phases.into_iter())
+            .filter_map(|(name, duration)| Some((name, duration?)))
+            .map(|(name, duration)| format!("{name};dur={:.3}", duration.as_secs_f64() * 1000.0))
+            .collect();
+        metrics.join(", ")
+    }
+}
+
+/// Sets a global hook that is invoked with the phase timings after every request that is handled via
+/// [`crate::reqresp`]
+///
+/// # Note
+/// The hook is called on the worker thread after the response has been written, so it should be cheap.
+pub fn set_hook<F>(hook: F)
+where
+    F: Fn(&PhaseTimings) + Send + Sync + 'static,
+{
+    let mut current = HOOK.write().unwrap_or_else(PoisonError::into_inner);
+    *current = Some(Arc::new(hook));
+}
+/// Removes the global timing hook
+pub fn clear_hook() {
+    let mut current = HOOK.write().unwrap_or_else(PoisonError::into_inner);
+    *current = None;
+}
+
+/// Records a handler-supplied metric for the `Server-Timing` header of the current request
+///
+/// # Note
+/// The metric is only emitted if the handler is wrapped by [`crate::http::server_timing`]; otherwise, it is discarded
+/// with the next request. The name must be a valid HTTP token (e.g. `db` or `cache-lookup`), and the description must not
+/// contain control characters; otherwise, the metric is rejected.
+pub fn record_metric(name: &str, duration: Duration, description: Option<&str>) -> Result<(), Error> {
+    // Validate the metric
+    if !is_token(name.as_bytes()) {
+        return Err(error!("Metric name is not a valid HTTP token: {name:?}"));
+    }
+    if description.is_some_and(|description| description.chars().any(char::is_control)) {
+        return Err(error!("Metric description contains control characters"));
+    }
+
+    // Format the metric
+    let mut metric = format!("{name};dur={:.3}", duration.as_secs_f64() * 1000.0);
+    if let Some(description) = description {
+        let description = description.replace('\\', r"\\").replace('"', r#"\""#);
+        let _ = write!(metric, r#";desc="{description}""#);
+    }
+
+    // Store the metric
+    METRICS.with(|metrics| metrics.borrow_mut().push(metric));
+    Ok(())
+}
+/// Takes the handler-supplied metrics of the current thread
+pub(crate) fn take_metrics() -> Vec<String> {
+    METRICS.with(|metrics| metrics.take())
+}
+
+/// Reports the given timings to the global hook if any
+pub(crate) fn report(timings: &PhaseTimings) {
+    // Clone the hook so that the lock is not held while the hook is running
+    let hook = HOOK.read().unwrap_or_else(PoisonError::into_inner).clone();
+    if let Some(hook) = hook {
+        hook(timings);
+    }
+}
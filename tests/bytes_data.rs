@@ -1,4 +1,5 @@
-use ehttpd::bytes::{Data, DataSliceExt};
+use ehttpd::bytes::{Data, DataParseExt, DataSliceExt};
+use std::sync::Arc;
 
 /// Tests the data represenataion
 fn test_data(bytes: Data, as_ref: &[u8], as_debug: &str) {
@@ -48,6 +49,34 @@ fn rc_vec() {
     test_data(bytes, b"Testolope", "RcVec { backing: [84, 101, 115, 116, 111, 108, 111, 112, 101], range: 0..9 }")
 }
 
+/// Tests ArcSlice data
+#[test]
+fn arc_slice() {
+    let backing: Arc<[u8]> = Arc::from(b"Testolope".as_slice());
+    let bytes = Data::from(backing.clone());
+    test_data(bytes, b"Testolope", "ArcSlice { backing: [84, 101, 115, 116, 111, 108, 111, 112, 101], range: 0..9 }");
+
+    // Ensure the buffer is shared
+    let Data::ArcSlice { backing: shared, .. } = Data::from(backing.clone()) else {
+        panic!("invalid data variant");
+    };
+    assert!(Arc::ptr_eq(&backing, &shared));
+}
+
+/// Tests the conversions from shared buffers
+#[test]
+fn from_arc() {
+    let backing = Arc::new(b"Testolope".to_vec());
+    let Data::ArcVec { backing: shared, range } = Data::from(backing.clone()) else {
+        panic!("invalid data variant");
+    };
+    assert!(Arc::ptr_eq(&backing, &shared));
+    assert_eq!(range, 0..9);
+
+    let string: Arc<str> = Arc::from("Testolope");
+    assert_eq!(Data::from(string), b"Testolope");
+}
+
 /// Tests other data
 #[test]
 fn other() {
@@ -68,3 +97,107 @@ fn other() {
     let bytes = Data::from_other(string_data);
     test_data(bytes, b"Testolope", r#"Other { data: StringData { string: "Testolope" }, range: 0..9 }"#)
 }
+
+/// Tests the `bytes::Bytes` interoperability
+#[test]
+#[cfg(feature = "bytes")]
+fn bytes() {
+    let bytes = Data::from(bytes::Bytes::from_static(b"Testolope"));
+    test_data(bytes, b"Testolope", "Bytes(b\"Testolope\")");
+
+    // Ensure shared backings are converted without copying
+    let backing = Arc::new(b"Testolope".to_vec());
+    let data = Data::ArcVec { backing: backing.clone(), range: 1..4 };
+    let bytes = bytes::Bytes::from(data);
+    assert_eq!(bytes.as_ref(), b"est");
+    assert_eq!(bytes.as_ptr(), backing[1..].as_ptr());
+}
+
+/// Tests the owned conversions
+#[test]
+fn into_vec() {
+    // Uniquely referenced backings are reused
+    let data = Data::new_arcvec(*b"Testolope").subcopy(2..6).expect("failed to create subcopy");
+    let Data::ArcVec { backing, .. } = &data else {
+        panic!("invalid data variant");
+    };
+    let pointer = backing.as_ptr();
+    let vec = data.into_vec();
+    assert_eq!(vec, b"stol");
+    assert_eq!(vec.as_ptr(), pointer);
+
+    // Shared backings and other variants are copied
+    let data = Data::new_arcvec(*b"Testolope");
+    let _shared = data.clone();
+    assert_eq!(data.into_vec(), b"Testolope");
+    assert_eq!(Vec::from(Data::from("Testolope")), b"Testolope");
+
+    // Test the string conversion
+    assert_eq!(String::try_from(Data::from("Testolope")).expect("failed to convert data"), "Testolope");
+    assert!(String::try_from(Data::from(b"\xff")).is_err());
+}
+
+/// Tests the serde representations
+#[test]
+#[cfg(feature = "serde")]
+fn serde() {
+    // Test the string representation
+    let json = serde_json::to_string(&Data::from("Testolope")).expect("failed to serialize data");
+    assert_eq!(json, r#""Testolope""#);
+    let data: Data = serde_json::from_str(&json).expect("failed to deserialize data");
+    assert_eq!(data, b"Testolope");
+
+    // Test the byte representation
+    let json = serde_json::to_string(&Data::from(b"\xff\x00")).expect("failed to serialize data");
+    assert_eq!(json, "[255,0]");
+    let data: Data = serde_json::from_str(&json).expect("failed to deserialize data");
+    assert_eq!(data, b"\xff\x00");
+}
+
+/// Tests the parsing extensions
+#[test]
+fn parse() {
+    // Test the searches
+    let data = Data::from("text/html, text/plain;q=0.5, */*;q=0.1");
+    assert_eq!(data.find(b", "), Some(9));
+    assert_eq!(data.rfind(b", "), Some(27));
+    assert_eq!(data.find_ignore_ascii_case(b"TEXT/PLAIN"), Some(11));
+    assert_eq!(data.find(b"TEXT/PLAIN"), None);
+
+    // Test the edge cases
+    let short = Data::from("abc");
+    assert_eq!((short.find(b""), short.rfind(b"")), (Some(0), Some(3)));
+    assert_eq!((short.find(b"c"), short.rfind(b"a")), (Some(2), Some(0)));
+    assert_eq!((short.find(b"abcd"), short.rfind(b"abcd")), (None, None));
+
+    // Test the splits
+    let mut remaining = data.clone();
+    let split = remaining.splitn_off(5, b", ");
+    let split: Vec<_> = split.iter().map(|data| data.as_ref()).collect();
+    assert_eq!(split, [b"text/html".as_slice(), b"text/plain;q=0.5"]);
+    assert_eq!(remaining, b"*/*;q=0.1");
+
+    let mut remaining = data.clone();
+    assert_eq!(remaining.rsplit_off(b";q=").expect("failed to split data"), b"0.1");
+    assert_eq!(remaining, b"text/html, text/plain;q=0.5, */*");
+
+    let mut content_type = Data::from("multipart/form-data; BOUNDARY=testolope");
+    content_type.split_off_ignore_ascii_case(b"boundary=").expect("failed to split data");
+    assert_eq!(content_type, b"testolope");
+}
+
+/// Tests the ASCII lowercase mapping and the token validation
+#[test]
+fn case_and_token() {
+    let data = Data::from(b"Content-Type");
+    assert_eq!(&*data.to_lowercase(), b"content-type");
+    assert_eq!(&*Data::from("STRA\u{df}E").to_lowercase(), "stra\u{df}e".as_bytes());
+
+    // Validate tokens
+    assert!(data.is_token());
+    assert!(Data::from(b"X-Custom_Field.v1!~").is_token());
+    assert!(!Data::from(b"").is_token());
+    assert!(!Data::from(b"Content Type").is_token());
+    assert!(!Data::from(b"Field:").is_token());
+    assert!(!Data::from("F\u{e4}ld").is_token());
+}
@@ -0,0 +1,55 @@
+use ehttpd::{
+    bytes::{Data, DataChain, Source},
+    http::{Response, ResponseExt},
+};
+use std::io::Read;
+
+/// Tests reading a chain of segments
+#[test]
+fn read() {
+    let mut chain = DataChain::new().with(b"Test").with(Data::Empty).with(String::from("olo")).with(b"pe");
+    assert_eq!(chain.len(), 9);
+
+    // Read the chain in small steps
+    let mut buf = [0; 3];
+    let mut read = Vec::new();
+    loop {
+        let len = chain.read(&mut buf).expect("failed to read chain");
+        if len == 0 {
+            break;
+        }
+        read.extend_from_slice(&buf[..len]);
+    }
+    assert_eq!(read, b"Testolope");
+    assert!(chain.is_empty());
+}
+
+/// Tests flattening a partially read chain
+#[test]
+fn into_data() {
+    let mut chain: DataChain = [b"Test".as_slice(), b"olope".as_slice()].into_iter().collect();
+    chain.read_exact(&mut [0; 2]).expect("failed to read chain");
+    assert_eq!(chain.len(), 7);
+    assert_eq!(chain.into_data(), b"stolope");
+
+    // A single segment is not copied
+    let chain = DataChain::new().with(b"Testolope");
+    assert!(matches!(chain.into_data(), Data::Static(b"Testolope")));
+}
+
+/// Tests a chain as response body
+#[test]
+fn body() {
+    let mut response: Response = Response::new_200_ok();
+    response.set_body(DataChain::new().with(b"Test").with(b"olope"));
+
+    let mut serialized = Vec::new();
+    response.to_stream(&mut serialized).expect("failed to serialize response");
+    assert!(serialized.ends_with(b"Content-Length: 9\r\n\r\nTestolope"));
+
+    // Test the conversion into a source
+    let mut source = Source::from(DataChain::new().with(b"Testolope"));
+    let mut read = String::new();
+    source.read_to_string(&mut read).expect("failed to read source");
+    assert_eq!(read, "Testolope");
+}
@@ -0,0 +1,68 @@
+#![cfg(any(feature = "flate2", feature = "zstd"))]
+
+use ehttpd::bytes::Source;
+use std::io::Read;
+
+/// The uncompressed test payload
+const PAYLOAD: &str = "Testolope Testolope Testolope Testolope";
+
+/// Tests the gzip decoder
+#[test]
+#[cfg(feature = "flate2")]
+fn gzip() {
+    use ehttpd::bytes::GzipDecoder;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    // Compress the payload
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(PAYLOAD.as_bytes()).expect("failed to compress payload");
+    let compressed = encoder.finish().expect("failed to compress payload");
+
+    // Decompress the payload
+    let mut source = Source::from(GzipDecoder::new(compressed));
+    let mut buf = String::new();
+    source.read_to_string(&mut buf).expect("failed to decompress payload");
+    assert_eq!(buf, PAYLOAD);
+}
+
+/// Tests the zstd decoder
+#[test]
+#[cfg(feature = "zstd")]
+fn zstd() {
+    use ehttpd::bytes::ZstdDecoder;
+
+    // Compress the payload
+    let compressed = zstd::encode_all(PAYLOAD.as_bytes(), 0).expect("failed to compress payload");
+
+    // Decompress the payload and test an invalid payload
+    let mut source = Source::from(ZstdDecoder::new(compressed).expect("failed to create decoder"));
+    let mut buf = String::new();
+    source.read_to_string(&mut buf).expect("failed to decompress payload");
+    assert_eq!(buf, PAYLOAD);
+
+    let mut invalid = ZstdDecoder::new("Testolope").expect("failed to create decoder");
+    assert!(invalid.read_to_end(&mut Vec::new()).is_err());
+}
+
+/// Tests that the zstd decoder rejects frames that require a large window
+#[test]
+#[cfg(feature = "zstd")]
+fn zstd_window() {
+    use ehttpd::bytes::ZstdDecoder;
+
+    /// Creates a frame with the given window descriptor and a single raw block with one byte
+    fn frame(window_descriptor: u8) -> Vec<u8> {
+        vec![0x28, 0xB5, 0x2F, 0xFD, 0x00, window_descriptor, 0x09, 0x00, 0x00, b'x']
+    }
+
+    // An 8 MiB window is accepted
+    let mut buf = Vec::new();
+    let mut decoder = ZstdDecoder::new(frame(13 << 3)).expect("failed to create decoder");
+    decoder.read_to_end(&mut buf).expect("failed to decompress payload");
+    assert_eq!(buf, b"x");
+
+    // A 128 MiB window is rejected
+    let mut decoder = ZstdDecoder::new(frame(17 << 3)).expect("failed to create decoder");
+    assert!(decoder.read_to_end(&mut Vec::new()).is_err());
+}
@@ -0,0 +1,83 @@
+use ehttpd::{bytes::Sink, error::Error};
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+/// Tests the connection liveness probe
+#[test]
+fn connection_alive() {
+    // Create a connected socket pair
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let client = TcpStream::connect(listener.local_addr().expect("failed to get address")).expect("failed to connect");
+    let (server, _) = listener.accept().expect("failed to accept connection");
+    let sink = Sink::from(server);
+    assert!(sink.connection_alive());
+
+    // Close the client and wait for the server to notice
+    drop(client);
+    for _ in 0..100 {
+        if !sink.connection_alive() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("closed connection is still considered alive");
+}
+
+/// Tests that the liveness probe keeps a non-blocking stream non-blocking
+#[test]
+#[cfg(target_family = "unix")]
+fn connection_alive_nonblocking() {
+    // Create a connected non-blocking socket pair
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let _client = TcpStream::connect(listener.local_addr().expect("failed to get address")).expect("failed to connect");
+    let (mut server, _) = listener.accept().expect("failed to accept connection");
+    server.set_nonblocking(true).expect("failed to set non-blocking mode");
+    let sink = Sink::from(server.try_clone().expect("failed to clone stream"));
+
+    // Probe the peer and ensure that the stream is still non-blocking
+    assert!(sink.connection_alive());
+    let error = server.read(&mut [0; 16]).expect_err("stream has become blocking or readable");
+    assert_eq!(error.kind(), ErrorKind::WouldBlock);
+}
+
+/// Tests that non-socket sinks are always alive
+#[test]
+fn connection_alive_other() {
+    assert!(Sink::Null.connection_alive());
+    assert!(Sink::Vector(Vec::new()).connection_alive());
+}
+
+/// Tests that interrupted writes are retried and aborts are classified
+#[test]
+fn interrupted() {
+    /// A writer that is interrupted before every write
+    #[derive(Debug, Default)]
+    struct Interrupting {
+        interrupted: bool,
+        written: Vec<u8>,
+    }
+    impl Write for Interrupting {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            match self.interrupted {
+                true => Err(io::Error::from(ErrorKind::Interrupted)),
+                false => self.written.write(buf),
+            }
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut sink = Sink::from_other(Interrupting::default());
+    assert_eq!(sink.write(b"Testolope").expect("interrupted write was not retried"), 9);
+
+    let error = Error::from(io::Error::from(ErrorKind::BrokenPipe));
+    assert!(error.is_client_abort());
+    let error = Error::from(io::Error::from(ErrorKind::PermissionDenied));
+    assert!(!error.is_client_abort());
+}
@@ -0,0 +1,160 @@
+use ehttpd::bytes::{Deadline, Source};
+use std::{
+    io::{BufRead, ErrorKind, Read},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Tests reading from a source before the deadline
+#[test]
+fn deadline_ok() {
+    let mut source = Source::from("Testolope");
+    let mut deadline = Deadline::with_timeout(&mut source, Duration::from_secs(60));
+
+    let mut buf = String::new();
+    deadline.read_to_string(&mut buf).expect("failed to read source");
+    assert_eq!(buf, "Testolope");
+}
+
+/// Tests reading from a source after the deadline
+#[test]
+fn deadline_exceeded() {
+    let source = Source::from("Testolope");
+    let mut deadline = Deadline::new(source, Instant::now());
+
+    let error = deadline.read(&mut [0; 16]).expect_err("read after deadline succeeded");
+    assert_eq!(error.kind(), ErrorKind::TimedOut);
+}
+
+/// Tests that the deadline interrupts blocking reads from a buffered TCP stream
+#[test]
+fn deadline_buffered_tcp() {
+    // Connect to an idle peer
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let stream = TcpStream::connect(listener.local_addr().expect("failed to get address")).expect("failed to connect");
+    let (_peer, _) = listener.accept().expect("failed to accept connection");
+    let mut source = Source::buffered(Source::from(stream.try_clone().expect("failed to clone stream")));
+
+    // Read until the deadline has passed
+    let start = Instant::now();
+    let mut deadline = Deadline::with_timeout(&mut source, Duration::from_millis(100));
+    let error = deadline.read(&mut [0; 16]).expect_err("read from idle peer succeeded");
+    assert_eq!(error.kind(), ErrorKind::TimedOut);
+    assert!(start.elapsed() < Duration::from_secs(4));
+
+    // The original timeout is restored
+    drop(deadline);
+    assert_eq!(stream.read_timeout().expect("failed to get timeout"), None);
+}
+
+/// Tests a limited source
+#[test]
+fn limited() {
+    let mut source = Source::limited("Testolope", 4);
+    let mut buf = String::new();
+    source.read_to_string(&mut buf).expect("failed to read source");
+    assert_eq!(buf, "Test");
+
+    // The underlying source may end before the limit
+    let mut source = Source::limited(Source::limited("Testolope", 7), 64);
+    let mut buf = String::new();
+    source.read_to_string(&mut buf).expect("failed to read source");
+    assert_eq!(buf, "Testolo");
+    assert!(matches!(source, Source::Limited { remaining: 57, .. }));
+}
+
+/// Tests a chain of sources
+#[test]
+fn chain() {
+    let mut source = Source::chain([Source::from("Test"), Source::Empty, Source::limited("olopeXXX", 5)]);
+    let mut buf = String::new();
+    source.read_to_string(&mut buf).expect("failed to read source");
+    assert_eq!(buf, "Testolope");
+    assert!(matches!(&source, Source::Chain(sources) if sources.is_empty()));
+}
+
+/// Tests lazily generated sources
+#[test]
+fn lazy() {
+    // Generate some CSV rows via a closure
+    let mut row = 0;
+    let mut source = Source::from_fn(move |buf| {
+        row += 1;
+        let line = format!("{row},row-{row}\n");
+        match row {
+            1..=3 => {
+                buf[..line.len()].copy_from_slice(line.as_bytes());
+                Ok(line.len())
+            }
+            _ => Ok(0),
+        }
+    });
+    let mut buf = String::new();
+    source.read_to_string(&mut buf).expect("failed to read source");
+    assert_eq!(buf, "1,row-1\n2,row-2\n3,row-3\n");
+
+    // Read the chunks of an iterator
+    let mut source = Source::from_chunks(["Test", "", "olope"]);
+    let mut buf = String::new();
+    source.read_to_string(&mut buf).expect("failed to read source");
+    assert_eq!(buf, "Testolope");
+}
+
+/// Tests a channel-backed source
+#[test]
+fn channel() {
+    // Produce some chunks on another thread
+    let (sender, mut source) = Source::from_channel(1);
+    let producer = thread::spawn(move || {
+        for chunk in ["Test", "", "olope"] {
+            sender.send(chunk).expect("failed to send chunk");
+        }
+    });
+    let mut buf = String::new();
+    source.read_to_string(&mut buf).expect("failed to read source");
+    assert_eq!(buf, "Testolope");
+    producer.join().expect("producer panicked");
+
+    // Abort the source
+    let (sender, mut source) = Source::from_channel(4);
+    sender.send("Test").expect("failed to send chunk");
+    sender.abort(ErrorKind::InvalidData.into());
+    let mut buf = String::new();
+    assert_eq!(source.read_to_string(&mut buf).map_err(|e| e.kind()), Err(ErrorKind::InvalidData));
+
+    // Send to a dropped source
+    let (sender, source) = Source::from_channel(4);
+    drop(source);
+    assert_eq!(sender.send("Test").map_err(|e| e.kind()), Err(ErrorKind::BrokenPipe));
+
+    // Time out on a stalled producer
+    let (sender, mut source) = Source::from_channel_timeout(4, Duration::from_millis(10));
+    sender.send("Test").expect("failed to send chunk");
+    let mut buf = String::new();
+    assert_eq!(source.read_to_string(&mut buf).map_err(|e| e.kind()), Err(ErrorKind::TimedOut));
+}
+
+/// Tests peeking into a source without consuming it
+#[test]
+fn buffered() {
+    // Unbuffered sources must be buffered explicitly
+    let mut source = Source::chain([Source::from("PROXY "), Source::from("TCP4\r\nGET")]);
+    assert_eq!(source.fill_buf().map_err(|e| e.kind()).err(), Some(ErrorKind::Unsupported));
+    assert!(matches!(&source, Source::Chain(_)));
+
+    // Sniff the protocol of a buffered source
+    let mut source = Source::buffered(source);
+    assert!(source.fill_buf().expect("failed to fill buffer").starts_with(b"PROXY"));
+
+    // Consume the sniffed bytes and read the remainder
+    source.consume(6);
+    let mut buf = String::new();
+    source.read_to_string(&mut buf).expect("failed to read source");
+    assert_eq!(buf, "TCP4\r\nGET");
+
+    // Buffered sources are not buffered twice
+    assert!(matches!(Source::buffered("Testolope"), Source::Data(_)));
+    let buffered = Source::buffered(Source::buffered(Source::chain([Source::Empty])));
+    assert!(matches!(buffered, Source::Buffered(source) if matches!(source.get_ref(), Source::Chain(_))));
+}
@@ -0,0 +1,18 @@
+use ehttpd::cancel::CancellationToken;
+
+/// Tests that cancellation is inherited by child tokens but not by parents
+#[test]
+fn child() {
+    let (parent, child) = (CancellationToken::new(), CancellationToken::new());
+    let (parent_child, child_child) = (parent.child(), child.child());
+    assert!(!parent_child.is_cancelled());
+
+    // Cancel the parent and the child
+    parent.cancel();
+    child_child.cancel();
+    assert!(parent_child.is_cancelled());
+    assert!(parent_child.check().is_err());
+    assert!(child_child.is_cancelled());
+    assert!(!child.is_cancelled());
+    assert!(child.check().is_ok());
+}
@@ -0,0 +1,64 @@
+use ehttpd::{
+    bytes::{Sink, Source},
+    config, Server,
+};
+use std::{env, time::Duration};
+
+/// Tests the byte size parser
+#[test]
+fn size() {
+    assert_eq!(config::parse_size("512").expect("failed to parse size"), 512);
+    assert_eq!(config::parse_size("64KiB").expect("failed to parse size"), 64 * 1024);
+    assert_eq!(config::parse_size("1.5 MB").expect("failed to parse size"), 1_500_000);
+    assert_eq!(config::parse_size(" 2gib ").expect("failed to parse size"), 2 << 30);
+
+    // Test invalid sizes
+    for invalid in ["", "KiB", "64 XB", "-1", "1.2.3MB", "20000000000TiB"] {
+        assert!(config::parse_size(invalid).is_err(), "{invalid} is valid");
+    }
+}
+
+/// Tests the duration parser
+#[test]
+fn duration() {
+    assert_eq!(config::parse_duration("30").expect("failed to parse duration"), Duration::from_secs(30));
+    assert_eq!(config::parse_duration("250ms").expect("failed to parse duration"), Duration::from_millis(250));
+    assert_eq!(config::parse_duration("2m30s").expect("failed to parse duration"), Duration::from_secs(150));
+    assert_eq!(config::parse_duration("1d1h").expect("failed to parse duration"), Duration::from_secs(25 * 60 * 60));
+
+    // Test invalid durations
+    for invalid in ["", "s", "2x", "1.5s", "-1s", "2m 30s"] {
+        assert!(config::parse_duration(invalid).is_err(), "{invalid} is valid");
+    }
+}
+
+/// Tests the environment variable overlay
+#[test]
+fn env_overlay() {
+    /// The connection handler
+    fn handler(_: &mut Source, _: &mut Sink) -> bool {
+        false
+    }
+
+    // Apply valid variables
+    env::set_var("EHTTPD_WORKER_MIN", "2");
+    env::set_var("EHTTPD_BACKPRESSURE", "block:250ms");
+    env::set_var("EHTTPD_SEND_BUFFER_SIZE", "64KiB");
+    let mut server: Server<fn(&mut Source, &mut Sink) -> bool> = Server::new(4, handler);
+    server.apply_env().expect("failed to apply environment variables");
+    assert_eq!(server.stats().workers, 2);
+
+    // Apply an invalid variable
+    env::set_var("EHTTPD_PEER_LIMIT", "nope");
+    let error = server.apply_env().expect_err("invalid variable was accepted");
+    assert!(error.error.starts_with("Invalid environment variable EHTTPD_PEER_LIMIT: "));
+
+    // An invalid variable leaves the server unchanged
+    let mut server: Server<fn(&mut Source, &mut Sink) -> bool> = Server::new(4, handler);
+    assert!(server.apply_env().is_err());
+    assert_eq!(server.stats().workers, 0);
+    assert_eq!(
+        config::env("EHTTPD_PEER_LIMIT", |value| Ok(value.to_string())).expect("failed to read variable"),
+        Some("nope".to_string())
+    );
+}
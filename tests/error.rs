@@ -0,0 +1,26 @@
+use ehttpd::error;
+use std::error::Error;
+use std::io::{self, ErrorKind};
+
+/// Tests the aggregation of multiple underlying errors
+#[test]
+fn aggregate() {
+    // Aggregate the errors of all attempts
+    let attempts = [io::Error::from(ErrorKind::ConnectionRefused), io::Error::from(ErrorKind::TimedOut)];
+    let error = error!(all: attempts, "All {} upstreams failed", 2);
+    assert_eq!(error.error, "All 2 upstreams failed");
+    assert!(error.source.is_none());
+
+    // Iterate the sources
+    let sources: Vec<_> = error.sources().map(ToString::to_string).collect();
+    assert_eq!(sources, ["connection refused", "timed out"]);
+    assert_eq!(error.source().map(ToString::to_string).as_deref(), Some("connection refused"));
+    assert_eq!(
+        error.to_string(),
+        "All 2 upstreams failed\n caused by [0]: connection refused\n caused by [1]: timed out\n"
+    );
+
+    // A single source is iterated too
+    let error = error!(with: io::Error::from(ErrorKind::TimedOut), "Upstream failed");
+    assert_eq!(error.sources().count(), 1);
+}
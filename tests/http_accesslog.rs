@@ -0,0 +1,53 @@
+use ehttpd::{
+    bytes::Data,
+    http::{AccessLogFormat, AccessLogRecord},
+    timing::PhaseTimings,
+};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Creates a test record
+fn record() -> AccessLogRecord {
+    let mut timings = PhaseTimings::default();
+    timings.parse = Some(Duration::from_micros(42));
+
+    AccessLogRecord {
+        peer: Some("[::1]:4711".parse().expect("invalid socket address")),
+        method: Data::from("GET"),
+        target: Data::from("/\"testolope\""),
+        version: Data::from("HTTP/1.1"),
+        status: Data::from("200"),
+        size: Some(9),
+        time: UNIX_EPOCH + Duration::from_secs(971_186_136),
+        duration: Duration::from_micros(1337),
+        timings,
+    }
+}
+
+/// Tests the common log format
+#[test]
+fn common() {
+    let formatted = record().format(AccessLogFormat::Common);
+    assert_eq!(formatted, r#"::1 - - [10/Oct/2000:13:55:36 +0000] "GET /\"testolope\" HTTP/1.1" 200 9 1337"#);
+}
+
+/// Tests that the common log format escapes control characters and backslashes in the request line
+#[test]
+fn common_escaped() {
+    let mut record = record();
+    record.target = Data::from("/a\\b\r\n::1 - - forged");
+    let formatted = record.format(AccessLogFormat::Common);
+    assert_eq!(
+        formatted,
+        r#"::1 - - [10/Oct/2000:13:55:36 +0000] "GET /a\\b\x0d\x0a::1 - - forged HTTP/1.1" 200 9 1337"#
+    );
+}
+
+/// Tests the JSON format
+#[test]
+fn json() {
+    let formatted = record().format(AccessLogFormat::Json);
+    assert_eq!(
+        formatted,
+        r#"{"time":971186136,"peer":"[::1]:4711","method":"GET","target":"/\"testolope\"","version":"HTTP/1.1","status":"200","size":9,"duration_us":1337,"queue_us":null,"first_byte_us":null,"parse_us":42}"#
+    );
+}
@@ -0,0 +1,220 @@
+use ehttpd::{
+    bytes::Data,
+    error,
+    http::{Client, OutboundRequest, Response, ResponseExt},
+};
+use socket2::{Domain, Socket, Type};
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    net::{Ipv6Addr, SocketAddr, TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Reads the body of the response
+fn body(response: &mut Response) -> String {
+    let mut body = String::new();
+    response.body.source.read_to_string(&mut body).expect("failed to read body");
+    body
+}
+
+/// Tests requests with keep-alive and the retry on a closed connection
+#[test]
+fn keep_alive() {
+    // Start a server that answers two requests on the first connection and one request on the second connection
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let address = listener.local_addr().expect("failed to get listening address");
+    let server = thread::spawn(move || {
+        let responses: [&[&[u8]]; 2] = [
+            &[
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nTest\r\n5\r\nolope\r\n0\r\n\r\n",
+                b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok",
+            ],
+            &[b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"],
+        ];
+        let mut requests = Vec::new();
+        for responses in responses {
+            let (stream, _) = listener.accept().expect("failed to accept connection");
+            let mut reader = BufReader::new(stream.try_clone().expect("failed to clone stream"));
+            for response in responses {
+                // Read the request header and body
+                let mut request = String::new();
+                while !request.ends_with("\r\n\r\n") {
+                    reader.read_line(&mut request).expect("failed to read request");
+                }
+                let len =
+                    request.split("Content-Length: ").nth(1).map(|len| len.split("\r\n").next().unwrap_or_default());
+                let len: u64 = len.unwrap_or("0").parse().expect("invalid content length");
+                (&mut reader).take(len).read_to_string(&mut request).expect("failed to read body");
+                requests.push(request);
+                (&stream).write_all(response).expect("failed to write response");
+            }
+        }
+        requests
+    });
+
+    // Perform the requests
+    let mut client = Client::new(address);
+    client.set_host("example.org");
+    let mut response = client.send("GET", "/a", [("Accept", "text/plain")], "").expect("failed to send request");
+    assert_eq!(response.status.as_ref(), b"200");
+    assert_eq!(response.content_length().expect("invalid content length"), Some(9));
+    assert!(!response.fields.iter().any(|(key, _)| key.eq_ignore_ascii_case(b"Transfer-Encoding")));
+    assert_eq!(body(&mut response), "Testolope");
+    let mut response = client.send("POST", "/b", [("X-Hook", "1")], "data").expect("failed to send request");
+    assert_eq!(response.status.as_ref(), b"201");
+    assert_eq!(body(&mut response), "ok");
+    let response = client.send("GET", "/c", [("Accept", "*/*")], "").expect("failed to send request");
+    assert_eq!(response.status.as_ref(), b"404");
+
+    // Validate the requests
+    let requests = server.join().expect("server panicked");
+    assert_eq!(
+        requests,
+        [
+            "GET /a HTTP/1.1\r\nHost: example.org\r\nAccept: text/plain\r\n\r\n",
+            "POST /b HTTP/1.1\r\nHost: example.org\r\nX-Hook: 1\r\nContent-Length: 4\r\n\r\ndata",
+            "GET /c HTTP/1.1\r\nHost: example.org\r\nAccept: */*\r\n\r\n",
+        ]
+    );
+}
+
+/// Tests that requests are not retried if the server has already started to respond
+#[test]
+fn no_retry_after_response() {
+    // Start a server that answers the first request and truncates the second response
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let address = listener.local_addr().expect("failed to get listening address");
+    let server_listener = listener.try_clone().expect("failed to clone listener");
+    let server = thread::spawn(move || {
+        let (stream, _) = server_listener.accept().expect("failed to accept connection");
+        let mut reader = BufReader::new(stream.try_clone().expect("failed to clone stream"));
+        for response in [b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".as_slice(), b"HTTP/1.1 200 OK\r\nContent-Le"]
+        {
+            let mut request = String::new();
+            while !request.ends_with("\r\n\r\n") {
+                reader.read_line(&mut request).expect("failed to read request");
+            }
+            (&stream).write_all(response).expect("failed to write response");
+        }
+    });
+
+    // Perform the requests
+    let mut client = Client::new(address);
+    client.send("GET", "/a", [("Accept", "*/*")], "").expect("failed to send request");
+    assert!(client.send("GET", "/b", [("Accept", "*/*")], "").is_err());
+    server.join().expect("server panicked");
+
+    // Ensure that the request has not been retried on a new connection
+    listener.set_nonblocking(true).expect("failed to set listener to non-blocking");
+    let error = listener.accept().expect_err("request has been retried");
+    assert_eq!(error.kind(), ErrorKind::WouldBlock);
+}
+
+/// Tests that a client with multiple addresses falls back to the next address if an address is unreachable
+#[test]
+fn with_addresses() {
+    // Get a free port without a listener
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let closed = listener.local_addr().expect("failed to get listening address");
+    drop(listener);
+
+    // Start a server that answers a single request
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let address = listener.local_addr().expect("failed to get listening address");
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("failed to accept connection");
+        let mut reader = BufReader::new(stream.try_clone().expect("failed to clone stream"));
+        let mut request = String::new();
+        while !request.ends_with("\r\n\r\n") {
+            reader.read_line(&mut request).expect("failed to read request");
+        }
+        (&stream).write_all(b"HTTP/1.1 204 No Content\r\n\r\n").expect("failed to write response");
+        request
+    });
+
+    // Perform the request
+    let ipv6_closed = SocketAddr::new(Ipv6Addr::LOCALHOST.into(), closed.port());
+    let mut client = Client::with_addresses([closed, ipv6_closed, address]);
+    let response = client.send("GET", "/", [("Accept", "*/*")], "").expect("failed to send request");
+    assert_eq!(response.status.as_ref(), b"204");
+
+    // Validate that the host defaults to the first address
+    let request = server.join().expect("server panicked");
+    assert_eq!(request, format!("GET / HTTP/1.1\r\nHost: {closed}\r\nAccept: */*\r\n\r\n"));
+}
+
+/// Tests that an unresponsive address does not stall the connection until the timeout
+#[test]
+fn with_addresses_staggered() {
+    // Create a listener with a full backlog, so that further connection attempts are not answered
+    let socket = Socket::new(Domain::IPV4, Type::STREAM, None).expect("failed to create socket");
+    socket.bind(&SocketAddr::from(([127, 0, 0, 1], 0)).into()).expect("failed to bind socket");
+    socket.listen(0).expect("failed to listen");
+    let unresponsive = socket.local_addr().ok().and_then(|address| address.as_socket());
+    let unresponsive = unresponsive.expect("failed to get listening address");
+    let _queued = TcpStream::connect(unresponsive).expect("failed to fill backlog");
+
+    // Start a server that answers a single request
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let address = listener.local_addr().expect("failed to get listening address");
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("failed to accept connection");
+        let mut reader = BufReader::new(stream.try_clone().expect("failed to clone stream"));
+        let mut request = String::new();
+        while !request.ends_with("\r\n\r\n") {
+            reader.read_line(&mut request).expect("failed to read request");
+        }
+        (&stream).write_all(b"HTTP/1.1 204 No Content\r\n\r\n").expect("failed to write response");
+    });
+
+    // Perform the request
+    let (mut client, start) = (Client::with_addresses([unresponsive, address]), Instant::now());
+    client.set_timeout(Duration::from_secs(10));
+    let response = client.send("GET", "/", [("Accept", "*/*")], "").expect("failed to send request");
+    assert_eq!(response.status.as_ref(), b"204");
+    assert!(start.elapsed() < Duration::from_secs(5), "connection has been stalled by the unresponsive address");
+    server.join().expect("server panicked");
+}
+
+/// Tests that the signer can decorate requests and that a failing signer prevents the request
+#[test]
+fn signer() {
+    // Start a server that answers a single request
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let address = listener.local_addr().expect("failed to get listening address");
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("failed to accept connection");
+        let mut reader = BufReader::new(stream.try_clone().expect("failed to clone stream"));
+        let mut request = String::new();
+        while !request.ends_with("\r\n\r\n") {
+            reader.read_line(&mut request).expect("failed to read request");
+        }
+        reader.take(4).read_to_string(&mut request).expect("failed to read body");
+        (&stream).write_all(b"HTTP/1.1 204 No Content\r\n\r\n").expect("failed to write response");
+        request
+    });
+
+    // Sign the request with the amount of fields and the body
+    let mut client = Client::new(address);
+    client.set_host("example.org");
+    client.set_signer(|request: &mut OutboundRequest| {
+        if request.target.eq(b"/forbidden") {
+            return Err(error!("Refusing to sign request"));
+        }
+        let signature =
+            format!("{}:{}", request.fields.len(), String::from_utf8_lossy(request.body.unwrap_or_default()));
+        request.fields.push((Data::from(b"X-Signature"), Data::from(signature)));
+        Ok(())
+    });
+    let response = client.send("POST", "/", [("Accept", "*/*")], "data").expect("failed to send request");
+    assert_eq!(response.status.as_ref(), b"204");
+    assert!(client.send("GET", "/forbidden", [("Accept", "*/*")], "").is_err());
+
+    // Validate the request
+    let request = server.join().expect("server panicked");
+    assert_eq!(
+        request,
+        "POST / HTTP/1.1\r\nHost: example.org\r\nAccept: */*\r\nContent-Length: 4\r\nX-Signature: 3:data\r\n\r\ndata"
+    );
+}
@@ -0,0 +1,116 @@
+use ehttpd::{
+    bytes::Source,
+    http::{Cors, Request, Response, ResponseExt},
+};
+use std::time::Duration;
+
+/// Handles a raw request with the given handler
+fn handle<F>(handler: F, raw: &'static [u8]) -> Response
+where
+    F: Fn(Request) -> Response,
+{
+    let mut source = Source::from(raw);
+    let request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+    handler(request)
+}
+
+/// Gets a response field as string
+fn field<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+    let (_, value) = response.fields.iter().find(|(key, _)| key.eq_ignore_ascii_case(name.as_bytes()))?;
+    Some(std::str::from_utf8(value).expect("field is not valid UTF-8"))
+}
+
+/// Creates the test policy
+fn cors() -> Cors {
+    let mut cors = Cors::new();
+    cors.allow_origin("https://app.example.org");
+    cors.set_allowed_methods(["GET", "PUT"]);
+    cors.set_allowed_headers(["Content-Type", "X-Token"]);
+    cors.set_exposed_headers(["X-Request-Id"]);
+    cors.set_max_age(Duration::from_secs(600));
+    cors
+}
+
+/// Tests the preflight requests
+#[test]
+fn preflight() {
+    let handler = cors().wrap(|_| Response::new_404_notfound());
+
+    // Answer an allowed preflight request
+    let response = handle(&handler, b"OPTIONS /api HTTP/1.1\r\nOrigin: https://app.example.org\r\nAccess-Control-Request-Method: PUT\r\nAccess-Control-Request-Headers: x-token, content-type\r\n\r\n");
+    assert_eq!(&*response.status, b"204");
+    assert_eq!(field(&response, "Content-Length"), None);
+    assert_eq!(field(&response, "Access-Control-Allow-Origin"), Some("https://app.example.org"));
+    assert_eq!(field(&response, "Access-Control-Allow-Methods"), Some("GET, PUT"));
+    assert_eq!(field(&response, "Access-Control-Allow-Headers"), Some("Content-Type, X-Token"));
+    assert_eq!(field(&response, "Access-Control-Max-Age"), Some("600"));
+    assert_eq!(field(&response, "Vary"), Some("Origin"));
+
+    // Reject disallowed origins, methods and header fields
+    let response = handle(
+        &handler,
+        b"OPTIONS /api HTTP/1.1\r\nOrigin: https://evil.example.org\r\nAccess-Control-Request-Method: GET\r\n\r\n",
+    );
+    assert_eq!(&*response.status, b"403");
+    let response = handle(
+        &handler,
+        b"OPTIONS /api HTTP/1.1\r\nOrigin: https://app.example.org\r\nAccess-Control-Request-Method: DELETE\r\n\r\n",
+    );
+    assert_eq!(&*response.status, b"403");
+    let response = handle(&handler, b"OPTIONS /api HTTP/1.1\r\nOrigin: https://app.example.org\r\nAccess-Control-Request-Method: GET\r\nAccess-Control-Request-Headers: X-Other\r\n\r\n");
+    assert_eq!(&*response.status, b"403");
+
+    // Pass plain OPTIONS requests through
+    let response = handle(&handler, b"OPTIONS /api HTTP/1.1\r\n\r\n");
+    assert_eq!(&*response.status, b"404");
+}
+
+/// Tests the decoration of normal responses
+#[test]
+fn decorate() {
+    let handler = cors().wrap(|_| {
+        let mut response = Response::new_200_ok();
+        response.set_field("Vary", "Accept-Encoding");
+        response
+    });
+
+    // Decorate allowed origins
+    let response = handle(&handler, b"GET /api HTTP/1.1\r\nOrigin: https://app.example.org\r\n\r\n");
+    assert_eq!(field(&response, "Access-Control-Allow-Origin"), Some("https://app.example.org"));
+    assert_eq!(field(&response, "Access-Control-Expose-Headers"), Some("X-Request-Id"));
+    assert_eq!(field(&response, "Vary"), Some("Accept-Encoding, Origin"));
+
+    // Do not decorate other origins or same-origin requests
+    let response = handle(&handler, b"GET /api HTTP/1.1\r\nOrigin: https://evil.example.org\r\n\r\n");
+    assert_eq!(field(&response, "Access-Control-Allow-Origin"), None);
+    assert_eq!(field(&response, "Vary"), Some("Accept-Encoding, Origin"));
+    let response = handle(&handler, b"GET /api HTTP/1.1\r\n\r\n");
+    assert_eq!(field(&response, "Access-Control-Allow-Origin"), None);
+    assert_eq!(field(&response, "Vary"), Some("Accept-Encoding, Origin"));
+}
+
+/// Tests the wildcard origin and its exclusion of credentials
+#[test]
+fn any_origin() {
+    let mut cors = Cors::new();
+    cors.allow_any_origin().expect("failed to allow any origin");
+    let handler = cors.clone().wrap(|_| Response::new_200_ok());
+    let response = handle(&handler, b"GET / HTTP/1.1\r\nOrigin: https://any.example.org\r\n\r\n");
+    assert_eq!(field(&response, "Access-Control-Allow-Origin"), Some("*"));
+    let response = handle(&handler, b"GET / HTTP/1.1\r\n\r\n");
+    assert_eq!(field(&response, "Vary"), None);
+
+    // Credentials cannot be combined with any origin
+    assert!(cors.set_allow_credentials(true).is_err());
+    let mut cors = Cors::new();
+    cors.set_allow_credentials(true).expect("failed to allow credentials");
+    assert!(cors.allow_any_origin().is_err());
+
+    // Credentials echo an explicitly allowed origin
+    cors.allow_origin("https://app.example.org");
+    let handler = cors.wrap(|_| Response::new_200_ok());
+    let response = handle(&handler, b"GET / HTTP/1.1\r\nOrigin: https://app.example.org\r\n\r\n");
+    assert_eq!(field(&response, "Access-Control-Allow-Origin"), Some("https://app.example.org"));
+    assert_eq!(field(&response, "Access-Control-Allow-Credentials"), Some("true"));
+}
@@ -0,0 +1,77 @@
+use ehttpd::{
+    bytes::{Sink, Source},
+    http::{Cors, Deferred, Request, Response, ResponseExt},
+};
+use std::{thread, time::Duration};
+
+/// Performs a request with the given handler and returns the raw response
+fn reqresp<F>(handler: F) -> Vec<u8>
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let mut source = Source::from(b"GET / HTTP/1.1\r\n\r\n");
+    let mut sink = Sink::Vector(Vec::new());
+    let _ = ehttpd::reqresp(&mut source, &mut sink, handler);
+    let Sink::Vector(response) = sink else { unreachable!("sink is not a vector") };
+    response
+}
+
+/// Tests a deferred response that is completed by another thread
+#[test]
+fn completed() {
+    let response = reqresp(|_| {
+        let (deferred, completer) = Deferred::new(Duration::from_secs(5));
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            let mut response = Response::new_200_ok();
+            response.set_body_data("Testolope");
+            completer.complete(response);
+        });
+        deferred.into_response()
+    });
+    assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with(b"\r\n\r\nTestolope"));
+}
+
+/// Tests the fallbacks for expired and abandoned deferred responses
+#[test]
+fn fallbacks() {
+    // Keep the completer alive beyond the deadline
+    let response = reqresp(|_| {
+        let (deferred, completer) = Deferred::new(Duration::from_millis(50));
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            completer.complete(Response::new_200_ok());
+        });
+        deferred.into_response()
+    });
+    assert_eq!(response, b"HTTP/1.1 504 Gateway Timeout\r\nContent-Length: 0\r\n\r\n");
+
+    // Drop the completer
+    let response = reqresp(|_| {
+        let (deferred, _) = Deferred::new(Duration::from_secs(5));
+        deferred.into_response()
+    });
+    assert_eq!(response, b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n");
+}
+
+/// Tests that wrappers see the completed response
+#[test]
+fn wrapped() {
+    let mut cors = Cors::new();
+    cors.allow_origin("https://app.example.org");
+    let handler = cors.wrap(|_| {
+        let (deferred, completer) = Deferred::new(Duration::from_secs(5));
+        thread::spawn(move || completer.complete(Response::new_200_ok()));
+        deferred.into_response()
+    });
+
+    // Perform the request
+    let mut source = Source::from(b"GET / HTTP/1.1\r\nOrigin: https://app.example.org\r\n\r\n");
+    let mut sink = Sink::Vector(Vec::new());
+    let _ = ehttpd::reqresp(&mut source, &mut sink, handler);
+    let Sink::Vector(response) = sink else { unreachable!("sink is not a vector") };
+    let response = String::from_utf8(response).expect("response is not valid UTF-8");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    assert!(response.contains("\r\nAccess-Control-Allow-Origin: https://app.example.org\r\n"), "{response}");
+}
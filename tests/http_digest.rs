@@ -0,0 +1,65 @@
+use ehttpd::{
+    bytes::Source,
+    http::{expected_digest, Crc32c, DigestAlgorithm, DigestReader, Request},
+};
+use std::io::Read;
+
+/// Tests the CRC32C checksum against the standard check value
+#[test]
+fn crc32c() {
+    let mut crc32c = Crc32c::new();
+    crc32c.update(b"123456789");
+    assert_eq!(crc32c.value(), 0xE306_9283);
+    assert_eq!(crc32c.finalize(), [0xE3, 0x06, 0x92, 0x83]);
+}
+
+/// Tests the verification of a streamed body
+#[test]
+fn verify() {
+    let mut source = Source::from(
+        b"POST / HTTP/1.1\r\nContent-Length: 9\r\nContent-Digest: sha-256=:AAAA:, crc32c=:4waSgw==:\r\n\r\n123456789"
+            .as_slice(),
+    );
+    let request: Request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+    let expected = expected_digest(&request, "crc32c").expect("failed to get digest").expect("missing digest");
+
+    // Read the body and verify it
+    let mut reader = DigestReader::new(request.stream.take(9), Crc32c::new());
+    reader.read_to_end(&mut Vec::new()).expect("failed to read body");
+    reader.verify(&expected).expect("failed to verify body");
+    assert!(reader.verify(b"\x00\x00\x00\x00").is_err());
+}
+
+/// Tests the lookup of the expected digests
+#[test]
+fn expected() {
+    let mut source = Source::from(
+        b"POST / HTTP/1.1\r\nDigest: SHA-256=AAEC\r\nContent-MD5: AAECAw==\r\nRepr-Digest: crc32c=x\r\n\r\n".as_slice(),
+    );
+    let request: Request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+
+    assert_eq!(expected_digest(&request, "sha-256").expect("failed to get digest"), Some(vec![0, 1, 2]));
+    assert_eq!(expected_digest(&request, "md5").expect("failed to get digest"), Some(vec![0, 1, 2, 3]));
+    assert_eq!(expected_digest(&request, "sha-512").expect("failed to get digest"), None);
+    assert!(expected_digest(&request, "crc32c").is_err());
+}
+
+/// Tests that the base64-encoded digests are decoded strictly
+#[test]
+fn expected_strict() {
+    for (value, expected) in [
+        ("AAECAw==", Some(vec![0, 1, 2, 3])),
+        ("AAECAw", Some(vec![0, 1, 2, 3])),
+        ("AAECAw=", None),
+        ("AAECA===", None),
+        ("AAECAx==", None),
+    ] {
+        let request = format!("POST / HTTP/1.1\r\nContent-MD5: {value}\r\n\r\n");
+        let mut source = Source::from(request.into_bytes());
+        let request: Request =
+            Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+        assert_eq!(expected_digest(&request, "md5").ok().flatten(), expected, "{value}");
+    }
+}
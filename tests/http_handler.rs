@@ -0,0 +1,51 @@
+use ehttpd::{
+    bytes::Source,
+    http::{Handler, Request, Response, ResponseExt},
+};
+
+/// Serializes the response of the handler for the given request
+fn handle<F>(handler: &F, raw: &'static [u8]) -> String
+where
+    F: Fn(Request) -> Response,
+{
+    let mut source = Source::from(raw);
+    let request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+
+    let mut buf = Vec::new();
+    handler(request).to_stream(&mut buf).expect("failed to serialize response");
+    String::from_utf8(buf).expect("response is not valid UTF-8")
+}
+
+/// Tests the handler combinators
+#[test]
+fn combinators() {
+    let api = (|_: Request| {
+        let mut response = Response::new_200_ok();
+        response.set_body_data(b"api");
+        response
+    })
+    .filter(|request| request.target.starts_with(b"/api/"))
+    .map_response(|mut response| {
+        response.set_field("Cache-Control", "no-store");
+        response
+    });
+    let assets = (|_: Request| {
+        let mut response = Response::new_200_ok();
+        response.set_body_data(b"asset");
+        response
+    })
+    .filter(|request| request.target.starts_with(b"/assets/"));
+    let handler = api.or_else(assets).into_fn();
+
+    // Test the routing
+    assert_eq!(
+        handle(&handler, b"GET /api/test HTTP/1.1\r\n\r\n"),
+        "HTTP/1.1 200 OK\r\nContent-Length: 3\r\nCache-Control: no-store\r\n\r\napi"
+    );
+    assert_eq!(
+        handle(&handler, b"GET /assets/test HTTP/1.1\r\n\r\n"),
+        "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nasset"
+    );
+    assert!(handle(&handler, b"GET /other HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404 "));
+}
@@ -0,0 +1,60 @@
+use ehttpd::{
+    bytes::{Data, Source},
+    http::{HeaderMap, Request, Response, ResponseExt},
+};
+
+/// Collects the values as strings
+fn values<'a, I>(values: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a Data>,
+{
+    values.into_iter().map(|value| String::from_utf8_lossy(value).to_string()).collect()
+}
+
+/// Tests the lookup, append and replace semantics
+#[test]
+fn semantics() {
+    let mut map: HeaderMap =
+        [("Content-Type", "text/plain"), ("Set-Cookie", "a=1"), ("X-Test", "1")].into_iter().collect();
+    map.append("set-cookie", "b=2");
+
+    // Test the case-insensitive lookup
+    assert_eq!(map.get("content-type").map(|value| value.as_ref()), Some(b"text/plain".as_slice()));
+    assert_eq!(values(map.get_all("SET-COOKIE")), ["a=1", "b=2"]);
+    assert!(map.get("X-Missing").is_none());
+    assert_eq!(map.get_all("X-Missing").count(), 0);
+
+    // Replace the multi-value field in place
+    map.insert("Set-Cookie", "c=3");
+    assert_eq!(values(map.get_all("set-cookie")), ["c=3"]);
+    assert_eq!(values(map.iter().map(|(key, _)| key)), ["Content-Type", "Set-Cookie", "X-Test"]);
+
+    // Remove a field
+    assert_eq!(values(&map.remove("content-type")), ["text/plain"]);
+    assert_eq!(values(map.iter().map(|(key, _)| key)), ["Set-Cookie", "X-Test"]);
+    assert_eq!(map.get("x-test").map(|value| value.as_ref()), Some(b"1".as_slice()));
+    assert_eq!(map.len(), 2);
+}
+
+/// Tests the conversion from request fields
+#[test]
+fn request() {
+    let mut source = Source::from(b"GET / HTTP/1.1\r\nAccept: a\r\naccept: b\r\nHost: localhost\r\n\r\n".as_slice());
+    let request = Request::<4096>::from_stream(&mut source).expect("failed to parse request").expect("unexpected end");
+    let map = request.header_map();
+    assert_eq!(values(map.get_all("Accept")), ["a", "b"]);
+    assert!(std::ptr::eq(map, request.header_map()));
+    assert_eq!(Vec::from(map.clone()).len(), 3);
+}
+
+/// Tests the conversion from and back into response fields
+#[test]
+fn response() {
+    let mut response: Response = Response::new_200_ok();
+    response.set_field("X-Test", "1");
+    let mut map = response.take_header_map();
+    assert!(response.fields.is_empty());
+    map.insert("x-test", "2");
+    response.fields = map.into();
+    assert_eq!(values(response.fields.iter().map(|(_, value)| value)), ["0", "2"]);
+}
@@ -0,0 +1,110 @@
+use ehttpd::{
+    bytes::Source,
+    http::{Idempotency, Request, RequestExt, Response, ResponseExt},
+};
+use std::{
+    io::Read,
+    sync::atomic::{AtomicUsize, Ordering::SeqCst},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Serializes the response of the handler for the given request
+fn handle<F>(handler: &F, raw: &'static [u8]) -> String
+where
+    F: Fn(Request) -> Response,
+{
+    let mut source = Source::from(raw);
+    let request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+
+    let mut buf = Vec::new();
+    handler(request).to_stream(&mut buf).expect("failed to serialize response");
+    String::from_utf8(buf).expect("response is not valid UTF-8")
+}
+
+/// Scopes the keys by the `Authorization` field
+fn scope(request: &Request) -> Option<Vec<u8>> {
+    Some(request.field("Authorization")?.to_vec())
+}
+
+/// Tests that retries with the same key are replayed
+#[test]
+fn replay() {
+    // Count the handler invocations
+    let invocations = Arc::new(AtomicUsize::new(0));
+    let handler = Idempotency::new(Duration::from_secs(60)).wrap(scope, {
+        let invocations = invocations.clone();
+        move |_| {
+            let invocation = invocations.fetch_add(1, SeqCst);
+            let mut response = Response::new_status_reason(201, "Created");
+            response.set_body_data(format!("payment {invocation}"));
+            response
+        }
+    });
+
+    // Perform the original request and a retry
+    let original = handle(&handler, b"POST /payments HTTP/1.1\r\nAuthorization: alice\r\nIdempotency-Key: 7\r\n\r\n");
+    assert_eq!(original, "HTTP/1.1 201 Created\r\nContent-Length: 9\r\n\r\npayment 0");
+    let retry = handle(&handler, b"POST /payments HTTP/1.1\r\nAuthorization: alice\r\nIdempotency-Key: 7\r\n\r\n");
+    assert_eq!(retry, "HTTP/1.1 201 Created\r\nContent-Length: 9\r\nIdempotent-Replayed: true\r\n\r\npayment 0");
+    assert_eq!(invocations.load(SeqCst), 1);
+
+    // Other keys, targets and requests without key are not deduplicated
+    assert!(handle(&handler, b"POST /payments HTTP/1.1\r\nAuthorization: alice\r\nIdempotency-Key: 8\r\n\r\n")
+        .ends_with("payment 1"));
+    assert!(handle(&handler, b"POST /refunds HTTP/1.1\r\nAuthorization: alice\r\nIdempotency-Key: 7\r\n\r\n")
+        .ends_with("payment 2"));
+    assert!(handle(&handler, b"POST /payments HTTP/1.1\r\n\r\n").ends_with("payment 3"));
+
+    // Other clients cannot replay the response, and requests without scope are not deduplicated
+    let other = b"POST /payments HTTP/1.1\r\nAuthorization: mallory\r\nIdempotency-Key: 7\r\n\r\n";
+    assert!(handle(&handler, other).ends_with("payment 4"));
+    assert!(handle(&handler, b"POST /payments HTTP/1.1\r\nIdempotency-Key: 7\r\n\r\n").ends_with("payment 5"));
+}
+
+/// Tests that retries with a different body are rejected and that the body is passed to the handler
+#[test]
+fn body_mismatch() {
+    let handler = Idempotency::new(Duration::from_secs(60)).wrap(scope, |mut request: Request| {
+        let mut body = String::new();
+        request.body().expect("failed to get body").read_to_string(&mut body).expect("failed to read body");
+        let mut response = Response::new_200_ok();
+        response.set_body_data(body);
+        response
+    });
+
+    // Send a chunked request, a retry with the same body and a retry with a different body
+    let original = b"POST /payments HTTP/1.1\r\nAuthorization: alice\r\nIdempotency-Key: 7\r\n\
+        Transfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n";
+    assert!(handle(&handler, original).ends_with("\r\n\r\nabc"));
+    let retry = b"POST /payments HTTP/1.1\r\nAuthorization: alice\r\nIdempotency-Key: 7\r\n\
+        Content-Length: 3\r\n\r\nabc";
+    assert!(handle(&handler, retry).contains("\r\nIdempotent-Replayed: true\r\n"));
+    let changed = b"POST /payments HTTP/1.1\r\nAuthorization: alice\r\nIdempotency-Key: 7\r\n\
+        Content-Length: 3\r\n\r\nxyz";
+    assert!(handle(&handler, changed).starts_with("HTTP/1.1 422 Unprocessable Content\r\n"));
+}
+
+/// Tests that server errors and large bodies are not stored
+#[test]
+fn not_stored() {
+    let mut idempotency = Idempotency::new(Duration::from_secs(60));
+    idempotency.set_body_size_max(4);
+    let handler = idempotency.wrap(scope, |request: Request| match request.target.as_ref() {
+        b"/error" => Response::new_503_serviceunavailable(),
+        _ => {
+            let mut response = Response::new_200_ok();
+            response.set_body_data(b"Testolope");
+            response
+        }
+    });
+
+    // Perform the requests twice
+    for _ in 0..2 {
+        let error = handle(&handler, b"POST /error HTTP/1.1\r\nAuthorization: alice\r\nIdempotency-Key: 7\r\n\r\n");
+        assert_eq!(error, "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n");
+        let large = handle(&handler, b"POST /large HTTP/1.1\r\nAuthorization: alice\r\nIdempotency-Key: 7\r\n\r\n");
+        assert_eq!(large, "HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nTestolope");
+    }
+}
@@ -0,0 +1,34 @@
+use ehttpd::http::parse_header_params;
+
+/// Tests the parsing of header values with parameters
+#[test]
+fn parse() {
+    // Parse a content type
+    let parsed = parse_header_params("text/html; charset=UTF-8").expect("failed to parse value");
+    assert_eq!(&*parsed.value, b"text/html");
+    assert_eq!(parsed.param("Charset").map(|value| &**value), Some(&b"UTF-8"[..]));
+
+    // Parse quoted-strings with escapes and semicolons
+    let parsed =
+        parse_header_params(r#"attachment ; filename="a \"b\"; c.pdf";size=42"#).expect("failed to parse value");
+    assert_eq!(&*parsed.value, b"attachment");
+    assert_eq!(parsed.param("filename").map(|value| &**value), Some(&br#"a "b"; c.pdf"#[..]));
+    assert_eq!(parsed.param("size").map(|value| &**value), Some(&b"42"[..]));
+
+    // Parse cookie attributes with flags and skip empty parameters
+    let parsed = parse_header_params(r#"id=abc; Path="/";; HttpOnly; Secure"#).expect("failed to parse value");
+    assert_eq!(&*parsed.value, b"id=abc");
+    assert_eq!(parsed.param("path").map(|value| &**value), Some(&b"/"[..]));
+    let names: Vec<_> = parsed.params.iter().map(|(name, _)| String::from_utf8_lossy(name).into_owned()).collect();
+    assert_eq!(names, ["Path", "HttpOnly", "Secure"]);
+    assert_eq!(parsed.param("httponly").map(|value| value.len()), Some(0));
+    assert!(parsed.param("Domain").is_none());
+
+    // Reject invalid values
+    assert!(parse_header_params(r#"text/plain; charset="utf-8"#).is_err());
+    assert!(parse_header_params(r#"text/plain; charset="utf"-8"#).is_err());
+    assert!(parse_header_params("text/plain; =utf-8").is_err());
+    assert!(parse_header_params("text/plain; char set=utf-8").is_err());
+    assert!(parse_header_params("text/plain; charset=utf 8").is_err());
+    assert!(parse_header_params("id=abc; Path=/").is_err());
+}
@@ -0,0 +1,217 @@
+use ehttpd::{
+    bytes::{Data, Sink, Source},
+    error,
+    http::{OutboundRequest, Proxy, Request, Response, ResponseExt},
+};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread::{self, JoinHandle},
+};
+
+/// Starts a one-shot upstream that answers the next request with the given raw response and returns the raw request
+fn upstream(response: &'static [u8]) -> (SocketAddr, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let address = listener.local_addr().expect("failed to get listening address");
+    let thread = thread::spawn(move || {
+        // Read the request until the upstream sees the end of the request body
+        let (stream, _) = listener.accept().expect("failed to accept connection");
+        let mut reader = BufReader::new(stream.try_clone().expect("failed to clone stream"));
+        let mut request = String::new();
+        while !request.ends_with("\r\n\r\n") {
+            reader.read_line(&mut request).expect("failed to read request");
+        }
+        if request.contains("Transfer-Encoding: chunked") {
+            while !request.ends_with("0\r\n\r\n") {
+                reader.read_line(&mut request).expect("failed to read request");
+            }
+        } else if let Some(len) = request.split("Content-Length: ").nth(1) {
+            let len: u64 = len.split("\r\n").next().unwrap_or_default().parse().expect("invalid content length");
+            reader.take(len).read_to_string(&mut request).expect("failed to read request");
+        }
+
+        // Write the response and close the connection
+        (&stream).write_all(response).expect("failed to write response");
+        request
+    });
+    (address, thread)
+}
+
+/// Forwards the raw request via the proxy and returns the serialized response
+fn forward(proxy: &Proxy, raw: &'static [u8]) -> String {
+    let mut source = Source::from(raw);
+    let mut request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+    let mut response: Response = proxy.forward(&mut request).expect("failed to forward request");
+
+    let mut buf = Vec::new();
+    response.to_stream(&mut buf).expect("failed to serialize response");
+    String::from_utf8(buf).expect("response is not valid UTF-8")
+}
+
+/// Tests forwarding a request with a fixed-length body
+#[test]
+fn fixed() {
+    let (address, upstream) =
+        upstream(b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 201 Created\r\nContent-Length: 2\r\nKeep-Alive: 5\r\n\r\nok");
+    let proxy = Proxy::new(address);
+    let response = forward(
+        &proxy,
+        b"POST /items?x=1 HTTP/1.1\r\nHost: example.org\r\nConnection: keep-alive, X-Private\r\nX-Private: 1\r\nContent-Length: 4\r\n\r\nbody",
+    );
+    assert_eq!(response, "HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok");
+
+    // Validate the upstream request
+    let request = upstream.join().expect("upstream panicked");
+    assert_eq!(
+        request,
+        format!(
+            "POST /items?x=1 HTTP/1.1\r\nX-Forwarded-Host: example.org\r\nHost: {address}\r\nContent-Length: 4\r\nConnection: close\r\n\r\nbody"
+        )
+    );
+}
+
+/// Tests forwarding chunked bodies in both directions
+#[test]
+fn chunked() {
+    let (address, upstream) =
+        upstream(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nTest\r\n5\r\nolope\r\n0\r\n\r\n");
+    let mut proxy = Proxy::new(address);
+    proxy.set_host(None::<String>);
+    let response = forward(
+        &proxy,
+        b"PUT / HTTP/1.1\r\nHost: example.org\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n",
+    );
+    assert_eq!(response, "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nTest\r\n5\r\nolope\r\n0\r\n\r\n");
+
+    // Validate the re-chunked upstream request
+    let request = upstream.join().expect("upstream panicked");
+    assert_eq!(
+        request,
+        "PUT / HTTP/1.1\r\nHost: example.org\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n"
+    );
+}
+
+/// Tests that chunked response bodies are delimited by closing the connection for HTTP/1.0 clients
+#[test]
+fn chunked_http10() {
+    let (address, upstream) = upstream(
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: X-Hop\r\nX-Hop: 1\r\n\r\n4\r\nTest\r\n5\r\nolope\r\n0\r\n\r\n",
+    );
+    let proxy = Proxy::new(address);
+    let response = forward(&proxy, b"GET / HTTP/1.0\r\nHost: example.org\r\n\r\n");
+    assert_eq!(response, "HTTP/1.1 200 OK\r\nConnection: Close\r\n\r\nTestolope");
+    upstream.join().expect("upstream panicked");
+}
+
+/// Tests that the upstream host is sent even if the client has sent no `Host` field
+#[test]
+fn host_missing() {
+    let (address, upstream) = upstream(b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nTestolope");
+    let proxy = Proxy::new(address);
+    let response = forward(&proxy, b"GET / HTTP/1.0\r\nX-Forwarded-Host: spoofed.org\r\n\r\n");
+    assert_eq!(response, "HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nTestolope");
+
+    // Validate the upstream request
+    let request = upstream.join().expect("upstream panicked");
+    assert_eq!(request, format!("GET / HTTP/1.1\r\nHost: {address}\r\nConnection: close\r\n\r\n"));
+}
+
+/// Tests that a truncated fixed-length upstream body fails the response
+#[test]
+fn truncated() {
+    let (address, upstream) = upstream(b"HTTP/1.1 200 OK\r\nContent-Length: 16\r\n\r\nTestolope");
+    let proxy = Proxy::new(address);
+    let mut source = Source::from(b"GET / HTTP/1.1\r\n\r\n");
+    let mut request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+    let mut response: Response = proxy.forward(&mut request).expect("failed to forward request");
+    upstream.join().expect("upstream panicked");
+
+    let mut buf = Vec::new();
+    assert!(response.to_stream(&mut buf).is_err());
+}
+
+/// Tests the gateway error responses
+#[test]
+fn unreachable() {
+    // Get a free port without a listener
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let address = listener.local_addr().expect("failed to get listening address");
+    drop(listener);
+
+    // Forward a request
+    let handler = Proxy::new(address).into_fn();
+    let mut source = Source::from(b"GET / HTTP/1.1\r\n\r\n");
+    let request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+    let response = handler(request);
+    assert_eq!(response.status.as_ref(), b"502");
+    assert_eq!(response.content_length().expect("invalid content length"), Some(0));
+}
+
+/// Tests relaying a large fixed-length body and a truncated body to a client connection through the server adapter
+#[test]
+fn relayed() {
+    // Create a large upstream response
+    let mut body = Vec::new();
+    (0..256 * 1024).for_each(|index| body.push(b'a' + (index % 26) as u8));
+    let mut raw = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+    raw.extend_from_slice(&body);
+
+    // Relay the responses to a client connection
+    for (raw, complete) in
+        [(&*Vec::leak(raw), true), (b"HTTP/1.1 200 OK\r\nContent-Length: 16\r\n\r\nTestolope".as_slice(), false)]
+    {
+        let (address, upstream) = upstream(raw);
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+        let mut client = TcpStream::connect(listener.local_addr().expect("failed to get listening address"))
+            .expect("failed to connect to server");
+        let (stream, _) = listener.accept().expect("failed to accept connection");
+
+        // Handle a single request and close the server side
+        client.write_all(b"GET / HTTP/1.1\r\n\r\n").expect("failed to write request");
+        let mut source = Source::buffered(stream.try_clone().expect("failed to clone stream"));
+        let keep_alive = ehttpd::reqresp(&mut source, &mut Sink::from(stream), Proxy::new(address).into_fn());
+        assert_eq!(keep_alive, complete);
+        drop(source);
+        upstream.join().expect("upstream panicked");
+
+        // Validate the relayed response
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).expect("failed to read response");
+        assert_eq!(response, raw);
+    }
+}
+
+/// Tests that the signer can decorate upstream requests and that a failing signer prevents forwarding
+#[test]
+fn signer() {
+    let (address, upstream) = upstream(b"HTTP/1.1 204 No Content\r\n\r\n");
+    let mut proxy = Proxy::new(address);
+    proxy.set_signer(|request: &mut OutboundRequest| {
+        if request.target.eq(b"/forbidden") {
+            return Err(error!("Refusing to sign request"));
+        }
+        request.fields.push((Data::from(b"X-Service-Auth"), Data::from(format!("{} token", request.body.is_none()))));
+        Ok(())
+    });
+    let response = forward(&proxy, b"DELETE /items/7 HTTP/1.1\r\nHost: example.org\r\n\r\n");
+    assert_eq!(response, "HTTP/1.1 204 No Content\r\n\r\n");
+
+    // Validate the upstream request
+    let request = upstream.join().expect("upstream panicked");
+    assert_eq!(
+        request,
+        format!(
+            "DELETE /items/7 HTTP/1.1\r\nX-Forwarded-Host: example.org\r\nHost: {address}\r\nConnection: close\r\nX-Service-Auth: true token\r\n\r\n"
+        )
+    );
+
+    // Validate that failing signers prevent forwarding
+    let mut source = Source::from(b"GET /forbidden HTTP/1.1\r\n\r\n");
+    let mut request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+    let error = proxy.forward(&mut request).expect_err("forwarded unsigned request");
+    assert!(error.to_string().starts_with("Refusing to sign request"), "unexpected error: {error}");
+}
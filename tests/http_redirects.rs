@@ -0,0 +1,79 @@
+use ehttpd::{
+    bytes::Source,
+    http::{Redirects, Request, Response, ResponseExt, StatusCode},
+};
+
+/// Gets the status and location of the redirect for the given request, or `None` if the request was not redirected
+fn handle(redirects: &Redirects, raw: &'static [u8]) -> Option<(u16, String)> {
+    let mut source = Source::from(raw);
+    let request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+
+    let response: Response = redirects.handle(&request)?;
+    assert_eq!(response.content_length().expect("invalid content length"), Some(0));
+    let status = response.status_code().expect("invalid status code").as_u16();
+    let (_, location) = response.fields.iter().find(|(key, _)| key.eq(b"Location")).expect("missing location");
+    Some((status, String::from_utf8(location.to_vec()).expect("location is not valid UTF-8")))
+}
+
+/// Tests the redirect table
+#[test]
+fn redirects() {
+    let mut redirects = Redirects::new();
+    redirects.add("www.example.org", "*", "https://example.org", StatusCode::PERMANENT_REDIRECT).expect("invalid rule");
+    redirects.add("*.old.example.org", "*", "https://example.org", StatusCode::FOUND).expect("invalid rule");
+    redirects
+        .add("example.org", "/blog/*", "https://blog.example.org/", StatusCode::MOVED_PERMANENTLY)
+        .expect("invalid rule");
+    redirects.add("*", "/legacy.php", "/current", StatusCode::MOVED_PERMANENTLY).expect("invalid rule");
+
+    // Host redirects
+    assert_eq!(
+        handle(&redirects, b"GET /a/b?c=d HTTP/1.1\r\nHost: WWW.example.org:8080\r\n\r\n"),
+        Some((308, "https://example.org/a/b?c=d".to_string()))
+    );
+    assert_eq!(
+        handle(&redirects, b"GET / HTTP/1.1\r\nHost: a.b.old.example.org\r\n\r\n"),
+        Some((302, "https://example.org/".to_string()))
+    );
+    assert_eq!(handle(&redirects, b"GET / HTTP/1.1\r\nHost: old.example.org\r\n\r\n"), None);
+
+    // Path redirects
+    assert_eq!(
+        handle(&redirects, b"GET /blog/2024/post?x HTTP/1.1\r\nHost: example.org\r\n\r\n"),
+        Some((301, "https://blog.example.org/2024/post?x".to_string()))
+    );
+    assert_eq!(handle(&redirects, b"GET /blog HTTP/1.1\r\nHost: example.org\r\n\r\n"), None);
+    assert_eq!(handle(&redirects, b"GET /legacy.php?id=7 HTTP/1.1\r\n\r\n"), Some((301, "/current?id=7".to_string())));
+    assert_eq!(handle(&redirects, b"GET /legacy.php/x HTTP/1.1\r\n\r\n"), None);
+
+    // Invalid rules
+    assert!(redirects.add("*", "*", "/", StatusCode::OK).is_err());
+    assert!(redirects.add("exa mple.org", "*", "/", StatusCode::FOUND).is_err());
+    assert!(redirects.add("*", "*", "//evil.example", StatusCode::FOUND).is_err());
+    assert!(redirects.add("*", "*", "evil.example", StatusCode::FOUND).is_err());
+}
+
+/// Tests that the appended path cannot change the authority of the redirect target
+#[test]
+fn open_redirect() {
+    let mut redirects = Redirects::new();
+    redirects.add("*", "/old/*", "/", StatusCode::FOUND).expect("invalid rule");
+    redirects.add("example.org", "*", "https://example.org", StatusCode::FOUND).expect("invalid rule");
+    redirects.add("*", "/legacy/*", "https://example.org", StatusCode::FOUND).expect("invalid rule");
+
+    // Leading slashes of the appended path are collapsed
+    assert_eq!(
+        handle(&redirects, b"GET /old//evil.example HTTP/1.1\r\n\r\n"),
+        Some((302, "/evil.example".to_string()))
+    );
+    assert_eq!(handle(&redirects, b"GET /old/a HTTP/1.1\r\n\r\n"), Some((302, "/a".to_string())));
+    assert_eq!(
+        handle(&redirects, b"GET /legacy/@evil.example HTTP/1.1\r\n\r\n"),
+        Some((302, "https://example.org/@evil.example".to_string()))
+    );
+
+    // Targets that are not in origin-form are not redirected
+    assert_eq!(handle(&redirects, b"GET @evil.example HTTP/1.1\r\nHost: example.org\r\n\r\n"), None);
+    assert_eq!(handle(&redirects, b"GET //evil.example HTTP/1.1\r\nHost: example.org\r\n\r\n"), None);
+}
@@ -0,0 +1,44 @@
+use ehttpd::{
+    bytes::Source,
+    http::{report_endpoint, Report, ReportKind, Request, Response},
+};
+use std::sync::{Arc, Mutex};
+
+/// Posts the given raw request to a report endpoint and returns the response status and the received reports
+fn post(raw: &'static [u8]) -> (String, Vec<Report>) {
+    // Create the endpoint
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let reports_ = reports.clone();
+    let endpoint = report_endpoint(64, move |report| reports_.lock().expect("failed to lock reports").push(report));
+
+    // Handle the request
+    let mut source = Source::from(raw);
+    let request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+    let response: Response = endpoint(request);
+    let reports = reports.lock().expect("failed to lock reports").drain(..).collect();
+    (response.status.to_string(), reports)
+}
+
+/// Tests the report endpoint
+#[test]
+fn endpoint() {
+    let (status, reports) = post(
+        b"POST /reports HTTP/1.1\r\nContent-Type: application/reports+json\r\nContent-Length: 23\r\n\r\n[{\"type\":\"csp\",\"n\":-1}]",
+    );
+    assert_eq!(status, "204");
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].kind, ReportKind::Reports);
+    assert_eq!(reports[0].payload, br#"[{"type":"csp","n":-1}]"#.as_slice());
+
+    // Invalid requests
+    let (status, reports) =
+        post(b"POST /reports HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 4\r\n\r\n{\"a\"");
+    assert_eq!((status.as_str(), reports.len()), ("400", 0));
+    let (status, _) = post(b"POST /reports HTTP/1.1\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\n{}");
+    assert_eq!(status, "415");
+    let (status, _) = post(b"POST /reports HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 65\r\n\r\n");
+    assert_eq!(status, "413");
+    let (status, _) = post(b"GET /reports HTTP/1.1\r\n\r\n");
+    assert_eq!(status, "405");
+}
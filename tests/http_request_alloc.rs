@@ -0,0 +1,68 @@
+use ehttpd::{bytes::Source, http::Request};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// A global allocator that counts the allocations of the current thread while enabled
+struct CountingAllocator;
+thread_local! {
+    /// The allocation count of the current thread, or `None` if counting is disabled
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+impl CountingAllocator {
+    /// Counts an allocation if counting is enabled for the current thread
+    fn count() {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get().map(|count| count + 1)));
+    }
+}
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Parses the request and returns the number of allocations performed by the parser
+fn allocations(raw: &'static [u8]) -> usize {
+    let mut source = Source::from(raw);
+
+    // Parse the request and count the allocations
+    ALLOCATIONS.with(|count| count.set(Some(0)));
+    let request: Result<Option<Request>, _> = Request::from_stream(&mut source);
+    let allocations = ALLOCATIONS.with(|count| count.take()).expect("allocation counting was disabled");
+
+    // Validate the request
+    let request = request.expect("failed to parse request").expect("unexpected end of stream");
+    assert_eq!(request.method, b"GET".as_slice());
+    allocations
+}
+
+/// Tests that the parser does not allocate per parsed component
+#[test]
+fn parse_allocations() {
+    // Parse a minimal and a large request
+    let minimal = allocations(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    let large = allocations(
+        concat!(
+            "GET /testolope HTTP/1.1\r\n",
+            "Host: localhost\r\nA: 1\r\nB: 2\r\nC: 3\r\nD: 4\r\nE: 5\r\nF: 6\r\nG: 7\r\nH: 8\r\n",
+            "I: 9\r\nJ: 10\r\nK: 11\r\nL: 12\r\nM: 13\r\nN: 14\r\nO: 15\r\nP: 16\r\nQ: 17\r\nR: 18\r\n",
+            "S: 19\r\nT: 20\r\nU: 21\r\nV: 22\r\nW: 23\r\nX: 24\r\nY: 25\r\nZ: 26\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+
+    // The additional fields may only cost the amortized growth of the field list
+    // Note: A copy per key or value would cost at least 52 additional allocations
+    assert!(large - minimal <= 4, "parsing allocated per component ({minimal} vs {large} allocations)");
+}
@@ -0,0 +1,271 @@
+use ehttpd::{
+    bytes::{Data, Sink, Source},
+    http::{Body, FrameOptions, Framing, PartialWrite, Request, Response, ResponseExt, SecurityHeaders, StatusCode},
+};
+use std::io::{self, Write};
+
+/// Serializes a response
+fn serialize(mut response: Response) -> String {
+    let mut buf = Vec::new();
+    response.to_stream(&mut buf).expect("failed to serialize response");
+    String::from_utf8(buf).expect("response is not valid UTF-8")
+}
+
+/// Tests the framing of responses created from a status code
+#[test]
+fn new() {
+    let response: Response = Response::new(StatusCode::BAD_GATEWAY);
+    assert_eq!(serialize(response), "HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\n\r\n");
+    let response: Response = Response::new(StatusCode::NO_CONTENT);
+    assert_eq!(serialize(response), "HTTP/1.1 204 No Content\r\n\r\n");
+}
+
+/// Tests the synthesized body for empty error responses
+#[test]
+fn make_error_body() {
+    // Error responses get a body
+    let mut response = Response::new_404_notfound();
+    response.make_error_body();
+    assert_eq!(
+        serialize(response),
+        "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 15\r\n\r\n404 Not Found\r\n"
+    );
+
+    // Successful and non-empty responses are untouched
+    let mut response = Response::new_200_ok();
+    response.make_error_body();
+    assert_eq!(serialize(response), "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+
+    let mut response = Response::new_500_internalservererror();
+    response.set_body_data(b"Testolope");
+    response.make_error_body();
+    assert_eq!(serialize(response), "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 9\r\n\r\nTestolope");
+}
+
+/// Tests ranged data bodies
+#[test]
+fn set_body_data_range() {
+    let mut response: Response = Response::new_206_partialcontent();
+    response.set_body_data_range(b"Testolope", 4..=8).expect("failed to set body range");
+    assert_eq!(
+        serialize(response),
+        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 4-8/9\r\nContent-Length: 5\r\n\r\nolope"
+    );
+
+    let mut response: Response = Response::new_206_partialcontent();
+    assert!(response.set_body_data_range(b"Testolope", 4..=9).is_err());
+}
+
+/// Tests bodies with unknown length
+#[test]
+fn set_body_chunked() {
+    let mut response: Response = Response::new_200_ok();
+    response.set_body(Source::from("Testolope"));
+    assert_eq!(serialize(response), "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n9\r\nTestolope\r\n0\r\n\r\n");
+
+    let mut response: Response = Response::new_200_ok();
+    response.set_body(Body::with_framing(Source::from("Testolope"), None, Framing::Close));
+    assert_eq!(serialize(response), "HTTP/1.1 200 OK\r\nConnection: Close\r\n\r\nTestolope");
+}
+
+/// Tests that the framing fields are derived from directly assigned bodies
+#[test]
+fn body_assigned() {
+    let mut response: Response = Response::new_200_ok();
+    response.body = Body::new(Source::from("Testolope"), None);
+    assert_eq!(serialize(response), "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n9\r\nTestolope\r\n0\r\n\r\n");
+
+    let mut response: Response = Response::new_200_ok();
+    response.body = Body::from(Data::from(b"Testolope"));
+    assert_eq!(serialize(response), "HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nTestolope");
+
+    let mut response: Response = Response::new_200_ok();
+    response.body = Body::with_framing(Source::from("Testolope"), None, Framing::Close);
+    assert_eq!(serialize(response), "HTTP/1.1 200 OK\r\nConnection: Close\r\n\r\nTestolope");
+
+    // Responses without a body keep the announced framing
+    let mut response: Response = Response::new_200_ok();
+    response.set_body_data(b"Testolope");
+    response.make_head();
+    assert_eq!(serialize(response), "HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\n");
+}
+
+/// Tests that bodies with unknown length are delimited by closing the connection for HTTP/1.0 clients
+#[test]
+fn set_body_chunked_http10() {
+    for (version, expected) in [
+        ("HTTP/1.0", "HTTP/1.1 200 OK\r\nConnection: Close\r\n\r\nTestolope"),
+        ("HTTP/1.1", "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n9\r\nTestolope\r\n0\r\n\r\n"),
+    ] {
+        let mut source = Source::from(format!("GET / {version}\r\n\r\n").into_bytes());
+        let mut sink = Sink::Vector(Vec::new());
+        let _ = ehttpd::reqresp(&mut source, &mut sink, |_: Request| {
+            let mut response = Response::new_200_ok();
+            response.set_body(Source::from("Testolope"));
+            response
+        });
+        let Sink::Vector(response) = sink else { unreachable!("sink is not a vector") };
+        assert_eq!(String::from_utf8(response).expect("response is not valid UTF-8"), expected);
+    }
+}
+
+/// Tests that fixed-length bodies fail if the source is shorter than the announced length
+#[test]
+fn set_body_short() {
+    let mut response: Response = Response::new_200_ok();
+    response.set_body(Body::new(Source::from("Test"), Some(9)));
+    let error = response.to_stream(&mut Vec::new()).expect_err("short body was written");
+    assert_eq!(error.io_kind(), Some(io::ErrorKind::UnexpectedEof));
+}
+
+/// Tests the typed body helpers
+#[test]
+fn set_body_typed() {
+    let mut response: Response = Response::new_200_ok();
+    response.set_body_xml("<x/>");
+    assert_eq!(
+        serialize(response),
+        "HTTP/1.1 200 OK\r\nContent-Type: application/xml; charset=utf-8\r\nContent-Length: 4\r\n\r\n<x/>"
+    );
+}
+
+/// Tests that consecutive responses on the same thread do not share any header state
+#[test]
+fn header_buffer_reuse() {
+    // Serialize a response with a large header
+    let mut response = Response::new_200_ok();
+    response.set_field("X-Large", "x".repeat(8192));
+    assert!(serialize(response).ends_with(&format!("X-Large: {}\r\n\r\n", "x".repeat(8192))));
+
+    // Serialize a small response on the same thread
+    assert_eq!(serialize(Response::new_404_notfound()), "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+}
+
+/// A writer that counts the write calls
+#[derive(Default)]
+struct CountingWriter {
+    /// The written bytes
+    written: Vec<u8>,
+    /// The amount of write calls
+    writes: usize,
+}
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Tests that small in-memory responses are written at once
+#[test]
+fn small_response() {
+    // Write a small response
+    let mut response: Response = Response::new_200_ok();
+    response.set_body_data(b"{\"status\":\"ok\"}");
+    let mut writer = CountingWriter::default();
+    response.to_stream(&mut writer).expect("failed to write response");
+    assert_eq!(writer.writes, 1);
+    assert!(writer.written.ends_with(b"\r\n\r\n{\"status\":\"ok\"}"));
+
+    // Write a large response
+    let mut response: Response = Response::new_200_ok();
+    response.set_body_data(vec![b'x'; 8192]);
+    let mut writer = CountingWriter::default();
+    response.to_stream(&mut writer).expect("failed to write response");
+    assert!(writer.writes > 1);
+    assert!(writer.written.ends_with(&[b'x'; 8192]));
+}
+
+/// A writer that fails after a given amount of bytes
+struct FailingWriter {
+    /// The remaining amount of bytes before the writer fails
+    remaining: usize,
+}
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        let len = buf.len().min(self.remaining);
+        self.remaining -= len;
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Tests the recording of partially written responses
+#[test]
+fn partial_write() {
+    // Write a ranged response that fails midway through the body
+    let mut response: Response = Response::new_206_partialcontent();
+    response.set_field("ETag", "\"v1\"");
+    response.set_body_data_range(vec![b'x'; 16384], 1024..=16383).expect("failed to set range");
+    let header_len = serialize(Response::new_206_partialcontent()).len();
+
+    let mut body_sent = 0;
+    let mut writer = FailingWriter { remaining: 10_000 };
+    assert!(response.to_stream_counted(&mut writer, &mut body_sent).is_err());
+    assert!(body_sent > 0 && body_sent <= 10_000 - header_len as u64, "{body_sent}");
+
+    // Create the resumption record
+    let partial = PartialWrite::new(Data::from(b"/file"), &response, body_sent);
+    assert_eq!(partial.etag.as_deref(), Some(&b"\"v1\""[..]));
+    assert_eq!((partial.offset, partial.sent, partial.len), (1024, body_sent, Some(16384)));
+    assert_eq!(partial.resume_range(), format!("bytes={}-", 1024 + body_sent));
+}
+
+/// Tests the JSON body serialization
+#[test]
+#[cfg(feature = "json")]
+fn set_body_json() {
+    let mut response: Response = Response::new_200_ok();
+    response.set_body_json(&serde_json::json!({ "status": "ok" })).expect("failed to serialize body");
+    assert_eq!(
+        serialize(response),
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 15\r\n\r\n{\"status\":\"ok\"}"
+    );
+}
+
+/// Tests the security header preset
+#[test]
+fn security_headers() {
+    // Apply the default preset with a CSP
+    let mut security_headers = SecurityHeaders::new();
+    security_headers.set_csp_directive("default-src", "'none'");
+    security_headers.set_csp_directive("img-src", "'self'");
+    security_headers.set_csp_directive("DEFAULT-SRC", "'self'");
+    let mut response = Response::new_200_ok();
+    response.set_field("X-Frame-Options", "SAMEORIGIN");
+    response.apply_security_headers(&security_headers);
+    let serialized = serialize(response);
+    assert!(serialized.contains("\r\nStrict-Transport-Security: max-age=31536000; includeSubDomains\r\n"));
+    assert!(serialized.contains("\r\nX-Content-Type-Options: nosniff\r\n"));
+    assert!(serialized.contains("\r\nReferrer-Policy: strict-origin-when-cross-origin\r\n"));
+    assert!(serialized.contains("\r\nContent-Security-Policy: default-src 'self'; img-src 'self'\r\n"));
+
+    // Existing fields are not overridden
+    assert!(serialized.contains("\r\nX-Frame-Options: SAMEORIGIN\r\n"));
+    assert!(!serialized.contains("DENY"));
+
+    // Omit fields
+    security_headers.set_hsts(None);
+    security_headers.set_referrer_policy(None::<&str>);
+    security_headers.set_frame_options(Some(FrameOptions::SameOrigin));
+    let keys: Vec<_> = security_headers.fields().into_iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["X-Content-Type-Options", "X-Frame-Options", "Content-Security-Policy"]);
+}
+
+/// Tests the Basic authentication challenge
+#[test]
+fn basic_challenge() {
+    let response = Response::new_401_basic(r#"Admin "Area""#);
+    assert!(
+        serialize(response).contains("\r\nWWW-Authenticate: Basic realm=\"Admin \\\"Area\\\"\", charset=\"UTF-8\"\r\n")
+    );
+}
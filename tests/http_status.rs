@@ -0,0 +1,38 @@
+use ehttpd::http::{Response, ResponseExt, StatusCode};
+
+/// Tests the status code classes and reason phrases
+#[test]
+fn status_code() {
+    assert_eq!(StatusCode::NOT_FOUND.canonical_reason(), Some("Not Found"));
+    assert_eq!(StatusCode::from_u16(599).expect("failed to create status code").canonical_reason(), None);
+    assert!(StatusCode::NO_CONTENT.is_success() && !StatusCode::NO_CONTENT.is_error());
+    assert!(StatusCode::SEE_OTHER.is_redirection());
+    assert!(StatusCode::CONFLICT.is_client_error() && StatusCode::CONFLICT.is_error());
+    assert!(StatusCode::BAD_GATEWAY.is_server_error());
+    assert!(StatusCode::CONTINUE.is_informational());
+
+    // Test the conversions
+    assert_eq!("418".parse::<StatusCode>().expect("failed to parse status code").as_u16(), 418);
+    assert!(StatusCode::from_u16(99).is_err());
+    assert!(StatusCode::from_u16(1000).is_err());
+    assert!(StatusCode::parse("2OO").is_err());
+    assert!(StatusCode::parse("+20").is_err());
+}
+
+/// Tests the typed response constructor
+#[test]
+fn response() {
+    let response: Response = Response::new(StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(&*response.version, b"HTTP/1.1");
+    assert_eq!(&*response.status, b"429");
+    assert_eq!(&*response.reason, b"Too Many Requests");
+    assert_eq!(response.status_code().expect("invalid status code"), StatusCode::TOO_MANY_REQUESTS);
+
+    // Non-standard status codes have an empty reason phrase
+    let response: Response = Response::new(StatusCode::from_u16(299).expect("failed to create status code"));
+    assert_eq!(&*response.reason, b"");
+
+    // Test the status code of the convenience constructors
+    let response: Response = Response::new_404_notfound();
+    assert_eq!(response.status_code().expect("invalid status code"), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,18 @@
+#![cfg(feature = "template")]
+
+use ehttpd::http::template::{escape_html, render};
+
+/// Tests template rendering
+#[test]
+fn render_template() {
+    let template = "<h1>{{ status }} {{reason}}</h1><p>{{unknown}}</p>{{";
+    let rendered = render(template, &[("status", "404"), ("reason", "Not Found")]);
+    assert_eq!(rendered, "<h1>404 Not Found</h1><p>{{unknown}}</p>{{");
+}
+
+/// Tests HTML escaping
+#[test]
+fn escape() {
+    let escaped = escape_html(r#"<a href="x">Tom & Jerry's</a>"#);
+    assert_eq!(escaped, "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;");
+}
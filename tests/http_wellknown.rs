@@ -0,0 +1,46 @@
+use ehttpd::{
+    bytes::{Data, Source},
+    http::{Request, Response, WellKnown},
+};
+
+/// Serializes the response for the given request, or returns `None` if the request was not handled
+fn handle(well_known: &WellKnown, raw: &'static [u8]) -> Option<String> {
+    let mut source = Source::from(raw);
+    let request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+
+    let mut buf = Vec::new();
+    let mut response: Response = well_known.handle(&request)?;
+    response.to_stream(&mut buf).expect("failed to serialize response");
+    Some(String::from_utf8(buf).expect("response is not valid UTF-8"))
+}
+
+/// Tests the well-known registry
+#[test]
+fn well_known() {
+    let mut well_known = WellKnown::new();
+    well_known.register_security_txt("Contact: mailto:security@example.org\n");
+    well_known.register_acme_challenge(|token| (token == b"token").then(|| Data::from("token.key")));
+
+    // Registered resources
+    assert_eq!(
+        handle(&well_known, b"GET /.well-known/security.txt?x=y HTTP/1.1\r\n\r\n").expect("request was not handled"),
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 37\r\n\r\nContact: mailto:security@example.org\n"
+    );
+    assert_eq!(
+        handle(&well_known, b"GET /.well-known/acme-challenge/token HTTP/1.1\r\n\r\n")
+            .expect("request was not handled"),
+        "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: 9\r\n\r\ntoken.key"
+    );
+
+    // Unknown resources and methods
+    let response = handle(&well_known, b"GET /.well-known/acme-challenge/other HTTP/1.1\r\n\r\n");
+    assert!(response.expect("request was not handled").starts_with("HTTP/1.1 404 "));
+    let response = handle(&well_known, b"GET /.well-known/other HTTP/1.1\r\n\r\n");
+    assert!(response.expect("request was not handled").starts_with("HTTP/1.1 404 "));
+    let response = handle(&well_known, b"POST /.well-known/security.txt HTTP/1.1\r\n\r\n");
+    assert!(response.expect("request was not handled").starts_with("HTTP/1.1 405 "));
+
+    // Other paths are passed through
+    assert!(handle(&well_known, b"GET /security.txt HTTP/1.1\r\n\r\n").is_none());
+}
@@ -0,0 +1,24 @@
+use ehttpd::limits::PeerLimit;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Tests that the per-peer limit is enforced and released
+#[test]
+fn peer_limit() {
+    let limit = PeerLimit::new(2);
+    let peer = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let other = IpAddr::V6(Ipv6Addr::LOCALHOST);
+
+    // Acquire all slots
+    let first = limit.acquire(peer).expect("failed to acquire first slot");
+    let _second = limit.acquire(peer).expect("failed to acquire second slot");
+    assert!(limit.acquire(peer).is_none());
+    assert_eq!(limit.count(peer), 2);
+
+    // Other peers are not affected
+    let _other = limit.acquire(other).expect("failed to acquire slot for other peer");
+
+    // Release a slot and acquire it again
+    drop(first);
+    assert_eq!(limit.count(peer), 1);
+    let _third = limit.acquire(peer).expect("failed to reacquire slot");
+}
@@ -0,0 +1,41 @@
+use ehttpd::log::{self, Level};
+
+/// Tests the log level filtering
+#[test]
+fn level() {
+    // Test the default level
+    #[cfg(not(feature = "log"))]
+    {
+        assert_eq!(log::level(), Level::Warn);
+        assert!(log::enabled(Level::Error));
+        assert!(!log::enabled(Level::Info));
+    }
+
+    // Test a custom level
+    log::set_level(Level::Info);
+    #[cfg(not(feature = "log"))]
+    assert!(log::enabled(Level::Info));
+    assert!(!log::enabled(Level::Debug));
+    ehttpd::log_info!("Testolope {}", 7);
+
+    // Test disabled logging
+    log::set_level(Level::Off);
+    assert!(!log::enabled(Level::Error));
+    assert!(!log::enabled(Level::Off));
+}
+
+/// Tests the nesting of logging contexts
+#[test]
+fn context() {
+    assert_eq!(log::context(), "");
+    {
+        let _peer = log::enter_context("peer=127.0.0.1:4242");
+        assert_eq!(log::context(), "peer=127.0.0.1:4242");
+        {
+            let _request = log::enter_context(format_args!("request={}", 7));
+            assert_eq!(log::context(), "peer=127.0.0.1:4242 request=7");
+        }
+        assert_eq!(log::context(), "peer=127.0.0.1:4242");
+    }
+    assert_eq!(log::context(), "");
+}
@@ -0,0 +1,602 @@
+use ehttpd::{
+    bytes::{Sink, Source},
+    http::{Body, HeaderLimits, Request, Response, ResponseExt},
+    limits::PeerLimit,
+    tags::TagPolicy,
+    threadpool::{Backpressure, Shedding},
+    ConnectionInfo, Server,
+};
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// The server type
+type TestServer = Server<fn(&mut Source, &mut Sink) -> bool>;
+
+/// The default connection handler
+fn handler(source: &mut Source, sink: &mut Sink) -> bool {
+    ehttpd::reqresp(source, sink, |_: Request| {
+        let mut response = Response::new_200_ok();
+        response.set_body_data(b"Testolope");
+        response.set_connection_close();
+        response
+    })
+}
+
+/// Creates a new server
+fn server(worker_max: usize) -> TestServer {
+    Server::new(worker_max, handler)
+}
+
+/// Binds a listener on a random local port
+fn listener() -> (TcpListener, SocketAddr) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let address = listener.local_addr().expect("failed to get listener address");
+    (listener, address)
+}
+
+/// Starts a server with the given configuration on a random local port
+fn start<F>(worker_max: usize, configure: F) -> SocketAddr
+where
+    F: FnOnce(&mut TestServer),
+{
+    // Create the server
+    let mut server = server(worker_max);
+    configure(&mut server);
+
+    // Start the server
+    let (listener, address) = listener();
+    thread::spawn(move || server.accept_listener(listener));
+    address
+}
+
+/// Performs a request and returns the raw response
+fn request(address: SocketAddr) -> String {
+    let mut stream = TcpStream::connect(address).expect("failed to connect to server");
+    stream.write_all(b"GET /testolope HTTP/1.1\r\nHost: localhost\r\n\r\n").expect("failed to write request");
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).expect("failed to read response");
+    response
+}
+
+/// Tests a simple request
+#[test]
+fn ok() {
+    let address = start(16, |_| ());
+    let response = request(address);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\nTestolope"));
+}
+
+/// Tests a server with pre-spawned warm workers
+#[test]
+fn worker_min() {
+    let address = start(16, |server| server.set_worker_min(4).expect("failed to spawn workers"));
+    let response = request(address);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+}
+
+/// Tests the overload fallback
+#[test]
+fn overload() {
+    // Occupy the single worker and the single queue slot with idle connections
+    let address = start(1, |server| server.set_overload_fallback(7));
+    let _busy = TcpStream::connect(address).expect("failed to connect to server");
+    thread::sleep(Duration::from_millis(100));
+    let _queued = TcpStream::connect(address).expect("failed to connect to server");
+    thread::sleep(Duration::from_millis(100));
+
+    // Perform the request
+    let response = request(address);
+    assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    assert!(response.contains("\r\nRetry-After: 7\r\n"));
+}
+
+/// Tests the per-peer connection limit
+#[test]
+fn peer_limit() {
+    // Occupy the single peer slot with an idle connection
+    let address = start(16, |server| server.set_peer_limit(1));
+    let held = TcpStream::connect(address).expect("failed to connect to server");
+    thread::sleep(Duration::from_millis(100));
+
+    // Perform the request
+    let response = request(address);
+    assert!(response.starts_with("HTTP/1.1 429 Too Many Requests\r\n"), "{response}");
+
+    // Release the slot and retry
+    drop(held);
+    thread::sleep(Duration::from_millis(100));
+    let response = request(address);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+}
+
+/// Tests the blocking backpressure strategy
+#[test]
+fn backpressure() {
+    let address = start(1, |server| server.set_backpressure(Backpressure::Block(Duration::from_secs(4))));
+    let responses: Vec<_> = (0..4).map(|_| thread::spawn(move || request(address))).collect();
+    for response in responses {
+        let response = response.join().expect("request thread panicked");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+}
+
+/// Tests that connections that are dropped from the queue are answered
+#[test]
+fn backpressure_drop_oldest() {
+    // Occupy the single worker and the single queue slot with idle connections
+    let address = start(1, |server| {
+        server.set_backpressure(Backpressure::DropOldest);
+        server.set_overload_fallback(7);
+    });
+    let _busy = TcpStream::connect(address).expect("failed to connect to server");
+    thread::sleep(Duration::from_millis(100));
+    let mut queued = TcpStream::connect(address).expect("failed to connect to server");
+    thread::sleep(Duration::from_millis(100));
+
+    // Evict the queued connection
+    let _evicting = TcpStream::connect(address).expect("failed to connect to server");
+    let mut response = String::new();
+    queued.read_to_string(&mut response).expect("failed to read response");
+    assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"), "{response}");
+    assert!(response.contains("\r\nRetry-After: 7\r\n"));
+}
+
+/// Tests a server with multiple listeners
+#[test]
+fn listeners() {
+    let ((listener_a, address_a), (listener_b, address_b)) = (listener(), listener());
+    let server = server(16);
+    thread::spawn(move || server.accept_listeners([listener_a, listener_b]));
+
+    // Request both listeners
+    for address in [address_a, address_b] {
+        let response = request(address);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+}
+
+/// Tests a server with a dedicated handler per listener
+#[test]
+fn listener_handlers() {
+    /// The metrics handler
+    fn metrics(source: &mut Source, sink: &mut Sink) -> bool {
+        ehttpd::reqresp(source, sink, |_: Request| {
+            let mut response = Response::new_200_ok();
+            response.set_body_data(b"metrics");
+            response.set_connection_close();
+            response
+        })
+    }
+
+    // Start the server with the default handler on the first and the metrics handler on the second listener
+    let ((public, public_address), (internal, internal_address)) = (listener(), listener());
+    let server = server(16);
+    let handlers: [fn(&mut Source, &mut Sink) -> bool; 2] = [handler, metrics];
+    thread::spawn(move || server.accept_listeners_with([(public, handlers[0]), (internal, handlers[1])]));
+
+    // Request both listeners
+    assert!(request(public_address).ends_with("\r\n\r\nTestolope"));
+    assert!(request(internal_address).ends_with("\r\n\r\nmetrics"));
+}
+
+/// Tests that the peer address is passed to the handler
+#[test]
+fn peer() {
+    /// The connection handler
+    fn handler(source: &mut Source, sink: &mut Sink) -> bool {
+        ehttpd::reqresp(source, sink, |request: Request| {
+            let peer = request.peer.map(|peer| peer.to_string()).unwrap_or_default();
+            let mut response = Response::new_200_ok();
+            response.set_body_data(peer);
+            response.set_connection_close();
+            response
+        })
+    }
+
+    // Start the server
+    let server: TestServer = Server::new(16, handler);
+    let (listener, address) = listener();
+    thread::spawn(move || server.accept_listener(listener));
+
+    // Perform the request
+    let mut stream = TcpStream::connect(address).expect("failed to connect to server");
+    let local = stream.local_addr().expect("failed to get local address");
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").expect("failed to write request");
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).expect("failed to read response");
+    assert!(response.ends_with(&format!("\r\n\r\n{local}")));
+}
+
+/// Tests that a panicking handler is reported and answered with a `500 Internal Server Error`
+#[test]
+fn panic_response() {
+    /// The connection handler
+    fn handler(source: &mut Source, sink: &mut Sink) -> bool {
+        ehttpd::reqresp(source, sink, |_: Request| -> Response { panic!("Testolope") })
+    }
+
+    // Start the server
+    let (panics_tx, panics_rx) = std::sync::mpsc::channel();
+    let mut server: TestServer = Server::new(16, handler);
+    server.set_panic_response(true);
+    server.on_panic(move |payload, _| {
+        let message = ehttpd::threadpool::panic_message(payload).to_string();
+        let _ = panics_tx.send(message);
+    });
+    let (listener, address) = listener();
+    thread::spawn(move || server.accept_listener(listener));
+
+    // Perform the request
+    let response = request(address);
+    assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+    assert_eq!(panics_rx.recv_timeout(Duration::from_secs(4)).expect("panic was not reported"), "Testolope");
+}
+
+/// Tests the connection error callback for malformed requests and failed response writes
+#[test]
+fn connection_error() {
+    /// The connection handler
+    fn handler(source: &mut Source, sink: &mut Sink) -> bool {
+        ehttpd::reqresp(source, sink, |_: Request| {
+            // Fail while streaming the body
+            let failing = Source::from_fn(|_| Err(std::io::Error::other("Testolope")));
+            let mut response = Response::new_200_ok();
+            response.set_body(Body::new(failing, None));
+            response
+        })
+    }
+
+    // Start the server
+    let (errors_tx, errors_rx) = std::sync::mpsc::channel();
+    let mut server: TestServer = Server::new(16, handler);
+    server.on_connection_error(move |error, info| {
+        let _ = errors_tx.send((error.to_string(), info.peer));
+    });
+    let (listener, address) = listener();
+    thread::spawn(move || server.accept_listener(listener));
+
+    // Send a malformed request
+    let mut stream = TcpStream::connect(address).expect("failed to connect to server");
+    stream.write_all(b"GET / HTTP/0.9\r\n\r\n").expect("failed to write request");
+    let (error, peer) = errors_rx.recv_timeout(Duration::from_secs(4)).expect("read error was not reported");
+    assert!(error.starts_with("Unsupported HTTP version"), "{error}");
+    assert_eq!(peer, Some(stream.local_addr().expect("failed to get local address")));
+
+    // Fail to write the response
+    let mut stream = TcpStream::connect(address).expect("failed to connect to server");
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").expect("failed to write request");
+    let (error, peer) = errors_rx.recv_timeout(Duration::from_secs(4)).expect("write error was not reported");
+    assert!(error.contains("Testolope"), "{error}");
+    assert_eq!(peer, Some(stream.local_addr().expect("failed to get local address")));
+}
+
+/// Tests the connection tagging and the per-tag policies
+#[test]
+fn tags() {
+    /// The connection handler
+    fn handler(source: &mut Source, sink: &mut Sink) -> bool {
+        ehttpd::reqresp(source, sink, |_: Request| {
+            let tag = ConnectionInfo::current().and_then(|info| info.tag).unwrap_or_default();
+            assert_eq!(
+                ConnectionInfo::with_current(|info| info.tag.as_deref().unwrap_or_default() == &*tag),
+                Some(true)
+            );
+            let mut response = Response::new_200_ok();
+            response.set_body_data(tag.to_string());
+            response.set_connection_close();
+            response
+        })
+    }
+
+    // Tag the connections by listener
+    let ((admin, admin_address), (blocked, blocked_address)) = (listener(), listener());
+    let mut server: TestServer = Server::new(16, handler);
+    server.set_tagger(move |listener, _| match listener == admin_address {
+        true => Some(Arc::from("admin")),
+        false => Some(Arc::from("blocked")),
+    });
+
+    // Block all connections with the `blocked` tag
+    let mut policy = TagPolicy::default();
+    policy.peer_limit = Some(PeerLimit::new(0));
+    server.set_tag_policy("blocked", policy);
+    thread::spawn(move || server.accept_listeners([admin, blocked]));
+
+    // Perform the requests
+    assert!(request(admin_address).ends_with("\r\n\r\nadmin"));
+    assert!(request(blocked_address).starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+}
+
+/// Tests the runtime control commands
+#[test]
+fn control() {
+    let mut server = server(16);
+    server.set_overload_fallback(7);
+    let control = server.control();
+    let (listener, address) = listener();
+    thread::spawn(move || server.accept_listener(listener));
+
+    // Test the commands
+    assert!(control.execute("stats").starts_with("ok workers="));
+    assert_eq!(control.execute("level nope"), "error invalid level: nope");
+    assert_eq!(control.execute("nope"), "error invalid command: nope");
+
+    // Toggle the maintenance mode
+    assert_eq!(control.execute("maintenance on"), "ok");
+    let response = request(address);
+    assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    assert!(response.contains("\r\nRetry-After: 7\r\n"));
+    assert_eq!(control.execute("maintenance off"), "ok");
+    assert!(request(address).starts_with("HTTP/1.1 200 OK\r\n"));
+}
+
+/// Tests the connection draining with a keep-alive straggler
+#[test]
+fn drain() {
+    /// The connection handler
+    fn handler(source: &mut Source, sink: &mut Sink) -> bool {
+        ehttpd::reqresp(source, sink, |_: Request| {
+            let mut response = Response::new_200_ok();
+            response.set_body_data(b"Testolope");
+            response
+        })
+    }
+
+    // Start the server
+    let server: TestServer = Server::new(16, handler);
+    let (connections, control) = (server.connections(), server.control());
+    let (listener, address) = listener();
+    thread::spawn(move || server.accept_listener(listener));
+
+    // Perform a keep-alive request
+    let mut stream = TcpStream::connect(address).expect("failed to connect to server");
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").expect("failed to write request");
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\nTestolope") {
+        let mut buf = [0; 256];
+        let len = stream.read(&mut buf).expect("failed to read response");
+        assert_ne!(len, 0, "unexpected end of stream");
+        response.extend_from_slice(&buf[..len]);
+    }
+
+    // The connection becomes idle after the response has been written, but remains active
+    let mut snapshot = connections.snapshot();
+    while snapshot.iter().any(|connection| connection.request.is_some()) {
+        thread::sleep(Duration::from_millis(10));
+        snapshot = connections.snapshot();
+    }
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(snapshot[0].peer, Some(stream.local_addr().expect("failed to get local address")));
+    assert!(snapshot[0].request.is_none());
+    assert!(control.execute("connections").starts_with("ok active=1 in_flight=0 oldest="));
+
+    // Perform another request while draining, which is answered before its connection is closed
+    let late = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        request(address)
+    });
+
+    // Drain the server and abort the straggler
+    let report = connections.drain(Duration::from_millis(500));
+    assert_eq!((report.drained, report.aborted, report.completed), (1, 1, 1));
+    assert!(report.elapsed >= Duration::from_millis(500) && !report.is_clean());
+    assert_eq!(stream.read(&mut [0; 256]).expect("failed to read from stream"), 0);
+    assert!(late.join().expect("request thread panicked").starts_with("HTTP/1.1 200 OK\r\n"));
+    while !connections.is_empty() {
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Tests that pipelined requests are answered even if the client has half-closed the connection
+#[test]
+fn pipelined_half_close() {
+    /// The connection handler
+    fn handler(source: &mut Source, sink: &mut Sink) -> bool {
+        ehttpd::reqresp(source, sink, |request: Request| {
+            let mut response = Response::new_200_ok();
+            response.set_body_data(request.target.clone());
+            response
+        })
+    }
+
+    // Start the server
+    let server: TestServer = Server::new(16, handler);
+    let (listener, address) = listener();
+    thread::spawn(move || server.accept_listener(listener));
+
+    // Send the pipelined requests and half-close the connection
+    let mut stream = TcpStream::connect(address).expect("failed to connect to server");
+    stream.write_all(b"GET /first HTTP/1.1\r\n\r\nGET /second HTTP/1.1\r\n\r\n").expect("failed to write requests");
+    stream.shutdown(std::net::Shutdown::Write).expect("failed to half-close stream");
+
+    // Both requests are answered
+    let mut response = String::new();
+    stream.read_to_string(&mut response).expect("failed to read responses");
+    assert!(response.contains("\r\n\r\n/first"), "{response}");
+    assert!(response.ends_with("\r\n\r\n/second"), "{response}");
+}
+
+/// Tests aborting a live connection whose handler has replaced its sink
+#[test]
+fn abort_all() {
+    /// The connection handler
+    fn handler(source: &mut Source, sink: &mut Sink) -> bool {
+        // Answer the request and drop the original stream afterwards
+        let alive = ehttpd::reqresp(source, sink, |_: Request| {
+            let mut response = Response::new_200_ok();
+            response.set_body_data(b"Testolope");
+            response
+        });
+        let _ = std::mem::replace(sink, Sink::from(Vec::new()));
+        alive
+    }
+
+    // Start the server
+    let server: TestServer = Server::new(16, handler);
+    let connections = server.connections();
+    let (listener, address) = listener();
+    thread::spawn(move || server.accept_listener(listener));
+
+    // Perform a keep-alive request
+    let mut stream = TcpStream::connect(address).expect("failed to connect to server");
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").expect("failed to write request");
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\nTestolope") {
+        let mut buf = [0; 256];
+        let len = stream.read(&mut buf).expect("failed to read response");
+        assert_ne!(len, 0, "unexpected end of stream");
+        response.extend_from_slice(&buf[..len]);
+    }
+
+    // Wait until the handler has replaced the sink and is waiting for the next request
+    while connections.snapshot().iter().any(|connection| connection.request.is_some()) {
+        thread::sleep(Duration::from_millis(10));
+    }
+    thread::sleep(Duration::from_millis(50));
+
+    // Abort the connection without draining
+    assert_eq!(connections.abort_all(), 1);
+    assert_eq!(stream.read(&mut [0; 256]).expect("failed to read from stream"), 0);
+    while !connections.is_empty() {
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Tests the control socket
+#[test]
+#[cfg(target_family = "unix")]
+fn control_socket() {
+    use std::{
+        io::{BufRead, BufReader},
+        os::unix::{fs::PermissionsExt, net::UnixStream},
+    };
+
+    // Start the control socket
+    let path = std::env::temp_dir().join(format!("ehttpd-control-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let control = server(16).control();
+    thread::spawn({
+        let path = path.clone();
+        move || control.serve(path)
+    });
+
+    // Connect to the socket
+    let stream = loop {
+        match UnixStream::connect(&path) {
+            Ok(stream) => break stream,
+            Err(_) => thread::sleep(Duration::from_millis(10)),
+        }
+    };
+
+    // Execute a command
+    let mut tx = stream.try_clone().expect("failed to clone stream");
+    tx.write_all(b"drain\n").expect("failed to write command");
+    let mut response = String::new();
+    let mut rx = BufReader::new(stream);
+    rx.read_line(&mut response).expect("failed to read response");
+    assert_eq!(response, "ok\n");
+
+    // The socket is restricted to the owner
+    let mode = std::fs::metadata(&path).expect("failed to stat socket").permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    // Overlong commands are rejected
+    tx.write_all(format!("{}\n", "x".repeat(512)).as_bytes()).expect("failed to write command");
+    response.clear();
+    rx.read_line(&mut response).expect("failed to read response");
+    assert_eq!(response, "error command is too long\n");
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Tests the single-threaded mode
+#[test]
+fn single_threaded() {
+    /// Answers with the name of the handling thread
+    fn thread_name(source: &mut Source, sink: &mut Sink) -> bool {
+        ehttpd::reqresp(source, sink, |_: Request| {
+            let mut response = Response::new_200_ok();
+            response.set_body_data(thread::current().name().unwrap_or_default().to_string());
+            response.set_connection_close();
+            response
+        })
+    }
+
+    // Start the server on a free port
+    let (listener, address) = listener();
+    drop(listener);
+    let server: TestServer = Server::new(16, thread_name);
+    let builder = thread::Builder::new().name("single-threaded".to_string());
+    builder.spawn(move || server.serve_single_threaded(address)).expect("failed to spawn server thread");
+    thread::sleep(Duration::from_millis(100));
+
+    // Perform some requests
+    for _ in 0..3 {
+        let response = request(address);
+        assert!(response.ends_with("\r\n\r\nsingle-threaded"), "{response}");
+    }
+}
+
+/// Tests the shedding of connections that have waited too long in the queue
+#[test]
+fn backlog_shedding() {
+    // Occupy the single worker with an idle connection
+    let address = start(1, |server| {
+        server.set_overload_fallback(3);
+        server.set_backlog_shedding(Duration::from_millis(100), Shedding::Unavailable);
+    });
+    let busy = TcpStream::connect(address).expect("failed to connect to server");
+    thread::sleep(Duration::from_millis(100));
+
+    // Queue a request and release the worker after the queue age limit
+    let queued = thread::spawn(move || request(address));
+    thread::sleep(Duration::from_millis(300));
+    drop(busy);
+
+    // Validate the response
+    let response = queued.join().expect("request thread panicked");
+    assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"), "{response}");
+    assert!(response.contains("\r\nRetry-After: 3\r\n"));
+
+    // Fresh connections are handled normally
+    let response = request(address);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+}
+
+/// Tests the request header deadline
+#[test]
+fn header_timeout() {
+    // Start a server with a single worker and a short header deadline
+    let address = start(1, |server| {
+        let mut limits = HeaderLimits::default();
+        limits.timeout = Some(Duration::from_millis(200));
+        server.set_header_limits(limits);
+    });
+
+    // A trickling client that does not complete the header is answered with `408 Request Timeout`
+    let mut stream = TcpStream::connect(address).expect("failed to connect to server");
+    for byte in b"GET /" {
+        stream.write_all(&[*byte]).expect("failed to write request");
+        thread::sleep(Duration::from_millis(20));
+    }
+    let mut response = String::new();
+    stream.read_to_string(&mut response).expect("failed to read response");
+    assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"), "{response}");
+
+    // An idle connection is closed silently
+    let mut stream = TcpStream::connect(address).expect("failed to connect to server");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).expect("failed to read response");
+    assert!(response.is_empty(), "{response}");
+
+    // The worker is available for fresh connections
+    let response = request(address);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+}
@@ -0,0 +1,46 @@
+use ehttpd::socket::{ListenerOptions, SocketOptions};
+use std::{
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+/// Tests applying socket options
+#[test]
+fn socket_options() {
+    // Create a connected socket
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+    let stream = TcpStream::connect(listener.local_addr().expect("failed to get address")).expect("failed to connect");
+
+    // Apply the options
+    let mut options = SocketOptions::default();
+    options.nodelay = Some(true);
+    options.keepalive = Some(Duration::from_secs(60));
+    options.keepalive_interval = Some(Duration::from_secs(10));
+    options.linger = Some(None);
+    options.apply(&stream).expect("failed to apply socket options");
+    assert!(stream.nodelay().expect("failed to get nodelay"));
+}
+
+/// Tests applying listener options
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn listener_options() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+
+    let mut options = ListenerOptions::default();
+    options.fastopen = Some(16);
+    options.defer_accept = Some(Duration::from_millis(1500));
+    options.apply(&listener).expect("failed to apply listener options");
+}
+
+/// Tests that the socket activation environment is consumed once
+#[test]
+#[cfg(target_family = "unix")]
+fn listeners_from_env() {
+    std::env::set_var("LISTEN_PID", std::process::id().to_string());
+    std::env::set_var("LISTEN_FDS", "0");
+
+    let listeners = ehttpd::socket::listeners_from_env().expect("failed to take over listeners");
+    assert!(listeners.is_empty());
+    assert!(std::env::var("LISTEN_PID").is_err() && std::env::var("LISTEN_FDS").is_err());
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "testing")]
+
+use ehttpd::{bytes::Source, http::Request, testing::arbitrary::Generator};
+
+/// Tests the parser against generated valid and invalid requests
+#[test]
+fn parser() {
+    let mut generator = Generator::new(7);
+    for _ in 0..4096 {
+        let request = generator.request();
+        let mut source = Source::from(request.raw.clone());
+        match (Request::<4096>::from_stream(&mut source), request.valid) {
+            (Ok(Some(parsed)), true) => {
+                // Validate the parsed components
+                assert_eq!(parsed.method, request.method.as_slice());
+                assert_eq!(parsed.target, request.target.as_slice());
+                let fields: Vec<_> = (parsed.fields.iter())
+                    .map(|(key, value)| (key.as_ref().to_vec(), value.as_ref().to_vec()))
+                    .collect();
+                assert_eq!(fields, request.fields);
+            }
+            (Err(_), false) => (),
+            (result, valid) => panic!("unexpected result for {request:?} (valid: {valid}): {result:?}"),
+        }
+    }
+}
+
+/// Tests that the generator is deterministic
+#[test]
+fn deterministic() {
+    let (mut a, mut b) = (Generator::new(4), Generator::new(4));
+    for _ in 0..64 {
+        assert_eq!(a.request(), b.request());
+    }
+}
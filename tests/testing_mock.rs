@@ -0,0 +1,69 @@
+#![cfg(feature = "testing")]
+
+use ehttpd::{
+    http::{Response, ResponseExt},
+    testing::mock::MockUpstream,
+};
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+/// Reads a response with the given length from the stream
+fn read_response(stream: &mut TcpStream, len: usize) -> String {
+    let mut response = vec![0; len];
+    stream.read_exact(&mut response).expect("failed to read response");
+    String::from_utf8(response).expect("response is not valid UTF-8")
+}
+
+/// Tests the scripted responses on a keep-alive connection
+#[test]
+fn respond() {
+    // Script the responses
+    let upstream = MockUpstream::start().expect("failed to start mock upstream");
+    let mut response = Response::new_200_ok();
+    response.set_body_data(b"Testolope");
+    upstream.respond(response);
+    upstream.respond_after(Duration::from_millis(100), Response::new_404_notfound());
+
+    // Perform the requests
+    let mut stream = TcpStream::connect(upstream.address()).expect("failed to connect to upstream");
+    stream.write_all(b"POST /first HTTP/1.1\r\nContent-Length: 4\r\n\r\nbody").expect("failed to write request");
+    assert_eq!(read_response(&mut stream, 47), "HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nTestolope");
+
+    let start = Instant::now();
+    stream.write_all(b"GET /second HTTP/1.1\r\n\r\n").expect("failed to write request");
+    assert_eq!(read_response(&mut stream, 45), "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    // Exhaust the script
+    stream.write_all(b"GET /third HTTP/1.1\r\n\r\n").expect("failed to write request");
+    assert!(read_response(&mut stream, 57).starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+    assert_eq!((upstream.pending(), upstream.unexpected()), (0, 1));
+
+    // Validate the received requests
+    let requests = upstream.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0].header, b"POST /first HTTP/1.1\r\nContent-Length: 4\r\n\r\n");
+    assert_eq!(requests[0].body, b"body");
+}
+
+/// Tests the scripted connection reset and close
+#[test]
+fn reset_close() {
+    let upstream = MockUpstream::start().expect("failed to start mock upstream");
+    upstream.reset();
+    upstream.close();
+
+    // Test the reset
+    let mut stream = TcpStream::connect(upstream.address()).expect("failed to connect to upstream");
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").expect("failed to write request");
+    let error = stream.read(&mut [0; 16]).expect_err("connection was not reset");
+    assert_eq!(error.kind(), ErrorKind::ConnectionReset);
+
+    // Test the close
+    let mut stream = TcpStream::connect(upstream.address()).expect("failed to connect to upstream");
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").expect("failed to write request");
+    assert_eq!(stream.read(&mut [0; 16]).expect("failed to read from stream"), 0);
+}
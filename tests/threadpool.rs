@@ -0,0 +1,236 @@
+use ehttpd::threadpool::{Backpressure, Executable, PanicPolicy, Priority, Threadpool};
+use std::{sync::mpsc, time::Duration};
+
+/// A job that signals its execution
+struct Job(mpsc::Sender<()>);
+impl Executable for Job {
+    fn exec(self) {
+        let _ = self.0.send(());
+    }
+}
+
+/// Tests the threadpool statistics
+#[test]
+fn stats() {
+    // Create the threadpool with warm workers
+    let threadpool: Threadpool<Job, 65_536> = Threadpool::new(4);
+    threadpool.set_worker_min(2).expect("failed to spawn workers");
+    assert_eq!(threadpool.stats().workers, 2);
+    assert_eq!(threadpool.stats().capacity, 4);
+
+    // Execute some jobs
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..3 {
+        threadpool.dispatch(Job(tx.clone())).expect("failed to dispatch job");
+        rx.recv_timeout(Duration::from_secs(4)).expect("job was not executed");
+    }
+
+    // The execution counter is incremented after the job has returned
+    for _ in 0..100 {
+        if threadpool.stats().executed == 3 {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("executed jobs were not counted");
+}
+
+/// Tests that all jobs are executed if the queue is filled up to its capacity
+#[test]
+fn full_queue() {
+    let threadpool: Threadpool<Job, 65_536> = Threadpool::new(8);
+
+    // Dispatch jobs into all queue shards
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..8 {
+        threadpool.dispatch(Job(tx.clone())).expect("failed to dispatch job");
+    }
+    for _ in 0..8 {
+        rx.recv_timeout(Duration::from_secs(4)).expect("job was not executed");
+    }
+}
+
+/// A job that reports its name and dispatches its children into the pool
+struct Named(&'static str, Vec<(Named, Priority, Backpressure)>, Threadpool<Named, 65_536>, mpsc::Sender<&'static str>);
+impl Named {
+    /// Creates a new job without children
+    fn new(name: &'static str, threadpool: &Threadpool<Named, 65_536>, tx: &mpsc::Sender<&'static str>) -> Self {
+        Self(name, Vec::new(), threadpool.clone(), tx.clone())
+    }
+}
+impl Executable for Named {
+    fn exec(self) {
+        let _ = self.3.send(self.0);
+        for (child, priority, backpressure) in self.1 {
+            let _ = self.2.dispatch_with_backpressure(child, priority, backpressure);
+        }
+    }
+}
+
+/// Executes a job with the given children in an inline pool and returns the execution order
+fn execute_named<F>(queue_max: usize, children: F) -> Vec<&'static str>
+where
+    F: FnOnce(&Threadpool<Named, 65_536>, &mpsc::Sender<&'static str>) -> Vec<(Named, Priority, Backpressure)>,
+{
+    let threadpool = Threadpool::new_inline(queue_max);
+    let (tx, rx) = mpsc::channel();
+    let mut job = Named::new("outer", &threadpool, &tx);
+    job.1 = children(&threadpool, &tx);
+    threadpool.dispatch(job).expect("failed to dispatch job");
+    rx.try_iter().collect()
+}
+
+/// Tests that jobs with a higher priority are executed first
+#[test]
+fn priority() {
+    let order = execute_named(4, |threadpool, tx| {
+        vec![
+            (Named::new("low", threadpool, tx), Priority::Low, Backpressure::Reject),
+            (Named::new("high", threadpool, tx), Priority::High, Backpressure::Reject),
+        ]
+    });
+    assert_eq!(order, ["outer", "high", "low"]);
+}
+
+/// Tests that a steady stream of higher-priority jobs does not starve lower-priority jobs
+#[test]
+fn priority_starvation() {
+    let order = execute_named(16, |threadpool, tx| {
+        let low = (Named::new("low", threadpool, tx), Priority::Low, Backpressure::Reject);
+        let high = (0..9).map(|_| (Named::new("high", threadpool, tx), Priority::High, Backpressure::Reject));
+        [low].into_iter().chain(high).collect()
+    });
+    assert_eq!(order.len(), 11);
+    assert_eq!(order.iter().position(|name| *name == "low"), Some(7));
+}
+
+/// Tests that all priorities share the same capacity, and that dropping the oldest job prefers lower priorities
+#[test]
+fn priority_capacity() {
+    // All priorities share the capacity
+    let order = execute_named(2, |threadpool, tx| {
+        vec![
+            (Named::new("low", threadpool, tx), Priority::Low, Backpressure::Reject),
+            (Named::new("normal", threadpool, tx), Priority::Normal, Backpressure::Reject),
+            (Named::new("high", threadpool, tx), Priority::High, Backpressure::Reject),
+        ]
+    });
+    assert_eq!(order, ["outer", "normal", "low"]);
+
+    // The lowest priority is dropped first
+    let order = execute_named(2, |threadpool, tx| {
+        vec![
+            (Named::new("low", threadpool, tx), Priority::Low, Backpressure::Reject),
+            (Named::new("normal", threadpool, tx), Priority::Normal, Backpressure::Reject),
+            (Named::new("high", threadpool, tx), Priority::High, Backpressure::DropOldest),
+        ]
+    });
+    assert_eq!(order, ["outer", "high", "normal"]);
+
+    // Higher priorities are never dropped for lower priorities
+    let order = execute_named(2, |threadpool, tx| {
+        vec![
+            (Named::new("high", threadpool, tx), Priority::High, Backpressure::Reject),
+            (Named::new("normal", threadpool, tx), Priority::Normal, Backpressure::Reject),
+            (Named::new("low", threadpool, tx), Priority::Low, Backpressure::DropOldest),
+        ]
+    });
+    assert_eq!(order, ["outer", "high", "normal"]);
+}
+
+/// Tests that panicking jobs are reported and counted, and that the worker survives
+#[test]
+fn panic_hook() {
+    /// A job that panics
+    struct Panicking;
+    impl Executable for Panicking {
+        fn exec(self) {
+            panic!("Testolope");
+        }
+    }
+
+    // Create the threadpool with a single worker
+    let (tx, rx) = mpsc::channel();
+    let threadpool: Threadpool<Panicking, 65_536> = Threadpool::new(1);
+    threadpool.set_panic_hook(move |payload| {
+        let message = ehttpd::threadpool::panic_message(payload).to_string();
+        let _ = tx.send(message);
+    });
+
+    // Execute the panicking jobs
+    for _ in 0..2 {
+        threadpool.dispatch(Panicking).expect("failed to dispatch job");
+        assert_eq!(rx.recv_timeout(Duration::from_secs(4)).expect("panic was not reported"), "Testolope");
+    }
+    assert_eq!(threadpool.stats().panicked, 2);
+    assert_eq!(threadpool.stats().executed, 0);
+}
+
+/// Tests that panicking jobs terminate their worker if panics are propagated, and that the threadpool recovers
+#[test]
+fn panic_propagate() {
+    /// A job that optionally panics before it signals its execution
+    struct MaybePanicking(bool, mpsc::Sender<()>);
+    impl Executable for MaybePanicking {
+        fn exec(self) {
+            let _ = self.1.send(());
+            assert!(!self.0, "Testolope");
+        }
+    }
+
+    // Create the threadpool with a single worker
+    let threadpool: Threadpool<MaybePanicking, 65_536> = Threadpool::new(1);
+    threadpool.set_panic_policy(PanicPolicy::Propagate);
+    assert_eq!(threadpool.panic_policy(), PanicPolicy::Propagate);
+
+    // Execute the panicking job and wait until the worker has terminated
+    let (tx, rx) = mpsc::channel();
+    threadpool.dispatch(MaybePanicking(true, tx.clone())).expect("failed to dispatch job");
+    rx.recv_timeout(Duration::from_secs(4)).expect("job was not executed");
+    while threadpool.stats().workers > 0 {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // A new worker is spawned for the next job
+    threadpool.dispatch(MaybePanicking(false, tx)).expect("failed to dispatch job");
+    rx.recv_timeout(Duration::from_secs(4)).expect("job was not executed");
+}
+
+/// Tests that a pool with zero workers is clamped to a single worker
+#[test]
+fn zero_workers() {
+    let threadpool: Threadpool<Job, 65_536> = Threadpool::new(0);
+    assert_eq!(threadpool.stats().capacity, 1);
+
+    // Execute some jobs one at a time
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..3 {
+        threadpool.dispatch(Job(tx.clone())).expect("failed to dispatch job");
+        rx.recv_timeout(Duration::from_secs(4)).expect("job was not executed");
+    }
+}
+
+/// Tests the inline execution in the caller's thread
+#[test]
+fn inline() {
+    /// A job that records its thread and dispatches the remaining jobs into the pool
+    struct Nested(usize, Threadpool<Nested, 65_536>, mpsc::Sender<(usize, std::thread::ThreadId)>);
+    impl Executable for Nested {
+        fn exec(self) {
+            let _ = self.2.send((self.0, std::thread::current().id()));
+            if self.0 > 0 {
+                let next = Nested(self.0 - 1, self.1.clone(), self.2.clone());
+                self.1.dispatch(next).expect("failed to dispatch nested job");
+            }
+        }
+    }
+
+    // Execute a chain of nested jobs
+    let threadpool = Threadpool::new_inline(1);
+    let (tx, rx) = mpsc::channel();
+    threadpool.dispatch(Nested(3, threadpool.clone(), tx)).expect("failed to dispatch job");
+    let executed: Vec<_> = rx.try_iter().collect();
+    let current = std::thread::current().id();
+    assert_eq!(executed, [(3, current), (2, current), (1, current), (0, current)]);
+    assert_eq!((threadpool.stats().workers, threadpool.stats().executed), (0, 4));
+}
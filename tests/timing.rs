@@ -0,0 +1,71 @@
+use ehttpd::{
+    bytes::{Sink, Source},
+    http::{benchmark, server_timing, Request, Response, ResponseExt},
+    timing::{self, PhaseTimings},
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Tests the benchmark handler and the timing hook
+#[test]
+fn benchmark_timings() {
+    // Install the hook
+    let timings = Arc::new(Mutex::new(Vec::<PhaseTimings>::new()));
+    let timings_ = timings.clone();
+    timing::set_hook(move |timings| timings_.lock().expect("failed to lock timings").push(*timings));
+
+    // Perform a request
+    let mut source = Source::from(b"GET /?size=4 HTTP/1.1\r\n\r\n");
+    let mut sink = Sink::Vector(Vec::new());
+    let _ = ehttpd::reqresp(&mut source, &mut sink, benchmark(16));
+    timing::clear_hook();
+
+    // Validate the response and the timings
+    let Sink::Vector(response) = sink else { unreachable!("sink is not a vector") };
+    assert!(response.ends_with(b"\r\nContent-Length: 4\r\n\r\nxxxx"));
+
+    let timings = timings.lock().expect("failed to lock timings");
+    assert_eq!(timings.len(), 1);
+    assert_eq!(timings[0].queue, None);
+    assert!(timings[0].first_byte.is_some() && timings[0].parse.is_some());
+    assert!(timings[0].handler.is_some() && timings[0].write.is_some());
+}
+
+/// Tests the `Server-Timing` formatting
+#[test]
+fn to_server_timing() {
+    let mut timings = PhaseTimings::default();
+    timings.queue = Some(Duration::from_micros(12));
+    timings.handler = Some(Duration::from_millis(7));
+    assert_eq!(timings.to_server_timing(), "queue;dur=0.012, handler;dur=7.000");
+}
+
+/// Tests the `Server-Timing` middleware
+#[test]
+fn server_timing_middleware() {
+    let handler = server_timing(|_: Request| {
+        timing::record_metric("db", Duration::from_millis(3), Some("Query \"users\""))
+            .expect("failed to record metric");
+        assert!(timing::record_metric("db query", Duration::ZERO, None).is_err());
+        assert!(timing::record_metric("db", Duration::ZERO, Some("line\r\nX-Injected: 1")).is_err());
+        let mut response = Response::new_200_ok();
+        response.set_field("Server-Timing", "cache;desc=hit");
+        response
+    });
+
+    // Perform a request
+    let mut source = Source::from(b"GET / HTTP/1.1\r\n\r\n");
+    let request =
+        Request::from_stream(&mut source).expect("failed to parse request").expect("unexpected end of stream");
+    let response: Response = handler(request);
+
+    // Validate the header
+    let (_, server_timing) =
+        (response.fields.iter()).find(|(key, _)| key.eq(b"Server-Timing")).expect("missing server timing field");
+    let server_timing = String::from_utf8_lossy(server_timing);
+    assert!(server_timing.starts_with("first-byte;dur="), "{server_timing}");
+    assert!(server_timing.contains(", handler;dur="));
+    assert!(server_timing.ends_with(r#", db;dur=3.000;desc="Query \"users\"", cache;desc=hit"#));
+}